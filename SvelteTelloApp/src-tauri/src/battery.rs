@@ -0,0 +1,318 @@
+// Battery policy
+// Warnings and the low-battery landing run here rather than in the UI, so the
+// drone still comes down if the webview stalls. The battery value comes from
+// DroneState, which the state stream and telemetry queries keep current.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Manager;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::command_channel::{self, CommandChannel};
+use crate::error::TelloError;
+use crate::flight_log::{self, FlightRecorder};
+use crate::tello_command::TelloCommand;
+use crate::{AppState, DroneState};
+
+const POLICY_FILE: &str = "battery_policy.json";
+const TICK: Duration = Duration::from_secs(1);
+// A warning fires again only once the battery has climbed this far back above it
+const HYSTERESIS: i32 = 3;
+// Wait this long before repeating a landing the drone did not acknowledge
+const LAND_RETRY: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatteryPolicy {
+    // Percentages that each raise one `battery-warning` event
+    pub warning_levels: Vec<i32>,
+    // At or below this the drone is landed (if auto_land) and takeoff refused
+    pub critical_level: i32,
+    pub auto_land: bool,
+}
+
+impl Default for BatteryPolicy {
+    fn default() -> Self {
+        Self {
+            warning_levels: vec![30, 15],
+            critical_level: 10,
+            auto_land: true,
+        }
+    }
+}
+
+impl BatteryPolicy {
+    pub fn validate(&self) -> Result<(), TelloError> {
+        if !(0..=100).contains(&self.critical_level) {
+            return Err(TelloError::InvalidArgument("Critical battery level must be between 0 and 100".to_string()));
+        }
+        if let Some(level) = self.warning_levels.iter().find(|level| !(1..=100).contains(*level)) {
+            return Err(TelloError::InvalidArgument(format!("Battery warning level {} must be between 1 and 100", level)));
+        }
+        Ok(())
+    }
+
+    // `battery` is 0 until the first reading arrives, which says nothing
+    pub fn allows_takeoff(&self, battery: i32) -> Result<(), TelloError> {
+        if battery > 0 && battery <= self.critical_level {
+            return Err(TelloError::BatteryTooLow { battery, critical_level: self.critical_level });
+        }
+        Ok(())
+    }
+
+    pub fn path(app_data_dir: &Path) -> PathBuf {
+        app_data_dir.join(POLICY_FILE)
+    }
+
+    // Missing or unreadable files fall back to the defaults
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                eprintln!("[Battery] ⚠️ Ignoring invalid {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), TelloError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| TelloError::Io(format!("Failed to create directory: {}", e)))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| TelloError::Io(format!("Failed to serialize battery policy: {}", e)))?;
+        std::fs::write(path, json).map_err(|e| TelloError::Io(format!("Failed to save battery policy: {}", e)))
+    }
+}
+
+// Payload of the `battery-warning` and `battery-critical` events
+#[derive(Serialize, Clone, Debug)]
+pub struct BatteryEvent {
+    pub battery: i32,
+    pub level: i32,
+    // What the backend did about it, if anything
+    pub action: Option<String>,
+    pub message: String,
+}
+
+// What one battery reading calls for
+#[derive(Debug, PartialEq)]
+enum Alert {
+    Warning { level: i32 },
+    Critical { land: bool },
+}
+
+// Which alerts have fired, so each fires once per crossing
+#[derive(Default)]
+struct AlertState {
+    warned: Vec<i32>,
+    critical: bool,
+    last_land: Option<Instant>,
+}
+
+impl AlertState {
+    fn check(&mut self, policy: &BatteryPolicy, battery: i32, flying: bool, now: Instant) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+
+        self.warned.retain(|&level| battery <= level + HYSTERESIS);
+        for &level in &policy.warning_levels {
+            if battery <= level && !self.warned.contains(&level) {
+                self.warned.push(level);
+                alerts.push(Alert::Warning { level });
+            }
+        }
+
+        if battery > policy.critical_level + HYSTERESIS {
+            self.critical = false;
+            return alerts;
+        }
+        if battery > policy.critical_level {
+            return alerts;
+        }
+
+        let land = policy.auto_land && flying && self.last_land.is_none_or(|at| now.duration_since(at) >= LAND_RETRY);
+        if land {
+            self.last_land = Some(now);
+        }
+        if !self.critical || land {
+            self.critical = true;
+            alerts.push(Alert::Critical { land });
+        }
+        alerts
+    }
+}
+
+pub struct BatteryMonitor {
+    task: JoinHandle<()>,
+}
+
+impl BatteryMonitor {
+    // `policy` is read on every tick, so changes apply without a restart
+    pub fn start(
+        policy: Arc<Mutex<BatteryPolicy>>,
+        channel: CommandChannel,
+        drone: Arc<Mutex<DroneState>>,
+        flight_log: Arc<Mutex<Option<FlightRecorder>>>,
+        app_handle: tauri::AppHandle,
+    ) -> Self {
+        let task = tokio::spawn(async move {
+            let mut alerts = AlertState::default();
+            let mut interval = tokio::time::interval(TICK);
+
+            loop {
+                interval.tick().await;
+
+                let policy = policy.lock().unwrap().clone();
+                let (battery, flying) = {
+                    let drone = drone.lock().unwrap();
+                    (drone.battery, drone.flying)
+                };
+                if battery <= 0 {
+                    continue;
+                }
+
+                for alert in alerts.check(&policy, battery, flying, Instant::now()) {
+                    match alert {
+                        Alert::Warning { level } => emit(&app_handle, "battery-warning", BatteryEvent {
+                            battery,
+                            level,
+                            action: None,
+                            message: format!("Battery at {}% (warning level {}%)", battery, level),
+                        }),
+                        Alert::Critical { land: land_now } => {
+                            emit(&app_handle, "battery-critical", BatteryEvent {
+                                battery,
+                                level: policy.critical_level,
+                                action: land_now.then(|| "land".to_string()),
+                                message: if land_now {
+                                    format!("Battery critical at {}%, landing automatically", battery)
+                                } else {
+                                    format!("Battery critical at {}%, land as soon as possible", battery)
+                                },
+                            });
+                            if land_now {
+                                // Nothing may send the next step or stick after the landing
+                                crate::take_over(&app_handle.state::<AppState>(), "Battery critical");
+                                land(&channel, &drone, &flight_log).await;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        Self { task }
+    }
+}
+
+impl Drop for BatteryMonitor {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn land(channel: &CommandChannel, drone: &Mutex<DroneState>, flight_log: &Mutex<Option<FlightRecorder>>) {
+    println!("[Battery] 🛬 Automatic landing: sending 'land'");
+
+    // The land would otherwise queue behind a manoeuvre still in flight
+    if let Err(e) = channel.send(&TelloCommand::Stop.to_string()).await {
+        eprintln!("[Battery] ⚠️ Failed to stop before landing: {}", e);
+    }
+
+    let command = TelloCommand::Land.to_string();
+    let timeout = command_channel::response_timeout(&command).unwrap_or(Duration::from_secs(20));
+    match channel.request(&command, timeout).await {
+        Ok(response) if response == "ok" => {
+            drone.lock().unwrap().flying = false;
+            flight_log::finish(flight_log, "auto_land");
+            println!("[Battery] ✅ Automatic landing complete");
+        }
+        Ok(response) => eprintln!("[Battery] ❌ Automatic landing rejected: {}", response),
+        Err(e) => eprintln!("[Battery] ❌ Automatic landing failed: {}", e),
+    }
+}
+
+fn emit(app_handle: &tauri::AppHandle, event: &str, payload: BatteryEvent) {
+    println!("[Battery] 🔋 {}: {}", event, payload.message);
+    if let Err(e) = app_handle.emit_all(event, payload) {
+        eprintln!("[Battery] ❌ Failed to emit {}: {}", event, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> BatteryPolicy {
+        BatteryPolicy { warning_levels: vec![30, 15], critical_level: 10, auto_land: true }
+    }
+
+    #[test]
+    fn warns_once_per_crossing() {
+        let mut alerts = AlertState::default();
+        let now = Instant::now();
+
+        assert_eq!(alerts.check(&policy(), 31, false, now), []);
+        assert_eq!(alerts.check(&policy(), 30, false, now), [Alert::Warning { level: 30 }]);
+        assert_eq!(alerts.check(&policy(), 29, false, now), []);
+
+        // Readings jitter; a climb inside the hysteresis band does not re-arm
+        assert_eq!(alerts.check(&policy(), 30 + HYSTERESIS, false, now), []);
+        assert_eq!(alerts.check(&policy(), 30, false, now), []);
+
+        // A fresh battery does
+        assert_eq!(alerts.check(&policy(), 30 + HYSTERESIS + 1, false, now), []);
+        assert_eq!(alerts.check(&policy(), 30, false, now), [Alert::Warning { level: 30 }]);
+
+        // Dropping past several levels at once fires each of them
+        let mut alerts = AlertState::default();
+        assert_eq!(alerts.check(&policy(), 14, false, now), [Alert::Warning { level: 30 }, Alert::Warning { level: 15 }]);
+    }
+
+    #[test]
+    fn lands_at_the_critical_level_and_retries() {
+        let policy = BatteryPolicy { warning_levels: Vec::new(), ..policy() };
+        let mut alerts = AlertState::default();
+        let now = Instant::now();
+
+        assert_eq!(alerts.check(&policy, 11, true, now), []);
+        assert_eq!(alerts.check(&policy, 10, true, now), [Alert::Critical { land: true }]);
+
+        // The landing is not repeated until LAND_RETRY has passed without it taking
+        assert_eq!(alerts.check(&policy, 9, true, now + Duration::from_secs(1)), []);
+        assert_eq!(alerts.check(&policy, 9, true, now + LAND_RETRY - Duration::from_millis(1)), []);
+        assert_eq!(alerts.check(&policy, 9, true, now + LAND_RETRY), [Alert::Critical { land: true }]);
+
+        // Once down, it stays a single reminder
+        assert_eq!(alerts.check(&policy, 9, false, now + LAND_RETRY * 3), []);
+    }
+
+    #[test]
+    fn only_reminds_without_auto_land_or_on_the_ground() {
+        let mut alerts = AlertState::default();
+        let manual = BatteryPolicy { warning_levels: Vec::new(), auto_land: false, ..policy() };
+        let now = Instant::now();
+
+        assert_eq!(alerts.check(&manual, 8, true, now), [Alert::Critical { land: false }]);
+        assert_eq!(alerts.check(&manual, 8, true, now + LAND_RETRY), []);
+
+        // Charged back above critical + hysteresis re-arms the reminder
+        assert_eq!(alerts.check(&manual, 10 + HYSTERESIS, true, now), []);
+        assert_eq!(alerts.check(&manual, 9, true, now), []);
+        assert_eq!(alerts.check(&manual, 10 + HYSTERESIS + 1, true, now), []);
+        assert_eq!(alerts.check(&manual, 9, true, now), [Alert::Critical { land: false }]);
+
+        let mut alerts = AlertState::default();
+        let landed = BatteryPolicy { warning_levels: Vec::new(), ..policy() };
+        assert_eq!(alerts.check(&landed, 5, false, now), [Alert::Critical { land: false }]);
+    }
+
+    #[test]
+    fn refuses_takeoff_at_critical() {
+        assert!(policy().allows_takeoff(0).is_ok());
+        assert!(policy().allows_takeoff(11).is_ok());
+        assert!(matches!(policy().allows_takeoff(10), Err(TelloError::BatteryTooLow { battery: 10, critical_level: 10 })));
+    }
+}
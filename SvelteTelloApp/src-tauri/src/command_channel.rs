@@ -0,0 +1,436 @@
+// Command channel for Tello
+// A single tokio task owns the UDP command socket (8889). Callers queue commands
+// and get back the reply that belongs to *their* command, so concurrent Tauri
+// commands can no longer read each other's "ok".
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+
+use crate::error::TelloError;
+use crate::flight_log::{self, FlightEvent, FlightRecorder};
+
+// Replies to read commands arrive quickly; motion commands only answer once the
+// manoeuvre has finished, which can take well over ten seconds.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(3);
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(5);
+const MOTION_TIMEOUT: Duration = Duration::from_secs(20);
+
+const MOTION_COMMANDS: &[&str] = &[
+    "takeoff", "land", "up", "down", "left", "right", "forward", "back",
+    "cw", "ccw", "flip", "go", "curve", "jump", "throwfly",
+];
+
+struct PendingRequest {
+    command: String,
+    timeout: Duration,
+    reply: oneshot::Sender<Result<String, TelloError>>,
+}
+
+// When the link last carried traffic in each direction
+#[derive(Clone, Copy, Debug)]
+pub struct LinkActivity {
+    pub last_sent: Instant,
+    // Any datagram from the drone counts, even a late reply
+    pub last_reply: Option<Instant>,
+}
+
+#[derive(Clone)]
+pub struct CommandChannel {
+    requests: mpsc::Sender<PendingRequest>,
+    immediate: mpsc::Sender<String>,
+    activity: Arc<Mutex<LinkActivity>>,
+}
+
+impl CommandChannel {
+    // Bind the local command socket and spawn the task that owns it.
+    // The task exits once every clone of the channel has been dropped.
+    // Everything sent and received goes to `flight_log` while a flight is on.
+    pub async fn bind(
+        local_addr: &str,
+        drone_addr: SocketAddr,
+        flight_log: Arc<Mutex<Option<FlightRecorder>>>,
+    ) -> Result<Self, TelloError> {
+        let socket = UdpSocket::bind(local_addr).await.map_err(|e| TelloError::BindFailed {
+            port: local_addr.parse::<SocketAddr>().map_or(0, |addr| addr.port()),
+            reason: e.to_string(),
+        })?;
+
+        let (requests_tx, requests_rx) = mpsc::channel(32);
+        let (immediate_tx, immediate_rx) = mpsc::channel(64);
+
+        let activity = Arc::new(Mutex::new(LinkActivity {
+            last_sent: Instant::now(),
+            last_reply: None,
+        }));
+
+        tokio::spawn(run(socket, drone_addr, requests_rx, immediate_rx, Arc::clone(&activity), flight_log));
+
+        Ok(Self {
+            requests: requests_tx,
+            immediate: immediate_tx,
+            activity,
+        })
+    }
+
+    pub fn activity(&self) -> LinkActivity {
+        *self.activity.lock().unwrap()
+    }
+
+    // Queue a command and wait for the drone's reply to it.
+    pub async fn request(&self, command: &str, timeout: Duration) -> Result<String, TelloError> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+
+        self.requests
+            .send(PendingRequest {
+                command: command.to_string(),
+                timeout,
+                reply: reply_tx,
+            })
+            .await
+            .map_err(|_| TelloError::NotConnected)?;
+
+        // The task only goes away once the channel is dropped
+        reply_rx.await.map_err(|_| TelloError::NotConnected)?
+    }
+
+    // Send a command without waiting for a reply. These bypass the request
+    // queue, so an `rc`, `stop` or `emergency` is never stuck behind a long
+    // manoeuvre.
+    pub async fn send(&self, command: &str) -> Result<(), TelloError> {
+        self.immediate
+            .send(command.to_string())
+            .await
+            .map_err(|_| TelloError::NotConnected)
+    }
+}
+
+// How long to wait for the reply to `command`, or `None` if the drone never
+// answers it (rc) or it must not wait in the queue (stop, emergency): both are
+// meant to interrupt the manoeuvre the queue is waiting on.
+pub fn response_timeout(command: &str) -> Option<Duration> {
+    let keyword = command.split_whitespace().next().unwrap_or("");
+
+    if matches!(keyword, "rc" | "stop" | "emergency") {
+        None
+    } else if keyword.ends_with('?') {
+        Some(QUERY_TIMEOUT)
+    } else if MOTION_COMMANDS.contains(&keyword) {
+        Some(MOTION_TIMEOUT)
+    } else {
+        Some(CONTROL_TIMEOUT)
+    }
+}
+
+// The Tello protocol carries no request id, so a reply that arrives after its
+// command timed out could be mistaken for the next command's reply. Read
+// commands never answer "ok", and control commands never answer with a bare
+// number, which catches the stale replies we actually see.
+fn reply_matches(command: &str, response: &str) -> bool {
+    if command.trim_end().ends_with('?') {
+        !response.eq_ignore_ascii_case("ok")
+    } else {
+        response.parse::<f64>().is_err()
+    }
+}
+
+async fn run(
+    socket: UdpSocket,
+    drone_addr: SocketAddr,
+    mut requests: mpsc::Receiver<PendingRequest>,
+    mut immediate: mpsc::Receiver<String>,
+    activity: Arc<Mutex<LinkActivity>>,
+    flight_log: Arc<Mutex<Option<FlightRecorder>>>,
+) {
+    println!("[CommandChannel] 🚀 Command task started for {}", drone_addr);
+
+    let mut buf = [0u8; 1024];
+
+    loop {
+        let request = tokio::select! {
+            request = requests.recv() => match request {
+                Some(request) => request,
+                None => break,
+            },
+            command = immediate.recv() => match command {
+                Some(command) => {
+                    send_immediate(&socket, drone_addr, &command, &activity, &flight_log).await;
+                    continue;
+                }
+                None => break,
+            },
+            received = socket.recv_from(&mut buf) => {
+                // Nobody is waiting, so this is a late reply to a timed-out
+                // command; anything from another host is not the drone at all
+                match received {
+                    Ok((size, addr)) if addr.ip() == drone_addr.ip() => {
+                        activity.lock().unwrap().last_reply = Some(Instant::now());
+                        let response = String::from_utf8_lossy(&buf[..size]).trim().to_string();
+                        println!("[CommandChannel] 🗑️ Discarding unsolicited reply '{}'", response);
+                        flight_log::record(&flight_log, FlightEvent::Reply { command: None, reply: response });
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+        };
+
+        flight_log::record(&flight_log, FlightEvent::Command { command: request.command.clone() });
+
+        if let Err(e) = socket.send_to(request.command.as_bytes(), drone_addr).await {
+            let error = TelloError::Network(format!("Send failed: {}", e));
+            flight_log::record(&flight_log, FlightEvent::Error { command: request.command.clone(), error: error.to_string() });
+            let _ = request.reply.send(Err(error));
+            continue;
+        }
+        activity.lock().unwrap().last_sent = Instant::now();
+
+        let deadline = Instant::now() + request.timeout;
+
+        let result = loop {
+            tokio::select! {
+                _ = tokio::time::sleep_until(deadline) => {
+                    break Err(TelloError::Timeout {
+                        command: request.command.clone(),
+                        seconds: request.timeout.as_secs(),
+                    });
+                }
+                command = immediate.recv() => match command {
+                    Some(command) => send_immediate(&socket, drone_addr, &command, &activity, &flight_log).await,
+                    None => break Err(TelloError::NotConnected),
+                },
+                received = socket.recv_from(&mut buf) => match received {
+                    Ok((size, addr)) => {
+                        if addr.ip() != drone_addr.ip() {
+                            continue;
+                        }
+                        activity.lock().unwrap().last_reply = Some(Instant::now());
+
+                        let response = String::from_utf8_lossy(&buf[..size]).trim().to_string();
+
+                        if reply_matches(&request.command, &response) {
+                            break Ok(response);
+                        }
+
+                        println!(
+                            "[CommandChannel] 🗑️ Discarding stale reply '{}' while waiting for '{}'",
+                            response, request.command
+                        );
+                        flight_log::record(&flight_log, FlightEvent::Reply { command: None, reply: response });
+                    }
+                    Err(e) => break Err(TelloError::Network(format!("Receive failed: {}", e))),
+                }
+            }
+        };
+
+        let event = match &result {
+            Ok(reply) => FlightEvent::Reply { command: Some(request.command.clone()), reply: reply.clone() },
+            Err(e) => FlightEvent::Error { command: request.command.clone(), error: e.to_string() },
+        };
+        flight_log::record(&flight_log, event);
+
+        // The caller may have given up already; nothing to do in that case
+        let _ = request.reply.send(result);
+    }
+
+    println!("[CommandChannel] 🛑 Command task stopped");
+}
+
+async fn send_immediate(
+    socket: &UdpSocket,
+    drone_addr: SocketAddr,
+    command: &str,
+    activity: &Mutex<LinkActivity>,
+    flight_log: &Mutex<Option<FlightRecorder>>,
+) {
+    flight_log::record(flight_log, FlightEvent::Command { command: command.to_string() });
+    match socket.send_to(command.as_bytes(), drone_addr).await {
+        Ok(_) => activity.lock().unwrap().last_sent = Instant::now(),
+        Err(e) => {
+            eprintln!("[CommandChannel] ❌ Failed to send '{}': {}", command, e);
+            flight_log::record(flight_log, FlightEvent::Error { command: command.to_string(), error: e.to_string() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::{SimulatorConfig, TelloSimulator};
+
+    // A stand-in drone on loopback that answers only when told to
+    async fn drone() -> (UdpSocket, CommandChannel) {
+        let drone = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let channel = CommandChannel::bind("127.0.0.1:0", drone.local_addr().unwrap(), Arc::new(Mutex::new(None))).await.unwrap();
+        (drone, channel)
+    }
+
+    async fn receive(drone: &UdpSocket) -> (String, SocketAddr) {
+        let mut buf = [0u8; 1024];
+        let (size, from) = tokio::time::timeout(Duration::from_secs(1), drone.recv_from(&mut buf))
+            .await
+            .expect("nothing was sent")
+            .unwrap();
+        (String::from_utf8_lossy(&buf[..size]).to_string(), from)
+    }
+
+    async fn nothing_sent(drone: &UdpSocket) -> bool {
+        let mut buf = [0u8; 1024];
+        tokio::time::timeout(Duration::from_millis(150), drone.recv_from(&mut buf)).await.is_err()
+    }
+
+    fn spawn_request(channel: &CommandChannel, command: &'static str, timeout: Duration) -> tokio::task::JoinHandle<Result<String, TelloError>> {
+        let channel = channel.clone();
+        tokio::spawn(async move { channel.request(command, timeout).await })
+    }
+
+    #[test]
+    fn picks_timeouts_by_command() {
+        assert_eq!(response_timeout("battery?"), Some(QUERY_TIMEOUT));
+        assert_eq!(response_timeout("forward 100"), Some(MOTION_TIMEOUT));
+        assert_eq!(response_timeout("speed 50"), Some(CONTROL_TIMEOUT));
+        for command in ["rc 0 0 0 0", "stop", "emergency"] {
+            assert_eq!(response_timeout(command), None, "{}", command);
+        }
+    }
+
+    #[tokio::test]
+    async fn serves_requests_one_at_a_time_in_order() {
+        let (drone, channel) = drone().await;
+        let first = spawn_request(&channel, "forward 20", MOTION_TIMEOUT);
+        let (command, client) = receive(&drone).await;
+        assert_eq!(command, "forward 20");
+
+        // The second waits until the first has its reply
+        let second = spawn_request(&channel, "cw 90", MOTION_TIMEOUT);
+        assert!(nothing_sent(&drone).await);
+
+        drone.send_to(b"ok", client).await.unwrap();
+        assert_eq!(first.await.unwrap().unwrap(), "ok");
+        assert_eq!(receive(&drone).await.0, "cw 90");
+        drone.send_to(b"error Not joystick", client).await.unwrap();
+        assert_eq!(second.await.unwrap().unwrap(), "error Not joystick");
+    }
+
+    #[tokio::test]
+    async fn skips_replies_that_belong_to_another_command() {
+        let (drone, channel) = drone().await;
+
+        // A stray "ok" is not a battery level
+        let query = spawn_request(&channel, "battery?", QUERY_TIMEOUT);
+        let (_, client) = receive(&drone).await;
+        drone.send_to(b"ok", client).await.unwrap();
+        drone.send_to(b"87", client).await.unwrap();
+        assert_eq!(query.await.unwrap().unwrap(), "87");
+
+        // ...and a stray reading is not the answer to a control command
+        let control = spawn_request(&channel, "speed 50", CONTROL_TIMEOUT);
+        receive(&drone).await;
+        drone.send_to(b"86", client).await.unwrap();
+        drone.send_to(b"ok", client).await.unwrap();
+        assert_eq!(control.await.unwrap().unwrap(), "ok");
+        assert!(channel.activity().last_reply.is_some());
+    }
+
+    #[tokio::test]
+    async fn drops_late_replies_after_a_timeout() {
+        let (drone, channel) = drone().await;
+
+        let error = channel.request("battery?", Duration::from_millis(200)).await.unwrap_err();
+        assert!(matches!(error, TelloError::Timeout { ref command, .. } if command == "battery?"), "{}", error);
+        let (_, client) = receive(&drone).await;
+
+        // Arrives with nobody waiting: discarded, not handed to the next request
+        drone.send_to(b"64", client).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let next = spawn_request(&channel, "speed?", QUERY_TIMEOUT);
+        assert_eq!(receive(&drone).await.0, "speed?");
+        drone.send_to(b"100.0", client).await.unwrap();
+        assert_eq!(next.await.unwrap().unwrap(), "100.0");
+
+        // A late "ok" that turns up while a read is pending is skipped as well
+        let error = channel.request("command", Duration::from_millis(200)).await.unwrap_err();
+        assert!(matches!(error, TelloError::Timeout { .. }), "{}", error);
+        receive(&drone).await;
+        let read = spawn_request(&channel, "battery?", QUERY_TIMEOUT);
+        receive(&drone).await;
+        drone.send_to(b"ok", client).await.unwrap();
+        drone.send_to(b"63", client).await.unwrap();
+        assert_eq!(read.await.unwrap().unwrap(), "63");
+    }
+
+    #[tokio::test]
+    async fn rc_and_emergency_skip_a_pending_request() {
+        let (drone, channel) = drone().await;
+        let manoeuvre = spawn_request(&channel, "forward 500", MOTION_TIMEOUT);
+        let (_, client) = receive(&drone).await;
+
+        channel.send("rc 0 20 0 0").await.unwrap();
+        assert_eq!(receive(&drone).await.0, "rc 0 20 0 0");
+        channel.send("emergency").await.unwrap();
+        assert_eq!(receive(&drone).await.0, "emergency");
+        assert!(!manoeuvre.is_finished());
+
+        drone.send_to(b"ok", client).await.unwrap();
+        assert_eq!(manoeuvre.await.unwrap().unwrap(), "ok");
+
+        // With nothing pending they go out straight away too
+        channel.send("rc 0 0 0 0").await.unwrap();
+        assert_eq!(receive(&drone).await.0, "rc 0 0 0 0");
+    }
+
+    #[tokio::test]
+    async fn ignores_datagrams_from_other_hosts() {
+        let (drone, channel) = drone().await;
+        let stranger = UdpSocket::bind("127.0.0.2:0").await.unwrap();
+
+        let query = spawn_request(&channel, "battery?", QUERY_TIMEOUT);
+        let (_, client) = receive(&drone).await;
+        stranger.send_to(b"5", client).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(channel.activity().last_reply.is_none());
+        drone.send_to(b"87", client).await.unwrap();
+        assert_eq!(query.await.unwrap().unwrap(), "87");
+
+        // Nor does one arriving between requests count as the drone being alive
+        let replied = channel.activity().last_reply;
+        stranger.send_to(b"ok", client).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(channel.activity().last_reply, replied);
+    }
+
+    #[tokio::test]
+    async fn stop_interrupts_a_queued_manoeuvre() {
+        let simulator = TelloSimulator::start(SimulatorConfig {
+            command_addr: "127.0.0.1:0".parse().unwrap(),
+            state_port: 0,
+            video_port: 0,
+            ..SimulatorConfig::default()
+        })
+        .await
+        .unwrap();
+        let channel = CommandChannel::bind("127.0.0.1:0", simulator.command_addr(), Arc::new(Mutex::new(None))).await.unwrap();
+
+        assert_eq!(channel.request("command", CONTROL_TIMEOUT).await.unwrap(), "ok");
+        assert_eq!(channel.request("takeoff", MOTION_TIMEOUT).await.unwrap(), "ok");
+
+        // 500 cm at the default 100 cm/s takes five seconds to fly
+        let started = Instant::now();
+        let manoeuvre = tokio::spawn({
+            let channel = channel.clone();
+            async move { channel.request("forward 500", MOTION_TIMEOUT).await }
+        });
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        channel.send("stop").await.unwrap();
+
+        // The drone answers both at once; either reply ends the wait
+        let reply = manoeuvre.await.unwrap().unwrap();
+        assert!(reply == "ok" || reply == "error Stopped", "{}", reply);
+        assert!(started.elapsed() < Duration::from_secs(2), "stop waited {:?}", started.elapsed());
+    }
+}
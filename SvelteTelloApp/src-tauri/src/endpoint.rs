@@ -0,0 +1,93 @@
+// Drone network endpoint
+// Where the drone lives and which ports we use to talk to it. Defaults match a
+// Tello in AP mode; Tello EDU in station mode gets its address from the router.
+
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+
+use crate::error::TelloError;
+
+const ENDPOINT_FILE: &str = "drone_endpoint.json";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DroneEndpoint {
+    // Drone address
+    pub ip: IpAddr,
+    // Port the drone accepts SDK commands on
+    pub command_port: u16,
+    // Local port our command socket binds to (0 = any free port)
+    pub local_command_port: u16,
+    // Local ports the drone pushes state packets and video to
+    pub state_port: u16,
+    pub video_port: u16,
+}
+
+impl Default for DroneEndpoint {
+    fn default() -> Self {
+        Self {
+            ip: IpAddr::V4(Ipv4Addr::new(192, 168, 10, 1)),
+            command_port: 8889,
+            local_command_port: 8889,
+            state_port: 8890,
+            video_port: 11111,
+        }
+    }
+}
+
+impl DroneEndpoint {
+    pub fn command_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.ip, self.command_port)
+    }
+
+    // The drone's video port, used for the kick-start packet
+    pub fn video_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.ip, self.video_port)
+    }
+
+    pub fn local_command_bind(&self) -> String {
+        format!("0.0.0.0:{}", self.local_command_port)
+    }
+
+    pub fn local_state_bind(&self) -> String {
+        format!("0.0.0.0:{}", self.state_port)
+    }
+
+    pub fn local_video_bind(&self) -> String {
+        format!("0.0.0.0:{}", self.video_port)
+    }
+
+    pub fn validate(&self) -> Result<(), TelloError> {
+        if self.command_port == 0 || self.state_port == 0 || self.video_port == 0 {
+            return Err(TelloError::InvalidArgument("Command, state and video ports must be non-zero".to_string()));
+        }
+        if self.state_port == self.video_port {
+            return Err(TelloError::InvalidArgument("State and video ports must differ".to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn path(app_data_dir: &Path) -> PathBuf {
+        app_data_dir.join(ENDPOINT_FILE)
+    }
+
+    // Missing or unreadable files fall back to the defaults
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                eprintln!("[Endpoint] ⚠️ Ignoring invalid {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), TelloError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| TelloError::Io(format!("Failed to create directory: {}", e)))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| TelloError::Io(format!("Failed to serialize endpoint: {}", e)))?;
+        std::fs::write(path, json).map_err(|e| TelloError::Io(format!("Failed to save endpoint: {}", e)))
+    }
+}
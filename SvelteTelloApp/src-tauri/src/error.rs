@@ -0,0 +1,139 @@
+// Backend errors
+// Every Tauri command fails with a TelloError. It reaches the frontend as
+// {"kind": "bind_failed", "message": "..."}: the UI branches on `kind` to offer
+// the right recovery and shows `message` as is.
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum TelloError {
+    #[error("Failed to bind UDP port {port}: {reason}. Close other Tello apps and try again.")]
+    BindFailed { port: u16, reason: String },
+
+    #[error("Not connected to drone")]
+    NotConnected,
+
+    #[error("No reply to '{command}' within {seconds}s")]
+    Timeout { command: String, seconds: u64 },
+
+    #[error("No response from drone after {attempts} attempts. Make sure:\n1. Drone is powered on\n2. You're connected to TELLO-XXXXXX WiFi\n3. No other apps are using the drone")]
+    NoResponse { attempts: u32 },
+
+    // The drone answered, but with "error" or something else than "ok"
+    #[error("Drone rejected '{command}': {reason}")]
+    DroneError { command: String, reason: String },
+
+    #[error("Drone does not support '{0}' (firmware too old?)")]
+    Unsupported(String),
+
+    #[error("Unexpected reply to '{command}': '{reply}'")]
+    UnexpectedReply { command: String, reply: String },
+
+    // Carries the network diagnostic's advice
+    #[error("{0}")]
+    NotOnTelloNetwork(String),
+
+    #[error("Video port {port} is in use: {reason}. Close other apps showing the Tello video and try again.")]
+    VideoPortBusy { port: u16, reason: String },
+
+    #[error("Battery at {battery}%, takeoff is blocked at or below {critical_level}%")]
+    BatteryTooLow { battery: i32, critical_level: i32 },
+
+    #[error("{0}")]
+    InvalidArgument(String),
+
+    // A file the request names is gone (a deleted log or picture)
+    #[error("{0}")]
+    NotFound(String),
+
+    // The request makes no sense right now ("Already recording")
+    #[error("{0}")]
+    InvalidState(String),
+
+    // Socket send/receive failures after the link was set up
+    #[error("{0}")]
+    Network(String),
+
+    // Recording, snapshots and the gallery
+    #[error("{0}")]
+    Media(String),
+
+    // Settings files and folders
+    #[error("{0}")]
+    Io(String),
+
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl TelloError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::BindFailed { .. } => "bind_failed",
+            Self::NotConnected => "not_connected",
+            Self::Timeout { .. } => "timeout",
+            Self::NoResponse { .. } => "no_response",
+            Self::DroneError { .. } => "drone_error",
+            Self::Unsupported(_) => "unsupported",
+            Self::UnexpectedReply { .. } => "unexpected_reply",
+            Self::NotOnTelloNetwork(_) => "not_on_tello_network",
+            Self::VideoPortBusy { .. } => "video_port_busy",
+            Self::BatteryTooLow { .. } => "battery_too_low",
+            Self::InvalidArgument(_) => "invalid_argument",
+            Self::NotFound(_) => "not_found",
+            Self::InvalidState(_) => "invalid_state",
+            Self::Network(_) => "network",
+            Self::Media(_) => "media",
+            Self::Io(_) => "io",
+            Self::Internal(_) => "internal",
+        }
+    }
+
+    // A file operation that failed; a missing file is NotFound, anything
+    // else (permissions, a full disk) is Io
+    pub fn io(context: impl std::fmt::Display, error: std::io::Error) -> Self {
+        let message = format!("{}: {}", context, error);
+        match error.kind() {
+            std::io::ErrorKind::NotFound => Self::NotFound(message),
+            _ => Self::Io(message),
+        }
+    }
+}
+
+impl Serialize for TelloError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("TelloError", 2)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_as_kind_and_message() {
+        let error = TelloError::BindFailed { port: 8889, reason: "Address in use".to_string() };
+
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            serde_json::json!({
+                "kind": "bind_failed",
+                "message": "Failed to bind UDP port 8889: Address in use. Close other Tello apps and try again.",
+            })
+        );
+        assert_eq!(serde_json::to_value(TelloError::NotConnected).unwrap()["kind"], "not_connected");
+    }
+
+    #[test]
+    fn tells_missing_files_from_other_io_failures() {
+        let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+        let error = TelloError::io("Failed to open flight_1.jsonl", missing);
+        assert!(matches!(error, TelloError::NotFound(ref message) if message.starts_with("Failed to open flight_1.jsonl: ")), "{:?}", error);
+
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert_eq!(TelloError::io("Failed to save", denied).kind(), "io");
+    }
+}
@@ -0,0 +1,388 @@
+// Flight log export
+// Turns a recorded flight into files other tools read: a CSV with one row per
+// state sample, and a 3D track (KML or GPX) dead-reckoned from vgx/vgy and h
+// in the takeoff frame, then placed on the map at a user-supplied origin.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::error::TelloError;
+use crate::flight_log::{FlightEvent, FlightLogEntry};
+use crate::state_stream::TelloStatePacket;
+
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+// Same cap as the live integration, so a gap in the log isn't a jump in the track
+const MAX_STEP_S: f64 = 0.5;
+
+const CSV_HEADER: &str = "timestamp,elapsed_s,pitch,roll,yaw,vgx,vgy,vgz,templ,temph,tof,h,bat,baro,time,agx,agy,agz,mid,x,y,z,mpry,x_cm,y_cm,z_cm";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackFormat {
+    Kml,
+    Gpx,
+}
+
+impl TrackFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Kml => "kml",
+            Self::Gpx => "gpx",
+        }
+    }
+}
+
+// Where the drone took off, and which way it was facing
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct GeoOrigin {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64, // m above sea level
+    pub heading: f64,  // degrees clockwise from north
+}
+
+impl GeoOrigin {
+    pub fn validate(&self) -> Result<(), TelloError> {
+        // At the poles a metre east is no longitude at all (locate divides by cos)
+        if self.latitude.is_nan() || self.latitude.abs() >= 90.0 {
+            return Err(TelloError::InvalidArgument(format!("Latitude {} must be between -90 and 90, poles excluded", self.latitude)));
+        }
+        if !(-180.0..=180.0).contains(&self.longitude) {
+            return Err(TelloError::InvalidArgument(format!("Longitude {} must be between -180 and 180", self.longitude)));
+        }
+        if !self.altitude.is_finite() || !self.heading.is_finite() {
+            return Err(TelloError::InvalidArgument("Altitude and heading must be numbers".to_string()));
+        }
+        Ok(())
+    }
+
+    // Local takeoff frame (x forward, y right, z up, cm) to latitude/longitude/altitude
+    fn locate(&self, point: &TrackPoint) -> (f64, f64, f64) {
+        let heading = self.heading.to_radians();
+        let (x, y) = (point.x / 100.0, point.y / 100.0);
+        let north = x * heading.cos() - y * heading.sin();
+        let east = x * heading.sin() + y * heading.cos();
+
+        let latitude = self.latitude + (north / EARTH_RADIUS_M).to_degrees();
+        let longitude = self.longitude + (east / (EARTH_RADIUS_M * self.latitude.to_radians().cos())).to_degrees();
+        (latitude, longitude, self.altitude + point.z / 100.0)
+    }
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct FlightStats {
+    pub samples: usize,
+    pub duration: f64,   // seconds, first to last state sample
+    pub max_height: i32, // cm
+    pub battery_used: i32, // percent
+    pub distance: f64,   // cm flown, 3D
+}
+
+// Returned by export_flight_log
+#[derive(Serialize, Clone, Debug)]
+pub struct FlightExport {
+    pub csv: String,
+    pub track: String,
+    pub stats: FlightStats,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct TrackPoint {
+    timestamp: u64,
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+// Writes <name>.csv and <name>.kml/.gpx into `directory`
+pub fn export(
+    name: &str,
+    entries: &[FlightLogEntry],
+    origin: &GeoOrigin,
+    format: TrackFormat,
+    directory: &Path,
+) -> Result<FlightExport, TelloError> {
+    let samples: Vec<(u64, &TelloStatePacket)> = entries
+        .iter()
+        .filter_map(|entry| match &entry.event {
+            FlightEvent::State(packet) => Some((entry.timestamp, packet)),
+            _ => None,
+        })
+        .collect();
+    if samples.is_empty() {
+        return Err(TelloError::InvalidArgument(format!("{} has no state samples to export", name)));
+    }
+
+    let track = integrate(&samples);
+    let stats = stats(&samples, &track);
+
+    std::fs::create_dir_all(directory).map_err(|e| TelloError::io("Failed to create directory", e))?;
+    let csv_path = directory.join(format!("{}.csv", name));
+    let track_path = directory.join(format!("{}.{}", name, format.extension()));
+
+    let track_file = match format {
+        TrackFormat::Kml => kml(name, origin, &track, &stats),
+        TrackFormat::Gpx => gpx(name, origin, &track),
+    };
+    write(&csv_path, &csv(&samples, &track))?;
+    write(&track_path, &track_file)?;
+
+    println!(
+        "[FlightExport] 📤 Exported {} ({} samples, {:.1}s, max {}cm, {:.0}cm flown)",
+        name, stats.samples, stats.duration, stats.max_height, stats.distance
+    );
+
+    Ok(FlightExport {
+        csv: csv_path.to_string_lossy().to_string(),
+        track: track_path.to_string_lossy().to_string(),
+        stats,
+    })
+}
+
+fn write(path: &Path, contents: &str) -> Result<(), TelloError> {
+    std::fs::write(path, contents).map_err(|e| TelloError::io(format!("Failed to write {}", path.display()), e))
+}
+
+// Velocities are in dm/s; height comes straight from `h`
+fn integrate(samples: &[(u64, &TelloStatePacket)]) -> Vec<TrackPoint> {
+    let mut track = Vec::with_capacity(samples.len());
+    let (mut x, mut y) = (0.0, 0.0);
+    let mut previous: Option<u64> = None;
+
+    for &(timestamp, packet) in samples {
+        let dt = previous.map_or(0.0, |at| (timestamp.saturating_sub(at) as f64 / 1000.0).min(MAX_STEP_S));
+        previous = Some(timestamp);
+        x += packet.vgx as f64 * 10.0 * dt;
+        y += packet.vgy as f64 * 10.0 * dt;
+        track.push(TrackPoint { timestamp, x, y, z: packet.h as f64 });
+    }
+    track
+}
+
+fn stats(samples: &[(u64, &TelloStatePacket)], track: &[TrackPoint]) -> FlightStats {
+    let (first, last) = (samples[0], samples[samples.len() - 1]);
+    let distance = track
+        .windows(2)
+        .map(|pair| {
+            let (a, b) = (&pair[0], &pair[1]);
+            ((b.x - a.x).powi(2) + (b.y - a.y).powi(2) + (b.z - a.z).powi(2)).sqrt()
+        })
+        .sum();
+
+    FlightStats {
+        samples: samples.len(),
+        duration: last.0.saturating_sub(first.0) as f64 / 1000.0,
+        max_height: samples.iter().map(|(_, packet)| packet.h).max().unwrap_or(0),
+        battery_used: (first.1.bat - last.1.bat).max(0),
+        distance,
+    }
+}
+
+fn csv(samples: &[(u64, &TelloStatePacket)], track: &[TrackPoint]) -> String {
+    let start = samples[0].0;
+    let mut out = String::from(CSV_HEADER);
+    out.push('\n');
+    for (&(timestamp, p), point) in samples.iter().zip(track) {
+        // Empty while no pad is in sight; mpry is quoted, it holds commas
+        let pad = p.pad.map_or_else(
+            || ",,,,".to_string(),
+            |pad| format!("{},{},{},{},\"{},{},{}\"", pad.id, pad.x, pad.y, pad.z, pad.pitch, pad.roll, pad.yaw),
+        );
+        let _ = writeln!(
+            out,
+            "{},{:.3},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{:.1},{:.1},{:.1}",
+            timestamp,
+            timestamp.saturating_sub(start) as f64 / 1000.0,
+            p.pitch, p.roll, p.yaw, p.vgx, p.vgy, p.vgz, p.templ, p.temph, p.tof, p.h, p.bat,
+            p.baro, p.time, p.agx, p.agy, p.agz,
+            pad,
+            point.x, point.y, point.z,
+        );
+    }
+    out
+}
+
+fn kml(name: &str, origin: &GeoOrigin, track: &[TrackPoint], stats: &FlightStats) -> String {
+    let mut coordinates = String::new();
+    for point in track {
+        let (latitude, longitude, altitude) = origin.locate(point);
+        let _ = writeln!(coordinates, "          {:.7},{:.7},{:.2}", longitude, latitude, altitude);
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<kml xmlns="http://www.opengis.net/kml/2.2">
+  <Document>
+    <name>{name}</name>
+    <Placemark>
+      <name>{name}</name>
+      <description>Duration {duration:.1}s, max height {max_height}cm, battery used {battery_used}%, distance {distance:.0}cm</description>
+      <LineString>
+        <altitudeMode>absolute</altitudeMode>
+        <coordinates>
+{coordinates}        </coordinates>
+      </LineString>
+    </Placemark>
+  </Document>
+</kml>
+"#,
+        name = escape(name),
+        duration = stats.duration,
+        max_height = stats.max_height,
+        battery_used = stats.battery_used,
+        distance = stats.distance,
+    )
+}
+
+fn gpx(name: &str, origin: &GeoOrigin, track: &[TrackPoint]) -> String {
+    let mut points = String::new();
+    for point in track {
+        let (latitude, longitude, altitude) = origin.locate(point);
+        let _ = writeln!(
+            points,
+            r#"      <trkpt lat="{:.7}" lon="{:.7}"><ele>{:.2}</ele><time>{}</time></trkpt>"#,
+            latitude, longitude, altitude, utc_timestamp(point.timestamp)
+        );
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="Tello Control" xmlns="http://www.topografix.com/GPX/1/1">
+  <trk>
+    <name>{}</name>
+    <trkseg>
+{}    </trkseg>
+  </trk>
+</gpx>
+"#,
+        escape(name),
+        points
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+// ISO 8601 UTC from ms since the Unix epoch (days-to-civil, proleptic Gregorian)
+fn utc_timestamp(ms: u64) -> String {
+    let seconds = ms / 1000;
+    let days = (seconds / 86_400) as i64;
+    let time = seconds % 86_400;
+
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year, month, day, time / 3600, time % 3600 / 60, time % 60, ms % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_stream::PadPose;
+
+    fn sample(timestamp: u64, vgx: i32, h: i32, bat: i32) -> FlightLogEntry {
+        FlightLogEntry {
+            timestamp,
+            event: FlightEvent::State(TelloStatePacket { vgx, h, bat, ..Default::default() }),
+        }
+    }
+
+    fn samples(entries: &[FlightLogEntry]) -> Vec<(u64, &TelloStatePacket)> {
+        entries
+            .iter()
+            .filter_map(|entry| match &entry.event {
+                FlightEvent::State(packet) => Some((entry.timestamp, packet)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn integrates_track_and_stats() {
+        // 10 dm/s forward for a second, climbing to 100cm
+        let entries = [sample(1000, 10, 0, 90), sample(1500, 10, 50, 90), sample(2000, 10, 100, 89)];
+        let samples = samples(&entries);
+        let track = integrate(&samples);
+
+        assert_eq!(track.last().unwrap(), &TrackPoint { timestamp: 2000, x: 100.0, y: 0.0, z: 100.0 });
+
+        let stats = stats(&samples, &track);
+        assert_eq!(stats.samples, 3);
+        assert_eq!(stats.duration, 1.0);
+        assert_eq!(stats.max_height, 100);
+        assert_eq!(stats.battery_used, 1);
+        assert!((stats.distance - 2.0 * 50.0 * 2f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn places_track_relative_to_origin_heading() {
+        let origin = GeoOrigin { latitude: 0.0, longitude: 0.0, altitude: 10.0, heading: 90.0 };
+        // 100m forward while facing east
+        let (latitude, longitude, altitude) = origin.locate(&TrackPoint { timestamp: 0, x: 10_000.0, y: 0.0, z: 250.0 });
+
+        assert!(latitude.abs() < 1e-9);
+        assert!((longitude - (100.0 / EARTH_RADIUS_M).to_degrees()).abs() < 1e-9);
+        assert_eq!(altitude, 12.5);
+    }
+
+    #[test]
+    fn rejects_origins_at_or_past_the_poles() {
+        let origin = GeoOrigin { latitude: 0.0, longitude: 0.0, altitude: 10.0, heading: 0.0 };
+        assert!(origin.validate().is_ok());
+        assert!(GeoOrigin { latitude: 89.9, ..origin }.validate().is_ok());
+
+        for latitude in [90.0, -90.0, 91.0, f64::NAN] {
+            let error = GeoOrigin { latitude, ..origin }.validate().unwrap_err();
+            assert!(matches!(error, TelloError::InvalidArgument(_)), "{}: {}", latitude, error);
+        }
+        assert!(GeoOrigin { longitude: 180.5, ..origin }.validate().is_err());
+        assert!(GeoOrigin { heading: f64::INFINITY, ..origin }.validate().is_err());
+    }
+
+    #[test]
+    fn refuses_a_log_without_state_samples() {
+        let origin = GeoOrigin { latitude: 0.0, longitude: 0.0, altitude: 0.0, heading: 0.0 };
+        let entries = [FlightLogEntry { timestamp: 1, event: crate::flight_log::mode_event("takeoff", None) }];
+        let directory = std::env::temp_dir().join("tello_export_test_never_created");
+
+        let error = export("flight_1", &entries, &origin, TrackFormat::Kml, &directory).unwrap_err();
+        assert_eq!(error, TelloError::InvalidArgument("flight_1 has no state samples to export".to_string()));
+        assert!(!directory.exists());
+    }
+
+    #[test]
+    fn writes_pad_columns_only_with_a_pad_in_sight() {
+        let mut entries = vec![sample(1000, 0, 80, 90), sample(1100, 0, 80, 90)];
+        if let FlightEvent::State(packet) = &mut entries[1].event {
+            packet.pad = Some(PadPose { id: 3, x: -12, y: 40, z: 78, pitch: 1, roll: -2, yaw: 45 });
+        }
+        let samples = samples(&entries);
+        let csv = csv(&samples, &integrate(&samples));
+        let lines: Vec<&str> = csv.lines().collect();
+        let columns = CSV_HEADER.split(',').count();
+
+        assert_eq!(lines[1], "1000,0.000,0,0,0,0,0,0,0,0,0,80,90,0,0,0,0,0,,,,,,0.0,0.0,80.0");
+        assert_eq!(lines[2], "1100,0.100,0,0,0,0,0,0,0,0,0,80,90,0,0,0,0,0,3,-12,40,78,\"1,-2,45\",0.0,0.0,80.0");
+        assert_eq!(lines[1].split(',').count(), columns);
+        // The quoted mpry splits into three on a naive split, one column in CSV
+        assert_eq!(lines[2].split(',').count(), columns + 2);
+    }
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(utc_timestamp(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(utc_timestamp(1_709_210_096_789), "2024-02-29T12:34:56.789Z");
+    }
+}
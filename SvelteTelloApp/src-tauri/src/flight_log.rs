@@ -0,0 +1,253 @@
+// Flight data recorder
+// Every flight, from takeoff to landing, gets its own append-only JSON Lines
+// file: one `{"timestamp", "event", "data"}` object per outgoing command, reply,
+// state packet and mode change (the frontend's FlightLog shape). Lines are
+// flushed as they are written, so a crash loses at most the line in progress.
+// Old logs are rotated out by count and total size.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::error::TelloError;
+use crate::state_stream::TelloStatePacket;
+
+const LOG_DIR: &str = "flight_logs";
+const MAX_LOGS: usize = 100;
+const MAX_TOTAL_BYTES: u64 = 500 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum FlightEvent {
+    // As it went out on the wire, after the geofence
+    Command { command: String },
+    // `command` is None for late replies that no longer match a request
+    Reply { command: Option<String>, reply: String },
+    // The command got no usable reply (timeout, socket error)
+    Error { command: String, error: String },
+    State(TelloStatePacket),
+    // takeoff, landed, emergency, link_lost, auto_land, free_fly, ...
+    Mode { mode: String, detail: Option<String> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FlightLogEntry {
+    pub timestamp: u64, // ms since the Unix epoch
+    #[serde(flatten)]
+    pub event: FlightEvent,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FlightLogInfo {
+    pub name: String,
+    pub path: String,
+    pub started: u64, // ms since the Unix epoch
+    pub duration: f64, // seconds
+    pub size: u64,
+    pub entries: u64,
+    // How the flight ended, if the log was closed properly
+    pub ended_with: Option<String>,
+}
+
+pub struct FlightRecorder {
+    path: PathBuf,
+    writer: LineWriter<File>,
+    failed: bool,
+}
+
+impl FlightRecorder {
+    pub fn directory(app_data_dir: &Path) -> PathBuf {
+        app_data_dir.join(LOG_DIR)
+    }
+
+    pub fn start(directory: &Path) -> Result<Self, TelloError> {
+        std::fs::create_dir_all(directory).map_err(|e| TelloError::io("Failed to create directory", e))?;
+        rotate(directory, MAX_LOGS - 1);
+
+        let path = directory.join(format!("flight_{}.jsonl", now_ms()));
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| TelloError::io(format!("Failed to create {}", path.display()), e))?;
+
+        println!("[FlightLog] 📝 Recording flight to {}", path.display());
+
+        Ok(Self {
+            path,
+            writer: LineWriter::new(file),
+            failed: false,
+        })
+    }
+
+    pub fn record(&mut self, event: FlightEvent) {
+        let entry = FlightLogEntry { timestamp: now_ms(), event };
+        let result = serde_json::to_string(&entry)
+            .map_err(|e| e.to_string())
+            .and_then(|line| writeln!(self.writer, "{}", line).map_err(|e| e.to_string()));
+
+        // Report a full disk once, not ten times a second
+        match result {
+            Ok(()) => self.failed = false,
+            Err(e) if !self.failed => {
+                self.failed = true;
+                eprintln!("[FlightLog] ❌ Failed to write {}: {}", self.path.display(), e);
+            }
+            Err(_) => {}
+        }
+    }
+
+    pub fn finish(mut self, mode: &str) {
+        self.record(mode_event(mode, None));
+        println!("[FlightLog] 💾 Flight log closed ({}): {}", mode, self.path.display());
+    }
+}
+
+pub fn mode_event(mode: &str, detail: Option<String>) -> FlightEvent {
+    FlightEvent::Mode { mode: mode.to_string(), detail }
+}
+
+// Record into the current flight's log, if a flight is in progress
+pub fn record(recorder: &Mutex<Option<FlightRecorder>>, event: FlightEvent) {
+    if let Some(recorder) = recorder.lock().unwrap().as_mut() {
+        recorder.record(event);
+    }
+}
+
+pub fn finish(recorder: &Mutex<Option<FlightRecorder>>, mode: &str) {
+    if let Some(recorder) = recorder.lock().unwrap().take() {
+        recorder.finish(mode);
+    }
+}
+
+// Newest first
+pub fn list(directory: &Path) -> Result<Vec<FlightLogInfo>, TelloError> {
+    let mut logs = Vec::new();
+    for path in log_files(directory)? {
+        match describe(&path) {
+            Ok(info) => logs.push(info),
+            Err(e) => eprintln!("[FlightLog] ⚠️ Skipping {}: {}", path.display(), e),
+        }
+    }
+    logs.sort_by_key(|log| std::cmp::Reverse(log.started));
+    Ok(logs)
+}
+
+// Reads a whole log; lines that fail to parse (a torn last line) are skipped
+pub fn read(path: &Path) -> Result<Vec<FlightLogEntry>, TelloError> {
+    let file = File::open(path).map_err(|e| TelloError::io(format!("Failed to open {}", path.display()), e))?;
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+// A path from `list`, refused unless it is a log in `directory`
+pub fn resolve(directory: &Path, path: &str) -> Result<PathBuf, TelloError> {
+    let path = Path::new(path).canonicalize().map_err(|e| TelloError::io(path, e))?;
+    let in_directory = directory.canonicalize().is_ok_and(|dir| path.parent() == Some(dir.as_path()));
+
+    if !in_directory || path.extension().is_none_or(|ext| ext != "jsonl") {
+        return Err(TelloError::InvalidArgument(format!("{} is not a flight log", path.display())));
+    }
+    Ok(path)
+}
+
+fn describe(path: &Path) -> Result<FlightLogInfo, TelloError> {
+    let size = std::fs::metadata(path).map_err(|e| TelloError::io(path.display(), e))?.len();
+    let entries = read(path)?;
+    let first = entries.first().map_or(0, |entry| entry.timestamp);
+    let last = entries.last().map_or(first, |entry| entry.timestamp);
+    let ended_with = match entries.last().map(|entry| &entry.event) {
+        Some(FlightEvent::Mode { mode, .. }) if mode != "takeoff" => Some(mode.clone()),
+        _ => None,
+    };
+
+    Ok(FlightLogInfo {
+        name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+        path: path.to_string_lossy().to_string(),
+        started: first,
+        duration: last.saturating_sub(first) as f64 / 1000.0,
+        size,
+        entries: entries.len() as u64,
+        ended_with,
+    })
+}
+
+fn log_files(directory: &Path) -> Result<Vec<PathBuf>, TelloError> {
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(TelloError::io(format!("Failed to read {}", directory.display()), e)),
+    };
+
+    Ok(entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .collect())
+}
+
+// Delete the oldest logs until at most `keep` remain and they fit the size budget
+fn rotate(directory: &Path, keep: usize) {
+    let Ok(paths) = log_files(directory) else { return };
+
+    // flight_<ms>.jsonl names sort by start time
+    let mut logs: Vec<(PathBuf, u64)> = paths
+        .into_iter()
+        .map(|path| {
+            let size = std::fs::metadata(&path).map_or(0, |metadata| metadata.len());
+            (path, size)
+        })
+        .collect();
+    logs.sort();
+
+    let mut total: u64 = logs.iter().map(|(_, size)| size).sum();
+    let mut count = logs.len();
+    for (path, size) in logs {
+        if count <= keep && total <= MAX_TOTAL_BYTES {
+            break;
+        }
+        match std::fs::remove_file(&path) {
+            Ok(()) => println!("[FlightLog] 🗑️ Rotated out {}", path.display()),
+            Err(e) => eprintln!("[FlightLog] ⚠️ Failed to remove {}: {}", path.display(), e),
+        }
+        count -= 1;
+        total = total.saturating_sub(size);
+    }
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_round_trip_as_json_lines() {
+        let entries = [
+            FlightLogEntry { timestamp: 1, event: mode_event("takeoff", None) },
+            FlightLogEntry { timestamp: 2, event: FlightEvent::Command { command: "forward 50".to_string() } },
+            FlightLogEntry { timestamp: 3, event: FlightEvent::Reply { command: None, reply: "ok".to_string() } },
+            FlightLogEntry { timestamp: 4, event: FlightEvent::State(TelloStatePacket { h: 120, bat: 87, ..Default::default() }) },
+        ];
+
+        for entry in &entries {
+            let line = serde_json::to_string(entry).unwrap();
+            assert_eq!(serde_json::from_str::<FlightLogEntry>(&line).unwrap(), *entry);
+        }
+
+        assert_eq!(
+            serde_json::to_value(&entries[1]).unwrap(),
+            serde_json::json!({ "timestamp": 2, "event": "command", "data": { "command": "forward 50" } })
+        );
+    }
+}
@@ -0,0 +1,288 @@
+// Media gallery
+// Lists the pictures and recordings on disk, with thumbnails cached in the app's
+// cache directory so the gallery doesn't decode every file on each visit.
+
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::error::TelloError;
+use crate::{mp4, snapshot};
+
+const IMAGE_EXTENSIONS: [&str; 2] = ["jpg", "jpeg"];
+const VIDEO_EXTENSIONS: [&str; 1] = ["mp4"];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    Image,
+    Video,
+}
+
+// Compatible with `CapturedImage` in the frontend gallery store
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GalleryItem {
+    pub id: String,
+    pub filename: String,
+    pub path: String,
+    pub kind: MediaKind,
+    pub timestamp: u64, // capture time, milliseconds since the Unix epoch
+    pub size: u64,      // bytes
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub duration: Option<f64>, // seconds, videos only
+    pub thumbnail: Option<String>, // fetched separately with get_thumbnail
+}
+
+pub struct Gallery {
+    directories: Vec<PathBuf>,
+    thumbnail_dir: PathBuf,
+}
+
+impl Gallery {
+    pub fn new(picture_dir: PathBuf, video_dir: PathBuf, thumbnail_dir: PathBuf) -> Self {
+        let mut directories = vec![picture_dir];
+        if !directories.contains(&video_dir) {
+            directories.push(video_dir);
+        }
+        Self {
+            directories,
+            thumbnail_dir,
+        }
+    }
+
+    // Every picture and video, newest first
+    pub fn list(&self) -> Result<Vec<GalleryItem>, TelloError> {
+        let mut items = Vec::new();
+
+        for directory in &self.directories {
+            let entries = match std::fs::read_dir(directory) {
+                Ok(entries) => entries,
+                // Nothing captured yet
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(TelloError::io(format!("Failed to read {}", directory.display()), e)),
+            };
+
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if let Some(kind) = media_kind(&path) {
+                    match describe(&path, kind) {
+                        Ok(item) => items.push(item),
+                        Err(e) => eprintln!("[Gallery] ⚠️ Skipping {}: {}", path.display(), e),
+                    }
+                }
+            }
+        }
+
+        items.sort_by_key(|item| std::cmp::Reverse(item.timestamp));
+        Ok(items)
+    }
+
+    pub fn delete(&self, path: &str) -> Result<(), TelloError> {
+        let path = self.resolve(path)?;
+
+        // Drop the cached thumbnail first; the key needs the file's metadata
+        if let Ok(thumbnail) = self.thumbnail_path(&path) {
+            let _ = std::fs::remove_file(thumbnail);
+        }
+
+        std::fs::remove_file(&path).map_err(|e| TelloError::io(format!("Failed to delete {}", path.display()), e))?;
+        println!("[Gallery] 🗑️ Deleted {}", path.display());
+        Ok(())
+    }
+
+    // Thumbnail as a data URL, generated on first request
+    pub fn thumbnail(&self, path: &str) -> Result<String, TelloError> {
+        let path = self.resolve(path)?;
+        let cached = self.thumbnail_path(&path)?;
+
+        if let Ok(jpeg) = std::fs::read(&cached) {
+            return Ok(snapshot::data_url(&jpeg));
+        }
+
+        let image = match media_kind(&path) {
+            Some(MediaKind::Video) => {
+                let frame = mp4::read_first_frame(&path).map_err(|e| TelloError::io(format!("Failed to read {}", path.display()), e))?;
+                snapshot::decode_picture(&frame)?
+            }
+            _ => image::open(&path)
+                .map_err(|e| TelloError::Media(format!("Failed to open {}: {}", path.display(), e)))?
+                .to_rgb8(),
+        };
+        let jpeg = snapshot::thumbnail_jpeg(&image)?;
+
+        // A cache we can't write only costs time on the next visit
+        if let Err(e) = std::fs::create_dir_all(&self.thumbnail_dir).and_then(|_| std::fs::write(&cached, &jpeg)) {
+            eprintln!("[Gallery] ⚠️ Could not cache thumbnail: {}", e);
+        }
+
+        Ok(snapshot::data_url(&jpeg))
+    }
+
+    // Only files we would list may be touched, whatever path the UI sends
+    fn resolve(&self, path: &str) -> Result<PathBuf, TelloError> {
+        let path = Path::new(path).canonicalize().map_err(|e| TelloError::io(path, e))?;
+        let in_gallery = self
+            .directories
+            .iter()
+            .filter_map(|dir| dir.canonicalize().ok())
+            .any(|dir| path.parent() == Some(dir.as_path()));
+
+        if !in_gallery || media_kind(&path).is_none() {
+            return Err(TelloError::InvalidArgument(format!("{} is not a gallery item", path.display())));
+        }
+        Ok(path)
+    }
+
+    // Keyed on path, size and modification time so edited files get a new thumbnail
+    fn thumbnail_path(&self, path: &Path) -> Result<PathBuf, TelloError> {
+        let metadata = std::fs::metadata(path).map_err(|e| TelloError::io(path.display(), e))?;
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+        metadata.len().hash(&mut hasher);
+        metadata.modified().ok().hash(&mut hasher);
+        Ok(self.thumbnail_dir.join(format!("{:016x}.jpg", hasher.finish())))
+    }
+}
+
+fn media_kind(path: &Path) -> Option<MediaKind> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+        Some(MediaKind::Image)
+    } else if VIDEO_EXTENSIONS.contains(&extension.as_str()) {
+        Some(MediaKind::Video)
+    } else {
+        None
+    }
+}
+
+fn describe(path: &Path, kind: MediaKind) -> Result<GalleryItem, TelloError> {
+    let metadata = std::fs::metadata(path).map_err(|e| TelloError::io(path.display(), e))?;
+    if !metadata.is_file() {
+        return Err(TelloError::InvalidArgument(format!("{} is not a file", path.display())));
+    }
+
+    // Not every filesystem records creation time
+    let timestamp = metadata
+        .created()
+        .or_else(|_| metadata.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+
+    let (dimensions, duration) = match kind {
+        MediaKind::Image => (image::image_dimensions(path).ok(), None),
+        // A recording still being written has no fragments yet; list it anyway
+        MediaKind::Video => match mp4::read_info(path) {
+            Ok(info) => (Some((info.width, info.height)), Some(info.duration)),
+            Err(_) => (None, None),
+        },
+    };
+
+    let path_string = path.to_string_lossy().to_string();
+    Ok(GalleryItem {
+        id: path_string.clone(),
+        filename: path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+        path: path_string,
+        kind,
+        timestamp,
+        size: metadata.len(),
+        width: dimensions.map(|(width, _)| width),
+        height: dimensions.map(|(_, height)| height),
+        duration,
+        thumbnail: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // root/{pictures,videos,thumbnails} plus a file next to them that must stay out of reach
+    struct Fixture {
+        root: PathBuf,
+        gallery: Gallery,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!("tello_gallery_{}_{}", name, std::process::id()));
+            let _ = std::fs::remove_dir_all(&root);
+            for directory in ["pictures/nested", "videos", "thumbnails"] {
+                std::fs::create_dir_all(root.join(directory)).unwrap();
+            }
+            for file in ["pictures/tello_1.jpg", "pictures/nested/tello_2.jpg", "pictures/notes.txt", "videos/tello_3.mp4", "outside.jpg"] {
+                std::fs::write(root.join(file), b"not really media").unwrap();
+            }
+            let gallery = Gallery::new(root.join("pictures"), root.join("videos"), root.join("thumbnails"));
+            Self { root, gallery }
+        }
+
+        fn resolve(&self, path: &str) -> Result<PathBuf, TelloError> {
+            self.gallery.resolve(&self.root.join(path).to_string_lossy())
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn refused(result: Result<PathBuf, TelloError>) -> bool {
+        matches!(result, Err(TelloError::InvalidArgument(ref message)) if message.ends_with("is not a gallery item"))
+    }
+
+    #[test]
+    fn resolves_pictures_and_videos() {
+        let fixture = Fixture::new("items");
+        let root = fixture.root.canonicalize().unwrap();
+
+        assert_eq!(fixture.resolve("pictures/tello_1.jpg").unwrap(), root.join("pictures/tello_1.jpg"));
+        assert_eq!(fixture.resolve("videos/tello_3.mp4").unwrap(), root.join("videos/tello_3.mp4"));
+        // A detour that ends up back inside is the same file
+        assert_eq!(fixture.resolve("videos/../pictures/tello_1.jpg").unwrap(), root.join("pictures/tello_1.jpg"));
+    }
+
+    #[test]
+    fn refuses_paths_that_climb_out_of_the_gallery() {
+        let fixture = Fixture::new("escape");
+
+        assert!(refused(fixture.resolve("pictures/../outside.jpg")));
+        assert!(refused(fixture.resolve("videos/../pictures/../outside.jpg")));
+    }
+
+    #[test]
+    fn refuses_files_in_subdirectories() {
+        let fixture = Fixture::new("nested");
+        assert!(refused(fixture.resolve("pictures/nested/tello_2.jpg")));
+    }
+
+    #[test]
+    fn refuses_files_that_are_not_media() {
+        let fixture = Fixture::new("extension");
+        assert!(refused(fixture.resolve("pictures/notes.txt")));
+    }
+
+    #[test]
+    fn reports_missing_files_as_not_found() {
+        let fixture = Fixture::new("missing");
+        assert!(matches!(fixture.resolve("pictures/tello_9.jpg"), Err(TelloError::NotFound(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_links_that_point_outside_the_gallery() {
+        let fixture = Fixture::new("symlink");
+        std::os::unix::fs::symlink(fixture.root.join("outside.jpg"), fixture.root.join("pictures/link.jpg")).unwrap();
+        std::os::unix::fs::symlink(fixture.root.join("pictures/tello_1.jpg"), fixture.root.join("videos/link.mp4")).unwrap();
+
+        assert!(refused(fixture.resolve("pictures/link.jpg")));
+        // Resolved to its target, which is a gallery item
+        let root = fixture.root.canonicalize().unwrap();
+        assert_eq!(fixture.resolve("videos/link.mp4").unwrap(), root.join("pictures/tello_1.jpg"));
+    }
+}
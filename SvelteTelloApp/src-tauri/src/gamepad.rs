@@ -0,0 +1,512 @@
+// Gamepad input
+// Xbox/DualShock-style pads are read through gilrs on a thread of their own.
+// The sticks are shaped (deadzone, expo, inversion) and handed to the RC
+// stream as setpoints at a fixed rate; mapped buttons run takeoff, land,
+// emergency and flips through the same paths as the UI buttons. Everything
+// downstream of the reader works on PadEvents, so a virtual pad can stand in
+// for real hardware.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Manager;
+use tokio::sync::mpsc;
+
+use crate::error::TelloError;
+use crate::rc_stream::{RcSetpoint, RcSource};
+use crate::tello_command::FlipDirection;
+use crate::AppState;
+
+const CONFIG_FILE: &str = "gamepad.json";
+// How long the reader thread blocks before checking whether it is still wanted
+const READER_POLL: Duration = Duration::from_millis(100);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftZ,
+    RightZ,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftTrigger,
+    LeftTrigger2,
+    RightTrigger,
+    RightTrigger2,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GamepadAction {
+    Takeoff,
+    Land,
+    Emergency,
+    FlipForward,
+    FlipBack,
+    FlipLeft,
+    FlipRight,
+}
+
+impl GamepadAction {
+    pub fn flip_direction(self) -> Option<FlipDirection> {
+        match self {
+            Self::FlipForward => Some(FlipDirection::Forward),
+            Self::FlipBack => Some(FlipDirection::Back),
+            Self::FlipLeft => Some(FlipDirection::Left),
+            Self::FlipRight => Some(FlipDirection::Right),
+            _ => None,
+        }
+    }
+}
+
+// What the input side reports, from gilrs or a virtual pad
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PadEvent {
+    Connected { name: String },
+    Disconnected { name: String },
+    // -1.0 to 1.0, up and right positive
+    Axis { axis: PadAxis, value: f64 },
+    Pressed { button: PadButton },
+    Released { button: PadButton },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct AxisBinding {
+    pub axis: PadAxis,
+    pub invert: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct ButtonBinding {
+    pub button: PadButton,
+    pub action: GamepadAction,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct GamepadConfig {
+    pub enabled: bool,
+    pub left_right: AxisBinding,
+    pub forward_back: AxisBinding,
+    pub up_down: AxisBinding,
+    pub yaw: AxisBinding,
+    // Fraction of stick travel around center that reads as zero
+    pub deadzone: f64,
+    // 0 is linear; 1 is fully cubic, for fine control near center
+    pub expo: f64,
+    // rc value at full deflection
+    pub max_speed: i32,
+    // Setpoint updates per second while a stick is deflected
+    pub rate_hz: u32,
+    pub buttons: Vec<ButtonBinding>,
+}
+
+// Mode 2: throttle and yaw on the left stick, pitch and roll on the right.
+// Off until turned on in the settings, so a pad left plugged in cannot fly the
+// drone, and emergency and flips are left for the user to bind: one stray
+// press would cut the motors or flip the drone.
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            left_right: AxisBinding { axis: PadAxis::RightStickX, invert: false },
+            forward_back: AxisBinding { axis: PadAxis::RightStickY, invert: false },
+            up_down: AxisBinding { axis: PadAxis::LeftStickY, invert: false },
+            yaw: AxisBinding { axis: PadAxis::LeftStickX, invert: false },
+            deadzone: 0.1,
+            expo: 0.3,
+            max_speed: 100,
+            rate_hz: 20,
+            buttons: vec![
+                ButtonBinding { button: PadButton::Start, action: GamepadAction::Takeoff },
+                ButtonBinding { button: PadButton::Select, action: GamepadAction::Land },
+            ],
+        }
+    }
+}
+
+impl GamepadConfig {
+    pub fn validate(&self) -> Result<(), TelloError> {
+        if !(0.0..=0.5).contains(&self.deadzone) {
+            return Err(TelloError::InvalidArgument("Gamepad deadzone must be between 0 and 0.5".to_string()));
+        }
+        if !(0.0..=1.0).contains(&self.expo) {
+            return Err(TelloError::InvalidArgument("Gamepad expo must be between 0 and 1".to_string()));
+        }
+        if !(10..=100).contains(&self.max_speed) {
+            return Err(TelloError::InvalidArgument("Gamepad max speed must be between 10 and 100".to_string()));
+        }
+        if !(10..=50).contains(&self.rate_hz) {
+            return Err(TelloError::InvalidArgument("Gamepad rate must be between 10 and 50 Hz".to_string()));
+        }
+        if let Some(binding) = self.buttons.iter().find(|binding| {
+            self.buttons.iter().filter(|other| other.button == binding.button).count() > 1
+        }) {
+            return Err(TelloError::InvalidArgument(format!("Button {:?} is mapped more than once", binding.button)));
+        }
+        Ok(())
+    }
+
+    pub fn path(app_data_dir: &Path) -> PathBuf {
+        app_data_dir.join(CONFIG_FILE)
+    }
+
+    // Missing or unreadable files fall back to the defaults
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                eprintln!("[Gamepad] ⚠️ Ignoring invalid {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), TelloError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| TelloError::Io(format!("Failed to create directory: {}", e)))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| TelloError::Io(format!("Failed to serialize gamepad config: {}", e)))?;
+        std::fs::write(path, json).map_err(|e| TelloError::Io(format!("Failed to save gamepad config: {}", e)))
+    }
+
+    fn action(&self, button: PadButton) -> Option<GamepadAction> {
+        self.buttons.iter().find(|binding| binding.button == button).map(|binding| binding.action)
+    }
+}
+
+// Deadzone rescaled so output still starts at 0, then the expo curve
+pub fn shape(value: f64, deadzone: f64, expo: f64) -> f64 {
+    let value = value.clamp(-1.0, 1.0);
+    if value.abs() <= deadzone {
+        return 0.0;
+    }
+    let scaled = value.signum() * (value.abs() - deadzone) / (1.0 - deadzone);
+    (1.0 - expo) * scaled + expo * scaled.powi(3)
+}
+
+// What the pad currently reports
+#[derive(Default)]
+pub struct Pad {
+    name: Option<String>,
+    axes: HashMap<PadAxis, f64>,
+}
+
+impl Pad {
+    // Returns the action of a mapped button that was just pressed
+    pub fn apply(&mut self, event: PadEvent, config: &GamepadConfig) -> Option<GamepadAction> {
+        match event {
+            PadEvent::Connected { name } => self.name = Some(name),
+            // Centre the sticks so a pad unplugged mid-flight stops the drone
+            PadEvent::Disconnected { .. } => {
+                self.name = None;
+                self.axes.clear();
+            }
+            PadEvent::Axis { axis, value } => {
+                self.axes.insert(axis, value);
+            }
+            PadEvent::Pressed { button } => return config.action(button),
+            PadEvent::Released { .. } => {}
+        }
+        None
+    }
+
+    pub fn sticks(&self, config: &GamepadConfig) -> RcSetpoint {
+        let read = |binding: &AxisBinding| {
+            let value = self.axes.get(&binding.axis).copied().unwrap_or(0.0);
+            let value = if binding.invert { -value } else { value };
+            (shape(value, config.deadzone, config.expo) * config.max_speed as f64).round() as i32
+        };
+
+        // max_speed is at most 100, so only a broken config ends up neutral
+        RcSetpoint::new(read(&config.left_right), read(&config.forward_back), read(&config.up_down), read(&config.yaw)).unwrap_or_default()
+    }
+}
+
+// Payload of the `gamepad-status` event and get_gamepad_status
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct GamepadStatus {
+    pub connected: bool,
+    pub name: Option<String>,
+    // Set when gilrs could not start; virtual input still works
+    pub error: Option<String>,
+}
+
+pub struct GamepadInput {
+    task: tauri::async_runtime::JoinHandle<()>,
+    events: mpsc::UnboundedSender<PadEvent>,
+    status: Arc<Mutex<GamepadStatus>>,
+}
+
+impl GamepadInput {
+    // `config` is read on every tick, so changes apply without a restart
+    pub fn start(config: Arc<Mutex<GamepadConfig>>, app_handle: tauri::AppHandle) -> Self {
+        let (events, mut receiver) = mpsc::unbounded_channel();
+        let status = Arc::new(Mutex::new(GamepadStatus::default()));
+
+        if let Err(e) = spawn_reader(events.clone()) {
+            eprintln!("[Gamepad] ⚠️ Gamepad support unavailable: {}", e);
+            status.lock().unwrap().error = Some(e);
+        }
+
+        let task_status = Arc::clone(&status);
+        let task = tauri::async_runtime::spawn(async move {
+            let mut pad = Pad::default();
+            let mut last_sent = RcSetpoint::default();
+            let mut rate_hz = config.lock().unwrap().rate_hz;
+            let mut interval = tokio::time::interval(Duration::from_secs_f64(1.0 / rate_hz as f64));
+
+            loop {
+                tokio::select! {
+                    event = receiver.recv() => {
+                        let Some(event) = event else { break };
+                        let config = config.lock().unwrap().clone();
+
+                        if let PadEvent::Connected { name } | PadEvent::Disconnected { name } = &event {
+                            let connected = matches!(event, PadEvent::Connected { .. });
+                            println!("[Gamepad] 🎮 {} {}", name, if connected { "connected" } else { "disconnected" });
+                            let status = {
+                                let mut status = task_status.lock().unwrap();
+                                status.connected = connected;
+                                status.name = connected.then(|| name.clone());
+                                status.clone()
+                            };
+                            if let Err(e) = app_handle.emit_all("gamepad-status", status) {
+                                eprintln!("[Gamepad] ❌ Failed to emit gamepad-status: {}", e);
+                            }
+                        }
+
+                        if let Some(action) = pad.apply(event, &config) {
+                            if config.enabled {
+                                tauri::async_runtime::spawn(crate::run_gamepad_action(app_handle.clone(), action));
+                            }
+                        }
+                    }
+                    _ = interval.tick() => {
+                        let config = config.lock().unwrap().clone();
+                        if config.rate_hz != rate_hz {
+                            rate_hz = config.rate_hz;
+                            interval = tokio::time::interval(Duration::from_secs_f64(1.0 / rate_hz as f64));
+                        }
+
+                        let sticks = if config.enabled { pad.sticks(&config) } else { RcSetpoint::default() };
+                        // Centred sticks are sent once, so the keyboard and UI keep control otherwise
+                        if sticks.is_zero() && last_sent.is_zero() {
+                            continue;
+                        }
+                        last_sent = sticks;
+                        steer(&app_handle, sticks);
+                    }
+                }
+            }
+        });
+
+        Self { task, events, status }
+    }
+
+    // Feed an event as if it came from a pad: the virtual input device
+    pub fn inject(&self, event: PadEvent) {
+        let _ = self.events.send(event);
+    }
+
+    pub fn status(&self) -> GamepadStatus {
+        self.status.lock().unwrap().clone()
+    }
+}
+
+impl Drop for GamepadInput {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn steer(app_handle: &tauri::AppHandle, sticks: RcSetpoint) {
+    let state = app_handle.state::<AppState>();
+    // Without a connection there is no stream to steer
+    let stream = state.rc_stream.lock().unwrap();
+    if let Some(stream) = stream.as_ref() {
+        // While a safety action holds the sticks, the pad is ignored until centred
+        let _ = stream.set(RcSource::Gamepad, sticks);
+    }
+}
+
+// gilrs is created and polled on a plain thread (it is not Send everywhere);
+// the thread stops once the input task is gone
+fn spawn_reader(events: mpsc::UnboundedSender<PadEvent>) -> Result<(), String> {
+    let (started, result) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || {
+        let mut gilrs = match gilrs::Gilrs::new() {
+            Ok(gilrs) => {
+                let _ = started.send(Ok(()));
+                gilrs
+            }
+            Err(e) => {
+                let _ = started.send(Err(e.to_string()));
+                return;
+            }
+        };
+
+        println!("[Gamepad] 🎮 Listening for gamepads");
+        while !events.is_closed() {
+            let Some(event) = gilrs.next_event_blocking(Some(READER_POLL)) else { continue };
+            let name = gilrs.gamepad(event.id).name().to_string();
+            if let Some(event) = pad_event(event.event, name) {
+                if events.send(event).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+
+    result.recv().map_err(|_| "Gamepad thread exited".to_string())?
+}
+
+fn pad_event(event: gilrs::EventType, name: String) -> Option<PadEvent> {
+    use gilrs::EventType;
+
+    match event {
+        EventType::Connected => Some(PadEvent::Connected { name }),
+        EventType::Disconnected => Some(PadEvent::Disconnected { name }),
+        EventType::AxisChanged(axis, value, _) => Some(PadEvent::Axis { axis: pad_axis(axis)?, value: value as f64 }),
+        EventType::ButtonPressed(button, _) => Some(PadEvent::Pressed { button: pad_button(button)? }),
+        EventType::ButtonReleased(button, _) => Some(PadEvent::Released { button: pad_button(button)? }),
+        _ => None,
+    }
+}
+
+fn pad_axis(axis: gilrs::Axis) -> Option<PadAxis> {
+    use gilrs::Axis;
+
+    match axis {
+        Axis::LeftStickX => Some(PadAxis::LeftStickX),
+        Axis::LeftStickY => Some(PadAxis::LeftStickY),
+        Axis::RightStickX => Some(PadAxis::RightStickX),
+        Axis::RightStickY => Some(PadAxis::RightStickY),
+        Axis::LeftZ => Some(PadAxis::LeftZ),
+        Axis::RightZ => Some(PadAxis::RightZ),
+        _ => None,
+    }
+}
+
+fn pad_button(button: gilrs::Button) -> Option<PadButton> {
+    use gilrs::Button;
+
+    match button {
+        Button::South => Some(PadButton::South),
+        Button::East => Some(PadButton::East),
+        Button::North => Some(PadButton::North),
+        Button::West => Some(PadButton::West),
+        Button::LeftTrigger => Some(PadButton::LeftTrigger),
+        Button::LeftTrigger2 => Some(PadButton::LeftTrigger2),
+        Button::RightTrigger => Some(PadButton::RightTrigger),
+        Button::RightTrigger2 => Some(PadButton::RightTrigger2),
+        Button::Select => Some(PadButton::Select),
+        Button::Start => Some(PadButton::Start),
+        Button::Mode => Some(PadButton::Mode),
+        Button::LeftThumb => Some(PadButton::LeftThumb),
+        Button::RightThumb => Some(PadButton::RightThumb),
+        Button::DPadUp => Some(PadButton::DPadUp),
+        Button::DPadDown => Some(PadButton::DPadDown),
+        Button::DPadLeft => Some(PadButton::DPadLeft),
+        Button::DPadRight => Some(PadButton::DPadRight),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A virtual pad: events go in one end, sticks and actions come out the other
+    fn feed(pad: &mut Pad, config: &GamepadConfig, events: &[PadEvent]) -> Vec<GamepadAction> {
+        events.iter().filter_map(|event| pad.apply(event.clone(), config)).collect()
+    }
+
+    #[test]
+    fn shapes_stick_values() {
+        assert_eq!(shape(0.05, 0.1, 0.0), 0.0);
+        assert_eq!(shape(1.0, 0.1, 0.0), 1.0);
+        assert_eq!(shape(-1.0, 0.1, 0.5), -1.0);
+        assert!((shape(0.55, 0.1, 0.0) - 0.5).abs() < 1e-9);
+        // Expo softens the middle of the travel
+        assert!((shape(0.55, 0.1, 1.0) - 0.125).abs() < 1e-9);
+        assert_eq!(shape(3.0, 0.0, 0.0), 1.0);
+    }
+
+    #[test]
+    fn maps_virtual_pad_to_rc_and_actions() {
+        let mut config = GamepadConfig { enabled: true, deadzone: 0.0, expo: 0.0, max_speed: 50, ..Default::default() };
+        config.yaw.invert = true;
+        config.buttons.push(ButtonBinding { button: PadButton::DPadLeft, action: GamepadAction::FlipLeft });
+        let mut pad = Pad::default();
+
+        let actions = feed(&mut pad, &config, &[
+            PadEvent::Connected { name: "Virtual Pad".to_string() },
+            PadEvent::Axis { axis: PadAxis::RightStickY, value: 1.0 },
+            PadEvent::Axis { axis: PadAxis::RightStickX, value: -0.5 },
+            PadEvent::Axis { axis: PadAxis::LeftStickX, value: 0.5 },
+            PadEvent::Pressed { button: PadButton::Start },
+            PadEvent::Released { button: PadButton::Start },
+            PadEvent::Pressed { button: PadButton::DPadLeft },
+            PadEvent::Pressed { button: PadButton::North },
+        ]);
+
+        assert_eq!(actions, vec![GamepadAction::Takeoff, GamepadAction::FlipLeft]);
+        assert_eq!(pad.sticks(&config), RcSetpoint::new(-25, 50, 0, -25).unwrap());
+
+        feed(&mut pad, &config, &[PadEvent::Disconnected { name: "Virtual Pad".to_string() }]);
+        assert!(pad.sticks(&config).is_zero());
+    }
+
+    #[test]
+    fn ships_disabled_without_emergency_or_flips_bound() {
+        let config = GamepadConfig::default();
+        assert!(!config.enabled);
+
+        let mut pad = Pad::default();
+        let buttons = [PadButton::Mode, PadButton::DPadUp, PadButton::DPadDown, PadButton::DPadLeft, PadButton::DPadRight];
+        let presses: Vec<PadEvent> = buttons.into_iter().map(|button| PadEvent::Pressed { button }).collect();
+        assert!(feed(&mut pad, &config, &presses).is_empty());
+        assert!(config.buttons.iter().all(|binding| matches!(binding.action, GamepadAction::Takeoff | GamepadAction::Land)));
+    }
+
+    #[test]
+    fn validates_config() {
+        assert!(GamepadConfig::default().validate().is_ok());
+        assert!(GamepadConfig { rate_hz: 100, ..Default::default() }.validate().is_err());
+        assert!(GamepadConfig { deadzone: 0.8, ..Default::default() }.validate().is_err());
+
+        let mut config = GamepadConfig::default();
+        config.buttons.push(ButtonBinding { button: PadButton::Start, action: GamepadAction::Land });
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn parses_virtual_events() {
+        let event: PadEvent = serde_json::from_str(r#"{"type": "axis", "axis": "left_stick_y", "value": 0.5}"#).unwrap();
+        assert_eq!(event, PadEvent::Axis { axis: PadAxis::LeftStickY, value: 0.5 });
+    }
+}
@@ -0,0 +1,425 @@
+// Geofence
+// Every outgoing command passes through here before it reaches the drone.
+// Height limits use the state stream's height; horizontal distance from the
+// takeoff point is estimated by integrating the reported velocities, which
+// drifts, so keep some margin in max_distance.
+//
+// Frame: x points where the drone faced at yaw 0, y to its right. The Tello
+// reports vgx/vgy in that frame and yaw in degrees clockwise.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::TelloError;
+use crate::state_stream::TelloStatePacket;
+use crate::tello_command::TelloCommand;
+use crate::DroneState;
+
+// Smallest distance the SDK accepts for up/down/forward/... and go
+const MIN_MOVE_CM: f64 = 20.0;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeofenceConfig {
+    pub enabled: bool,
+    pub max_height: i32, // cm
+    pub min_height: i32, // cm, only enforced in the air
+    pub max_distance: i32, // cm from the takeoff point
+    pub max_yaw_rate: i32, // largest |yaw| accepted in rc, 0..=100
+}
+
+impl Default for GeofenceConfig {
+    fn default() -> Self {
+        // Matches ALTITUDE_MIN/ALTITUDE_MAX in the frontend's constants.ts
+        Self {
+            enabled: true,
+            max_height: 120,
+            min_height: 60,
+            max_distance: 500,
+            max_yaw_rate: 60,
+        }
+    }
+}
+
+impl GeofenceConfig {
+    pub fn validate(&self) -> Result<(), TelloError> {
+        if self.min_height < 0 || self.max_height <= self.min_height {
+            return Err(TelloError::InvalidArgument("Geofence heights must satisfy 0 <= min < max".to_string()));
+        }
+        if self.max_distance <= 0 {
+            return Err(TelloError::InvalidArgument("Geofence distance must be positive".to_string()));
+        }
+        if !(0..=100).contains(&self.max_yaw_rate) {
+            return Err(TelloError::InvalidArgument("Geofence yaw rate must be between 0 and 100".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Verdict {
+    // Send as is
+    Allow,
+    // Send this instead, for this reason
+    Clamp(String, String),
+    Reject(String),
+}
+
+impl DroneState {
+    // Dead reckoning from the state stream; velocities are in dm/s
+    pub fn integrate_velocity(&mut self, packet: &TelloStatePacket, dt: f64) {
+        if self.flying {
+            self.position_x += packet.vgx as f64 * 10.0 * dt;
+            self.position_y += packet.vgy as f64 * 10.0 * dt;
+        }
+    }
+
+    pub fn reset_position(&mut self) {
+        self.position_x = 0.0;
+        self.position_y = 0.0;
+    }
+}
+
+pub fn check(config: &GeofenceConfig, drone: &DroneState, command: &str) -> Verdict {
+    if !config.enabled {
+        return Verdict::Allow;
+    }
+
+    let parts: Vec<&str> = command.split_whitespace().collect();
+    let numbers: Vec<f64> = parts.iter().skip(1).filter_map(|part| part.parse().ok()).collect();
+    let fence = Fence { config, drone };
+    // The pad a go/jump/curve flies against is its first `mN` argument
+    let pad = parts.iter().find_map(|part| part.strip_prefix('m')?.parse::<i32>().ok());
+
+    match (parts.first().copied().unwrap_or(""), numbers.as_slice()) {
+        ("rc", &[lr, fb, ud, yaw]) => fence.rc(lr as i32, fb as i32, ud as i32, yaw as i32),
+        ("up", &[distance]) => fence.vertical("up", distance),
+        ("down", &[distance]) => fence.vertical("down", -distance),
+        ("forward", &[distance]) => fence.horizontal("forward", distance, 0.0),
+        ("back", &[distance]) => fence.horizontal("back", -distance, 0.0),
+        ("right", &[distance]) => fence.horizontal("right", 0.0, distance),
+        ("left", &[distance]) => fence.horizontal("left", 0.0, -distance),
+        ("go" | "jump", &[x, y, z, ..]) | ("curve", &[_, _, _, x, y, z, _]) if pad.is_some() => fence.pad_target(pad.unwrap_or(-2), x, y, z),
+        // go/curve take body-frame x forward, y left, z up; curve ends at its second point
+        ("go", &[x, y, z, _]) | ("curve", &[_, _, _, x, y, z, _]) => fence.target(x, -y, z),
+        _ => Verdict::Allow,
+    }
+}
+
+struct Fence<'a> {
+    config: &'a GeofenceConfig,
+    drone: &'a DroneState,
+}
+
+impl Fence<'_> {
+    fn distance(&self) -> f64 {
+        self.drone.position_x.hypot(self.drone.position_y)
+    }
+
+    // Body-frame (forward, right) to the takeoff frame
+    fn to_world(&self, forward: f64, right: f64) -> (f64, f64) {
+        rotate(self.drone.yaw as f64, forward, right)
+    }
+
+    fn rc(&self, mut lr: i32, mut fb: i32, mut ud: i32, mut yaw: i32) -> Verdict {
+        let mut reasons = Vec::new();
+        let height = self.drone.height;
+
+        if ud > 0 && height >= self.config.max_height {
+            ud = 0;
+            reasons.push(format!("at max height {}cm", self.config.max_height));
+        } else if ud < 0 && self.drone.flying && height <= self.config.min_height {
+            ud = 0;
+            reasons.push(format!("at min height {}cm", self.config.min_height));
+        }
+
+        // Outside the fence only motion back towards the takeoff point is allowed
+        if self.distance() >= self.config.max_distance as f64 && (lr != 0 || fb != 0) {
+            let (dx, dy) = self.to_world(fb as f64, lr as f64);
+            if dx * self.drone.position_x + dy * self.drone.position_y > 0.0 {
+                lr = 0;
+                fb = 0;
+                reasons.push(format!("at max distance {}cm", self.config.max_distance));
+            }
+        }
+
+        if yaw.abs() > self.config.max_yaw_rate {
+            yaw = yaw.signum() * self.config.max_yaw_rate;
+            reasons.push(format!("yaw rate limited to {}", self.config.max_yaw_rate));
+        }
+
+        if reasons.is_empty() {
+            return Verdict::Allow;
+        }
+        match TelloCommand::rc(lr, fb, ud, yaw) {
+            Ok(command) => Verdict::Clamp(command.to_string(), reasons.join(", ")),
+            Err(e) => Verdict::Reject(e.to_string()),
+        }
+    }
+
+    // `change` is signed: positive climbs
+    fn vertical(&self, keyword: &str, change: f64) -> Verdict {
+        let height = self.drone.height as f64;
+        let allowed = if change > 0.0 {
+            (self.config.max_height as f64 - height).max(0.0)
+        } else {
+            (height - self.config.min_height as f64).max(0.0)
+        };
+
+        if change.abs() <= allowed {
+            return Verdict::Allow;
+        }
+
+        let limit = if change > 0.0 { "max" } else { "min" };
+        let limit_height = if change > 0.0 { self.config.max_height } else { self.config.min_height };
+        if allowed < MIN_MOVE_CM {
+            return Verdict::Reject(format!("Geofence: {} {}cm would pass the {} height of {}cm", keyword, change.abs(), limit, limit_height));
+        }
+
+        let clamped = format!("{} {}", keyword, allowed.floor() as i32);
+        Verdict::Clamp(clamped, format!("shortened to stay within the {} height of {}cm", limit, limit_height))
+    }
+
+    fn horizontal(&self, keyword: &str, forward: f64, right: f64) -> Verdict {
+        let (dx, dy) = self.to_world(forward, right);
+        let (x, y) = (self.drone.position_x, self.drone.position_y);
+        let max = self.config.max_distance as f64;
+
+        let after = (x + dx).hypot(y + dy);
+        // Moving back in from outside the fence is always fine
+        if after <= max || after <= self.distance() {
+            return Verdict::Allow;
+        }
+
+        // How far along this direction we can go before crossing the circle:
+        // |p + t·u| = max, with u the unit direction
+        let length = dx.hypot(dy);
+        let (ux, uy) = (dx / length, dy / length);
+        let along = x * ux + y * uy;
+        let allowed = (-along + (along * along - (x * x + y * y - max * max)).max(0.0).sqrt()).max(0.0);
+
+        if allowed < MIN_MOVE_CM {
+            return Verdict::Reject(format!("Geofence: {} {}cm would leave the {}cm radius around takeoff", keyword, length, self.config.max_distance));
+        }
+
+        Verdict::Clamp(
+            format!("{} {}", keyword, allowed.floor() as i32),
+            format!("shortened to stay within {}cm of takeoff", self.config.max_distance),
+        )
+    }
+
+    // go/curve can't be shortened meaningfully, so they are all or nothing
+    fn target(&self, forward: f64, right: f64, up: f64) -> Verdict {
+        let height = self.drone.height as f64 + up;
+        if height > self.config.max_height as f64 {
+            return Verdict::Reject(format!("Geofence: target height {}cm is above the max of {}cm", height, self.config.max_height));
+        }
+        if height < self.config.min_height as f64 {
+            return Verdict::Reject(format!("Geofence: target height {}cm is below the min of {}cm", height, self.config.min_height));
+        }
+
+        let (dx, dy) = self.to_world(forward, right);
+        self.displacement(dx, dy)
+    }
+
+    // Against a mission pad x, y and z are in the pad's frame (y left, like
+    // go) and z is the height above the pad. Where the target lies relative
+    // to takeoff follows from the pose of that pad in the state stream; with
+    // no pose there is no telling, so the move is refused.
+    fn pad_target(&self, pad: i32, x: f64, y: f64, height: f64) -> Verdict {
+        if height > self.config.max_height as f64 {
+            return Verdict::Reject(format!("Geofence: {}cm above the pad is above the max height of {}cm", height, self.config.max_height));
+        }
+        if height < self.config.min_height as f64 {
+            return Verdict::Reject(format!("Geofence: {}cm above the pad is below the min height of {}cm", height, self.config.min_height));
+        }
+
+        // m-1 and m-2 fly against whichever pad is in sight
+        let Some(pose) = self.drone.mission_pad.filter(|pose| pad < 1 || pose.id == pad) else {
+            return Verdict::Reject(format!("Geofence: pad m{} is not in sight, so the target's distance from takeoff is unknown", pad));
+        };
+
+        // The pad's x axis points `pose.yaw` degrees anticlockwise of the drone's heading
+        let heading = (self.drone.yaw - pose.yaw) as f64;
+        let (dx, dy) = rotate(heading, x - pose.x as f64, -(y - pose.y as f64));
+        self.displacement(dx, dy)
+    }
+
+    // A takeoff-frame move from the current position, all or nothing
+    fn displacement(&self, dx: f64, dy: f64) -> Verdict {
+        let after = (self.drone.position_x + dx).hypot(self.drone.position_y + dy);
+        if after > self.config.max_distance as f64 && after > self.distance() {
+            return Verdict::Reject(format!("Geofence: target is {:.0}cm from takeoff, the limit is {}cm", after, self.config.max_distance));
+        }
+        Verdict::Allow
+    }
+}
+
+// (forward, right) in a frame facing `heading` degrees clockwise to the takeoff frame
+fn rotate(heading: f64, forward: f64, right: f64) -> (f64, f64) {
+    let (sin, cos) = heading.to_radians().sin_cos();
+    (forward * cos - right * sin, forward * sin + right * cos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_stream::PadPose;
+
+    // In the air at `height`, `x` cm ahead of and `y` cm right of takeoff
+    fn drone(height: i32, x: f64, y: f64, yaw: i32) -> DroneState {
+        DroneState { flying: true, height, position_x: x, position_y: y, yaw, ..DroneState::default() }
+    }
+
+    fn check_default(drone: &DroneState, command: &str) -> Verdict {
+        check(&GeofenceConfig::default(), drone, command)
+    }
+
+    fn clamp(command: &str, reason: &str) -> Verdict {
+        Verdict::Clamp(command.to_string(), reason.to_string())
+    }
+
+    fn reject(reason: &str) -> Verdict {
+        Verdict::Reject(reason.to_string())
+    }
+
+    #[test]
+    fn limits_climbs_and_descents() {
+        let drone80 = drone(80, 0.0, 0.0, 0);
+        assert_eq!(check_default(&drone80, "up 40"), Verdict::Allow);
+        assert_eq!(check_default(&drone80, "up 100"), clamp("up 40", "shortened to stay within the max height of 120cm"));
+        assert_eq!(check_default(&drone(110, 0.0, 0.0, 0), "up 50"), reject("Geofence: up 50cm would pass the max height of 120cm"));
+
+        let drone100 = drone(100, 0.0, 0.0, 0);
+        assert_eq!(check_default(&drone100, "down 40"), Verdict::Allow);
+        assert_eq!(check_default(&drone100, "down 60"), clamp("down 40", "shortened to stay within the min height of 60cm"));
+        assert_eq!(check_default(&drone(70, 0.0, 0.0, 0), "down 30"), reject("Geofence: down 30cm would pass the min height of 60cm"));
+
+        assert_eq!(check_default(&drone80, "up 0"), Verdict::Allow);
+        assert_eq!(check_default(&drone80, "down 0"), Verdict::Allow);
+    }
+
+    #[test]
+    fn shortens_moves_at_the_radius() {
+        // Straight out from takeoff the whole radius is available
+        assert_eq!(check_default(&drone(80, 0.0, 0.0, 0), "forward 600"), clamp("forward 500", "shortened to stay within 500cm of takeoff"));
+        assert_eq!(check_default(&drone(80, 400.0, 0.0, 0), "forward 200"), clamp("forward 100", "shortened to stay within 500cm of takeoff"));
+        assert_eq!(check_default(&drone(80, -400.0, 0.0, 0), "back 200"), clamp("back 100", "shortened to stay within 500cm of takeoff"));
+        assert_eq!(check_default(&drone(80, 0.0, 400.0, 0), "right 200"), clamp("right 100", "shortened to stay within 500cm of takeoff"));
+        assert_eq!(check_default(&drone(80, 0.0, 400.0, 0), "left 200"), Verdict::Allow);
+
+        // Where the circle is crossed depends on the heading: facing +y (yaw 90)
+        // "forward" runs along y
+        assert_eq!(check_default(&drone(80, 0.0, 400.0, 90), "forward 200"), clamp("forward 100", "shortened to stay within 500cm of takeoff"));
+        assert_eq!(check_default(&drone(80, 400.0, 0.0, 90), "forward 200"), Verdict::Allow);
+
+        // Less than the SDK's 20cm minimum left
+        assert_eq!(
+            check_default(&drone(80, 490.0, 0.0, 0), "forward 50"),
+            reject("Geofence: forward 50cm would leave the 500cm radius around takeoff")
+        );
+        assert_eq!(check_default(&drone(80, 490.0, 0.0, 0), "forward 0"), Verdict::Allow);
+    }
+
+    #[test]
+    fn only_lets_a_drone_outside_the_radius_come_back() {
+        let outside = drone(80, 600.0, 0.0, 0);
+        assert_eq!(check_default(&outside, "back 50"), Verdict::Allow);
+        assert_eq!(check_default(&outside, "forward 0"), Verdict::Allow);
+        assert_eq!(check_default(&outside, "forward 50"), reject("Geofence: forward 50cm would leave the 500cm radius around takeoff"));
+        // Sideways also takes it further out
+        assert_eq!(check_default(&outside, "right 100"), reject("Geofence: right 100cm would leave the 500cm radius around takeoff"));
+        assert_eq!(check_default(&outside, "go -100 0 0 50"), Verdict::Allow);
+    }
+
+    #[test]
+    fn rejects_go_and_curve_targets_outside() {
+        let drone80 = drone(80, 0.0, 0.0, 0);
+        assert_eq!(check_default(&drone80, "go 100 0 0 50"), Verdict::Allow);
+        assert_eq!(check_default(&drone80, "go 0 0 0 10"), Verdict::Allow);
+        assert_eq!(check_default(&drone80, "go 0 0 50 50"), reject("Geofence: target height 130cm is above the max of 120cm"));
+        assert_eq!(check_default(&drone80, "go 0 0 -30 50"), reject("Geofence: target height 50cm is below the min of 60cm"));
+        assert_eq!(check_default(&drone80, "go 600 0 0 50"), reject("Geofence: target is 600cm from takeoff, the limit is 500cm"));
+
+        // go's y points left, the fence's right
+        assert_eq!(check_default(&drone(80, 0.0, -400.0, 0), "go 0 200 0 50"), reject("Geofence: target is 600cm from takeoff, the limit is 500cm"));
+        assert_eq!(check_default(&drone(80, 0.0, -400.0, 0), "go 0 -200 0 50"), Verdict::Allow);
+
+        // A curve is judged by where it ends
+        assert_eq!(check_default(&drone80, "curve 100 100 0 600 0 0 30"), reject("Geofence: target is 600cm from takeoff, the limit is 500cm"));
+        assert_eq!(check_default(&drone80, "curve 600 100 0 100 0 0 30"), Verdict::Allow);
+    }
+
+    #[test]
+    fn checks_pad_targets_against_the_pad_in_sight() {
+        // 480cm out, right above pad 1 and facing the same way as it
+        let pad = |id, x, y, yaw| PadPose { id, x, y, z: 80, pitch: 0, roll: 0, yaw };
+        let over_pad = DroneState { mission_pad: Some(pad(1, 0, 0, 0)), ..drone(80, 480.0, 0.0, 0) };
+
+        assert_eq!(check_default(&over_pad, "go 0 0 150 30 m1"), reject("Geofence: 150cm above the pad is above the max height of 120cm"));
+        assert_eq!(check_default(&over_pad, "jump 100 0 40 40 0 m1 m2"), reject("Geofence: 40cm above the pad is below the min height of 60cm"));
+
+        assert_eq!(check_default(&over_pad, "go 500 500 80 100 m1"), reject("Geofence: target is 1100cm from takeoff, the limit is 500cm"));
+        assert_eq!(check_default(&over_pad, "go -400 0 80 30 m1"), Verdict::Allow);
+        assert_eq!(check_default(&over_pad, "jump 0 50 80 40 0 m1 m2"), Verdict::Allow);
+        assert_eq!(check_default(&over_pad, "curve 50 50 80 100 0 90 30 m-2"), reject("Geofence: target is 580cm from takeoff, the limit is 500cm"));
+
+        // Where the drone sits over the pad counts: 300cm beyond it is 200cm further out
+        let behind_pad = DroneState { mission_pad: Some(pad(1, 100, 0, 0)), ..drone(80, 480.0, 0.0, 0) };
+        assert_eq!(check_default(&behind_pad, "go 0 0 80 30 m1"), Verdict::Allow);
+        assert_eq!(check_default(&behind_pad, "go 300 0 80 30 m1"), reject("Geofence: target is 680cm from takeoff, the limit is 500cm"));
+
+        // Turned 90° clockwise relative to the pad: its right (-y) is our forward, its left our back
+        let turned = DroneState { mission_pad: Some(pad(1, 0, 0, 90)), ..drone(80, 480.0, 0.0, 0) };
+        assert_eq!(check_default(&turned, "go 0 -300 80 30 m1"), reject("Geofence: target is 780cm from takeoff, the limit is 500cm"));
+        assert_eq!(check_default(&turned, "go 0 300 80 30 m1"), Verdict::Allow);
+
+        // Without the pose of the pad named, the distance is unknown
+        let no_pad = drone(80, 0.0, 0.0, 0);
+        assert_eq!(check_default(&no_pad, "go 50 0 80 30 m1"), reject("Geofence: pad m1 is not in sight, so the target's distance from takeoff is unknown"));
+        let other_pad = DroneState { mission_pad: Some(pad(2, 0, 0, 0)), ..no_pad };
+        assert_eq!(check_default(&other_pad, "go 50 0 80 30 m1"), reject("Geofence: pad m1 is not in sight, so the target's distance from takeoff is unknown"));
+        assert_eq!(check_default(&other_pad, "go 50 0 80 30 m-1"), Verdict::Allow);
+    }
+
+    #[test]
+    fn clamps_rc_sticks() {
+        assert_eq!(check_default(&drone(80, 0.0, 0.0, 0), "rc 30 -30 50 60"), Verdict::Allow);
+        assert_eq!(check_default(&drone(120, 0.0, 0.0, 0), "rc 10 0 50 0"), clamp("rc 10 0 0 0", "at max height 120cm"));
+        assert_eq!(check_default(&drone(60, 0.0, 0.0, 0), "rc 0 0 -50 0"), clamp("rc 0 0 0 0", "at min height 60cm"));
+        assert_eq!(check_default(&drone(80, 0.0, 0.0, 0), "rc 0 0 0 -100"), clamp("rc 0 0 0 -60", "yaw rate limited to 60"));
+
+        // On the ground the min height does not apply
+        let landed = DroneState::default();
+        assert_eq!(check_default(&landed, "rc 0 0 -50 0"), Verdict::Allow);
+
+        // At the radius, sticks pointing out are centred and those pointing back kept
+        let edge = drone(80, 500.0, 0.0, 0);
+        assert_eq!(check_default(&edge, "rc 0 50 0 0"), clamp("rc 0 0 0 0", "at max distance 500cm"));
+        assert_eq!(check_default(&edge, "rc 0 -50 0 0"), Verdict::Allow);
+
+        assert_eq!(
+            check_default(&drone(120, 500.0, 0.0, 0), "rc 0 50 50 80"),
+            clamp("rc 0 0 0 60", "at max height 120cm, at max distance 500cm, yaw rate limited to 60")
+        );
+    }
+
+    #[test]
+    fn passes_everything_when_disabled_or_unrelated() {
+        let disabled = GeofenceConfig { enabled: false, ..GeofenceConfig::default() };
+        let high = drone(200, 900.0, 0.0, 0);
+        for command in ["up 100", "forward 500", "go 500 0 0 50", "rc 0 100 100 100"] {
+            assert_eq!(check(&disabled, &high, command), Verdict::Allow, "{}", command);
+        }
+        for command in ["takeoff", "land", "speed 50", "cw 90", "battery?"] {
+            assert_eq!(check_default(&high, command), Verdict::Allow, "{}", command);
+        }
+    }
+
+    #[test]
+    fn validates_config() {
+        assert!(GeofenceConfig::default().validate().is_ok());
+        assert!(GeofenceConfig { min_height: 120, ..GeofenceConfig::default() }.validate().is_err());
+        assert!(GeofenceConfig { max_distance: 0, ..GeofenceConfig::default() }.validate().is_err());
+        assert!(GeofenceConfig { max_yaw_rate: 101, ..GeofenceConfig::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,546 @@
+// H.264 frame assembler for the Tello video stream
+// The drone sends an Annex B byte stream cut into ~1460-byte UDP datagrams with
+// no regard for NAL boundaries. This splits the stream on start codes, groups
+// the NAL units into access units (one picture each) and keeps track of the
+// latest SPS/PPS so every keyframe can be decoded on its own.
+
+use std::time::Instant;
+
+pub const NAL_SLICE: u8 = 1;
+pub const NAL_IDR: u8 = 5;
+pub const NAL_SEI: u8 = 6;
+pub const NAL_SPS: u8 = 7;
+pub const NAL_PPS: u8 = 8;
+pub const NAL_AUD: u8 = 9;
+
+const START_CODE: [u8; 4] = [0, 0, 0, 1];
+
+#[derive(Clone, Debug)]
+pub struct NalUnit {
+    // NAL header byte followed by the payload, without start code
+    pub data: Vec<u8>,
+}
+
+impl NalUnit {
+    pub fn nal_type(&self) -> u8 {
+        self.data.first().map_or(0, |header| header & 0x1f)
+    }
+
+    pub fn is_vcl(&self) -> bool {
+        matches!(self.nal_type(), NAL_SLICE..=NAL_IDR)
+    }
+
+    // first_mb_in_slice is the first ue(v) of the slice header; it is 0 (a
+    // single '1' bit) exactly when this slice starts a new picture
+    fn starts_picture(&self) -> bool {
+        self.data.get(1).is_some_and(|byte| byte & 0x80 != 0)
+    }
+}
+
+// One complete picture as an Annex B byte stream
+#[derive(Clone, Debug)]
+pub struct AccessUnit {
+    pub data: Vec<u8>,
+    pub keyframe: bool,
+    pub sequence: u64,
+    pub received_at: Instant,
+}
+
+#[derive(Default)]
+pub struct FrameAssembler {
+    // Bytes after the last start code seen, not yet known to be a whole NAL unit
+    pending: Vec<u8>,
+    // How far `pending` has already been searched for a start code
+    scanned: usize,
+    synced: bool,
+    current: Vec<NalUnit>,
+    sps: Option<NalUnit>,
+    pps: Option<NalUnit>,
+    sequence: u64,
+}
+
+impl FrameAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Feed one datagram; returns every access unit it completed
+    pub fn push(&mut self, data: &[u8]) -> Vec<AccessUnit> {
+        self.pending.extend_from_slice(data);
+
+        let mut frames = Vec::new();
+
+        while let Some((start, length)) = find_start_code(&self.pending, self.scanned) {
+            if self.synced {
+                let nal = trim_trailing_zeros(&self.pending[..start]).to_vec();
+                if !nal.is_empty() {
+                    if let Some(frame) = self.add_nal(NalUnit { data: nal }) {
+                        frames.push(frame);
+                    }
+                }
+            }
+            // Anything before the very first start code is the tail of a NAL
+            // unit we joined half way through
+            self.synced = true;
+            self.pending.drain(..start + length);
+            self.scanned = 0;
+        }
+
+        // A start code may straddle two datagrams, so rescan the last bytes
+        self.scanned = self.pending.len().saturating_sub(3);
+        frames
+    }
+
+    // Emit whatever is buffered, e.g. when the stream stops. The last NAL unit
+    // may start a picture of its own, so that can be two access units.
+    pub fn flush(&mut self) -> Vec<AccessUnit> {
+        let mut frames = Vec::new();
+        if self.synced && !self.pending.is_empty() {
+            let nal = trim_trailing_zeros(&self.pending).to_vec();
+            self.pending.clear();
+            self.scanned = 0;
+            if !nal.is_empty() {
+                frames.extend(self.add_nal(NalUnit { data: nal }));
+            }
+        }
+        frames.extend(self.finish_access_unit());
+        frames
+    }
+
+    fn add_nal(&mut self, nal: NalUnit) -> Option<AccessUnit> {
+        let has_vcl = self.current.iter().any(NalUnit::is_vcl);
+        let starts_new = has_vcl
+            && match nal.nal_type() {
+                NAL_SEI | NAL_SPS | NAL_PPS | NAL_AUD | 14..=18 => true,
+                NAL_SLICE | NAL_IDR => nal.starts_picture(),
+                _ => false,
+            };
+
+        let finished = if starts_new { self.finish_access_unit() } else { None };
+
+        match nal.nal_type() {
+            NAL_SPS => self.sps = Some(nal.clone()),
+            NAL_PPS => self.pps = Some(nal.clone()),
+            _ => {}
+        }
+
+        self.current.push(nal);
+        finished
+    }
+
+    fn finish_access_unit(&mut self) -> Option<AccessUnit> {
+        if !self.current.iter().any(NalUnit::is_vcl) {
+            return None;
+        }
+
+        let nals = std::mem::take(&mut self.current);
+        let keyframe = nals.iter().any(|nal| nal.nal_type() == NAL_IDR);
+        let mut data = Vec::with_capacity(nals.iter().map(|nal| nal.data.len() + 4).sum::<usize>() + 64);
+
+        // Keyframes must carry their parameter sets so they decode on their own
+        if keyframe {
+            for (nal_type, parameter_set) in [(NAL_SPS, &self.sps), (NAL_PPS, &self.pps)] {
+                if !nals.iter().any(|nal| nal.nal_type() == nal_type) {
+                    if let Some(parameter_set) = parameter_set {
+                        data.extend_from_slice(&START_CODE);
+                        data.extend_from_slice(&parameter_set.data);
+                    }
+                }
+            }
+        }
+
+        for nal in &nals {
+            data.extend_from_slice(&START_CODE);
+            data.extend_from_slice(&nal.data);
+        }
+
+        self.sequence += 1;
+
+        Some(AccessUnit {
+            data,
+            keyframe,
+            sequence: self.sequence,
+            received_at: Instant::now(),
+        })
+    }
+}
+
+// Position and length of the first 3- or 4-byte start code at or after `from`
+fn find_start_code(data: &[u8], from: usize) -> Option<(usize, usize)> {
+    let mut i = from;
+    while i + 3 <= data.len() {
+        if data[i] == 0 && data[i + 1] == 0 && data[i + 2] == 1 {
+            return Some(if i > 0 && data[i - 1] == 0 { (i - 1, 4) } else { (i, 3) });
+        }
+        i += 1;
+    }
+    None
+}
+
+// Zero bytes before the next start code are trailing_zero_8bits, not NAL data
+fn trim_trailing_zeros(data: &[u8]) -> &[u8] {
+    let end = data.iter().rposition(|&byte| byte != 0).map_or(0, |i| i + 1);
+    &data[..end]
+}
+
+// Split a complete Annex B buffer into NAL units (header byte first)
+pub fn split_nal_units(data: &[u8]) -> Vec<&[u8]> {
+    let mut units = Vec::new();
+    let Some((start, length)) = find_start_code(data, 0) else {
+        return units;
+    };
+    let mut position = start + length;
+
+    while let Some((start, length)) = find_start_code(data, position) {
+        let nal = trim_trailing_zeros(&data[position..start]);
+        if !nal.is_empty() {
+            units.push(nal);
+        }
+        position = start + length;
+    }
+
+    let nal = trim_trailing_zeros(&data[position..]);
+    if !nal.is_empty() {
+        units.push(nal);
+    }
+    units
+}
+
+// Drop the 0x03 bytes the encoder inserted to avoid fake start codes
+fn unescape_rbsp(data: &[u8]) -> Vec<u8> {
+    let mut rbsp = Vec::with_capacity(data.len());
+    let mut zeros = 0;
+    for &byte in data {
+        if zeros >= 2 && byte == 3 {
+            zeros = 0;
+            continue;
+        }
+        rbsp.push(byte);
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+    }
+    rbsp
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn bits(&mut self, count: u32) -> Option<u32> {
+        let mut value = 0;
+        for _ in 0..count {
+            let byte = self.data.get(self.position / 8)?;
+            let bit = (byte >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | bit as u32;
+            self.position += 1;
+        }
+        Some(value)
+    }
+
+    fn ue(&mut self) -> Option<u32> {
+        let mut leading_zeros = 0;
+        while self.bits(1)? == 0 {
+            leading_zeros += 1;
+            if leading_zeros > 31 {
+                return None;
+            }
+        }
+        Some((1 << leading_zeros) - 1 + self.bits(leading_zeros)?)
+    }
+
+    fn se(&mut self) -> Option<i32> {
+        let value = self.ue()?;
+        Some(if value % 2 == 1 { value.div_ceil(2) as i32 } else { -((value / 2) as i32) })
+    }
+}
+
+// Picture size in pixels from a sequence parameter set (NAL header included)
+pub fn sps_dimensions(sps: &[u8]) -> Option<(u32, u32)> {
+    let rbsp = unescape_rbsp(sps.get(1..)?);
+    let mut r = BitReader::new(&rbsp);
+
+    let profile_idc = r.bits(8)?;
+    r.bits(16)?; // constraint flags + level_idc
+    r.ue()?; // seq_parameter_set_id
+
+    let mut chroma_format_idc = 1;
+    if matches!(profile_idc, 100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 139 | 134 | 135) {
+        chroma_format_idc = r.ue()?;
+        if chroma_format_idc == 3 {
+            r.bits(1)?; // separate_colour_plane_flag
+        }
+        r.ue()?; // bit_depth_luma_minus8
+        r.ue()?; // bit_depth_chroma_minus8
+        r.bits(1)?; // qpprime_y_zero_transform_bypass_flag
+        if r.bits(1)? == 1 {
+            let lists = if chroma_format_idc == 3 { 12 } else { 8 };
+            for i in 0..lists {
+                if r.bits(1)? == 1 {
+                    skip_scaling_list(&mut r, if i < 6 { 16 } else { 64 })?;
+                }
+            }
+        }
+    }
+
+    r.ue()?; // log2_max_frame_num_minus4
+    match r.ue()? {
+        0 => {
+            r.ue()?; // log2_max_pic_order_cnt_lsb_minus4
+        }
+        1 => {
+            r.bits(1)?; // delta_pic_order_always_zero_flag
+            r.se()?;
+            r.se()?;
+            for _ in 0..r.ue()? {
+                r.se()?;
+            }
+        }
+        _ => {}
+    }
+    r.ue()?; // max_num_ref_frames
+    r.bits(1)?; // gaps_in_frame_num_value_allowed_flag
+
+    let width_mbs = r.ue()? + 1;
+    let height_map_units = r.ue()? + 1;
+    let frame_mbs_only = r.bits(1)?;
+    if frame_mbs_only == 0 {
+        r.bits(1)?; // mb_adaptive_frame_field_flag
+    }
+    r.bits(1)?; // direct_8x8_inference_flag
+
+    let (mut left, mut right, mut top, mut bottom) = (0, 0, 0, 0);
+    if r.bits(1)? == 1 {
+        left = r.ue()?;
+        right = r.ue()?;
+        top = r.ue()?;
+        bottom = r.ue()?;
+    }
+
+    let (crop_x, crop_y) = match chroma_format_idc {
+        1 => (2, 2 * (2 - frame_mbs_only)),
+        2 => (2, 2 - frame_mbs_only),
+        _ => (1, 2 - frame_mbs_only),
+    };
+
+    let width = (width_mbs * 16).checked_sub(crop_x * (left + right))?;
+    let height = ((2 - frame_mbs_only) * height_map_units * 16).checked_sub(crop_y * (top + bottom))?;
+    Some((width, height))
+}
+
+fn skip_scaling_list(r: &mut BitReader, size: usize) -> Option<()> {
+    let mut last = 8;
+    let mut next = 8;
+    for _ in 0..size {
+        if next != 0 {
+            next = (last + r.se()? + 256) % 256;
+        }
+        if next != 0 {
+            last = next;
+        }
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Parameter sets and slices small enough to read: only the header byte and,
+    // for slices, the first payload byte (first_mb_in_slice) matter here
+    const SPS: [u8; 4] = [0x67, 0x42, 0xc0, 0x1e];
+    const PPS: [u8; 3] = [0x68, 0xce, 0x38];
+    // first_mb_in_slice 0 ('1'), a new picture
+    const IDR: [u8; 3] = [0x65, 0x88, 0x84];
+    const SLICE: [u8; 3] = [0x41, 0x9a, 0x21];
+    // first_mb_in_slice 1 ('010'), the rest of the same picture
+    const IDR_CONTINUED: [u8; 3] = [0x65, 0x40, 0x84];
+    const SLICE_CONTINUED: [u8; 3] = [0x41, 0x40, 0x21];
+
+    fn annex_b(nals: &[&[u8]]) -> Vec<u8> {
+        let mut stream = Vec::new();
+        for nal in nals {
+            stream.extend_from_slice(&START_CODE);
+            stream.extend_from_slice(nal);
+        }
+        stream
+    }
+
+    fn nal_types(frame: &AccessUnit) -> Vec<u8> {
+        split_nal_units(&frame.data).iter().map(|nal| nal[0] & 0x1f).collect()
+    }
+
+    #[test]
+    fn finds_three_and_four_byte_start_codes() {
+        let data = [0x42, 0, 0, 1, 0x67, 0, 0, 0, 1, 0x68, 0, 0, 2];
+
+        assert_eq!(find_start_code(&data, 0), Some((1, 3)));
+        assert_eq!(find_start_code(&data, 4), Some((5, 4)));
+        // Searching from inside a start code still reports all of it
+        assert_eq!(find_start_code(&data, 6), Some((5, 4)));
+        assert_eq!(find_start_code(&data, 9), None);
+        assert_eq!(find_start_code(&[0, 0], 0), None);
+    }
+
+    #[test]
+    fn splits_nal_units_on_either_start_code() {
+        let mut data = vec![0xaa, 0xbb]; // tail of a unit we joined late
+        data.extend_from_slice(&[0, 0, 1]);
+        data.extend_from_slice(&SPS);
+        data.extend_from_slice(&[0, 0, 0, 1]);
+        data.extend_from_slice(&PPS);
+        data.extend_from_slice(&[0, 0]); // trailing_zero_8bits
+        data.extend_from_slice(&[0, 0, 0, 1]);
+        data.extend_from_slice(&IDR);
+
+        assert_eq!(split_nal_units(&data), vec![&SPS[..], &PPS[..], &IDR[..]]);
+        assert!(split_nal_units(&[1, 2, 3]).is_empty());
+    }
+
+    #[test]
+    fn reassembles_start_codes_split_across_datagrams() {
+        let stream = annex_b(&[&SPS, &PPS, &IDR, &SLICE]);
+
+        // Every cut point, including the ones inside a start code
+        for cut in 1..stream.len() {
+            let mut assembler = FrameAssembler::new();
+            let mut frames = assembler.push(&stream[..cut]);
+            frames.extend(assembler.push(&stream[cut..]));
+            frames.extend(assembler.flush());
+
+            assert_eq!(frames.len(), 2, "cut at {}", cut);
+            assert_eq!(frames[0].data, annex_b(&[&SPS, &PPS, &IDR]), "cut at {}", cut);
+            assert_eq!(frames[1].data, annex_b(&[&SLICE]), "cut at {}", cut);
+        }
+
+        // And one byte at a time
+        let mut assembler = FrameAssembler::new();
+        let mut frames: Vec<AccessUnit> = stream.iter().flat_map(|byte| assembler.push(&[*byte])).collect();
+        frames.extend(assembler.flush());
+        assert_eq!(frames.len(), 2);
+    }
+
+    #[test]
+    fn accepts_three_byte_start_codes() {
+        let mut stream = vec![0, 0, 1];
+        stream.extend_from_slice(&IDR);
+        stream.extend_from_slice(&[0, 0, 1]);
+        stream.extend_from_slice(&SLICE);
+
+        let mut assembler = FrameAssembler::new();
+        let mut frames = assembler.push(&stream);
+        frames.extend(assembler.flush());
+
+        // Written back out with 4-byte start codes
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].data, annex_b(&[&IDR]));
+        assert_eq!(frames[1].data, annex_b(&[&SLICE]));
+    }
+
+    #[test]
+    fn starts_a_picture_at_first_mb_in_slice_zero() {
+        let stream = annex_b(&[&SPS, &PPS, &IDR, &IDR_CONTINUED, &SLICE, &SLICE_CONTINUED, &SLICE_CONTINUED, &SLICE]);
+
+        let mut assembler = FrameAssembler::new();
+        let mut frames = assembler.push(&stream);
+        // The last slice is still waiting for the next start code
+        assert_eq!(frames.len(), 1);
+        frames.extend(assembler.flush());
+
+        assert_eq!(frames.len(), 3);
+        assert!(frames[0].keyframe);
+        assert_eq!(nal_types(&frames[0]), vec![NAL_SPS, NAL_PPS, NAL_IDR, NAL_IDR]);
+        assert!(!frames[1].keyframe);
+        assert_eq!(nal_types(&frames[1]), vec![NAL_SLICE; 3]);
+        assert_eq!(frames[2].data, annex_b(&[&SLICE]));
+        assert_eq!(frames.iter().map(|frame| frame.sequence).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert!(assembler.flush().is_empty());
+    }
+
+    #[test]
+    fn non_vcl_units_end_the_current_picture() {
+        let stream = annex_b(&[&IDR, &[NAL_AUD, 0xf0], &SLICE_CONTINUED]);
+
+        let mut assembler = FrameAssembler::new();
+        let mut frames = assembler.push(&stream);
+        frames.extend(assembler.flush());
+
+        // A slice that does not start at macroblock 0 still joins the AUD's picture
+        assert_eq!(frames.len(), 2);
+        assert_eq!(nal_types(&frames[0]), vec![NAL_IDR]);
+        assert_eq!(nal_types(&frames[1]), vec![NAL_AUD, NAL_SLICE]);
+    }
+
+    #[test]
+    fn injects_parameter_sets_into_bare_keyframes() {
+        let stream = annex_b(&[&SPS, &PPS, &IDR, &SLICE, &IDR, &SLICE]);
+
+        let mut assembler = FrameAssembler::new();
+        let mut frames = assembler.push(&stream);
+        frames.extend(assembler.flush());
+
+        assert_eq!(frames.len(), 4);
+        assert_eq!(frames[2].data, annex_b(&[&SPS, &PPS, &IDR]));
+        // Only keyframes get them
+        assert_eq!(frames[3].data, annex_b(&[&SLICE]));
+
+        // Nothing to inject before the first SPS/PPS arrives
+        let mut assembler = FrameAssembler::new();
+        let mut frames = assembler.push(&annex_b(&[&IDR, &PPS, &IDR]));
+        frames.extend(assembler.flush());
+        assert_eq!(frames[0].data, annex_b(&[&IDR]));
+        assert_eq!(frames[1].data, annex_b(&[&PPS, &IDR]));
+    }
+
+    #[test]
+    fn drops_bytes_before_the_first_start_code() {
+        let mut stream = vec![0x12, 0x34, 0x56];
+        stream.extend(annex_b(&[&IDR]));
+
+        let mut assembler = FrameAssembler::new();
+        assert!(assembler.push(&stream).is_empty());
+        assert_eq!(assembler.flush()[0].data, annex_b(&[&IDR]));
+    }
+
+    #[test]
+    fn reads_dimensions_from_the_sps() {
+        // Baseline 960x720, as the Tello sends it: 60x45 macroblocks, no cropping
+        let mut w = Vec::new();
+        let mut bits = String::new();
+        bits.push_str("01000010"); // profile_idc 66
+        bits.push_str("11000000"); // constraint flags
+        bits.push_str("00011111"); // level_idc 31
+        bits.push('1'); // seq_parameter_set_id 0
+        bits.push('1'); // log2_max_frame_num_minus4 0
+        bits.push_str("011"); // pic_order_cnt_type 2
+        bits.push_str("010"); // max_num_ref_frames 1
+        bits.push('0'); // gaps_in_frame_num_value_allowed_flag
+        bits.push_str("00000111100"); // pic_width_in_mbs_minus1 59
+        bits.push_str("00000101101"); // pic_height_in_map_units_minus1 44
+        bits.push('1'); // frame_mbs_only_flag
+        bits.push('1'); // direct_8x8_inference_flag
+        bits.push('0'); // frame_cropping_flag
+        bits.push('0'); // vui_parameters_present_flag
+        bits.push('1'); // rbsp_stop_one_bit
+        while !bits.len().is_multiple_of(8) {
+            bits.push('0');
+        }
+        w.push(0x67);
+        for byte in bits.as_bytes().chunks(8) {
+            w.push(u8::from_str_radix(std::str::from_utf8(byte).unwrap(), 2).unwrap());
+        }
+
+        assert_eq!(sps_dimensions(&w), Some((960, 720)));
+        assert_eq!(sps_dimensions(&w[..4]), None);
+        assert_eq!(sps_dimensions(&[0x67]), None);
+    }
+
+    #[test]
+    fn removes_emulation_prevention_bytes() {
+        assert_eq!(unescape_rbsp(&[0, 0, 3, 1, 0, 0, 3, 0, 3]), vec![0, 0, 1, 0, 0, 0, 3]);
+    }
+}
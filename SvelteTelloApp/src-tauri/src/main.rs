@@ -1,14 +1,62 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod battery;
+mod command_channel;
+mod endpoint;
+mod error;
+mod flight_export;
+mod flight_log;
+mod gamepad;
+mod geofence;
+mod gallery;
+mod h264;
+mod mission;
+mod mp4;
+mod network;
+mod rc_stream;
+mod recording;
+mod replay;
+mod script;
+mod simulator;
+mod snapshot;
+mod state_stream;
+mod swarm;
+mod tello_command;
+mod tello_response;
 mod video_capture;
+mod video_settings;
+mod watchdog;
 
+use battery::{BatteryMonitor, BatteryPolicy};
+use command_channel::CommandChannel;
+use endpoint::DroneEndpoint;
+use error::TelloError;
+use flight_export::{FlightExport, GeoOrigin, TrackFormat};
+use flight_log::{FlightLogInfo, FlightRecorder};
+use gamepad::{GamepadAction, GamepadConfig, GamepadInput, GamepadStatus, PadEvent};
+use geofence::{GeofenceConfig, Verdict};
+use gallery::{Gallery, GalleryItem};
+use mission::{MediaDirectories, Mission, MissionRun, MissionStatus};
+use network::NetworkDiagnostic;
+use rc_stream::{RcSetpoint, RcSource, RcStream, RcStreamConfig};
+use recording::{Recorder, RecordingInfo};
+use replay::{FlightReplay, ReplayStatus};
+use script::ScriptRun;
 use serde::{Deserialize, Serialize};
+use simulator::{SimulatorConfig, TelloSimulator};
+use snapshot::{CapturedImage, FrameHistory};
+use state_stream::{PadPose, StateReceiver};
+use swarm::{Swarm, SwarmCommand, SwarmConfig, SwarmDroneStatus, SwarmMember, SwarmReport, SwarmSafety, SwarmStep};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use std::net::UdpSocket;
 use std::time::Duration;
 use tauri::{State, Manager};
+use tello_command::{Query, TelloCommand};
+use tello_response::Reading;
 use video_capture::TelloVideoCapture;
+use video_settings::{VideoQuality, VideoSettings};
+use watchdog::{LinkWatchdog, WatchdogConfig};
 
 // Drone state structures
 #[derive(Default, Clone, Serialize, Deserialize)]
@@ -18,23 +66,55 @@ struct DroneState {
     battery: i32,
     temperature: i32,
     height: i32,
+    tof: i32,
     pitch: i32,
     roll: i32,
     yaw: i32,
     speed: i32,
     video_active: bool,
+    video: VideoSettings,
+    // Estimated position relative to the takeoff point, cm
+    position_x: f64,
+    position_y: f64,
+    // Tello EDU: None while mission pad detection is off, else the camera
+    // direction (0 = down, 1 = forward, 2 = both)
+    mission_pad_direction: Option<i32>,
+    mission_pad: Option<PadPose>,
 }
 
 #[derive(Default)]
 struct AppState {
     drone: Arc<Mutex<DroneState>>,
-    command_socket: Arc<Mutex<Option<UdpSocket>>>,
-    state_socket: Arc<Mutex<Option<UdpSocket>>>,
+    command_channel: Arc<Mutex<Option<CommandChannel>>>,
+    state_receiver: Arc<Mutex<Option<StateReceiver>>>,
+    watchdog: Arc<Mutex<Option<LinkWatchdog>>>,
+    watchdog_config: Arc<Mutex<WatchdogConfig>>,
+    rc_stream: Arc<Mutex<Option<RcStream>>>,
+    rc_stream_config: Arc<Mutex<RcStreamConfig>>,
+    battery_monitor: Arc<Mutex<Option<BatteryMonitor>>>,
+    battery_policy: Arc<Mutex<BatteryPolicy>>,
+    geofence: Arc<Mutex<GeofenceConfig>>,
     video_capture: Arc<Mutex<Option<TelloVideoCapture>>>,
+    recorder: Arc<Mutex<Option<Recorder>>>,
+    frame_history: Arc<Mutex<FrameHistory>>,
+    simulator: Arc<Mutex<Option<TelloSimulator>>>,
+    endpoint: Arc<Mutex<DroneEndpoint>>,
+    // Open from takeoff until the drone is down
+    flight_recorder: Arc<Mutex<Option<FlightRecorder>>>,
+    replay: Arc<Mutex<Option<FlightReplay>>>,
+    gamepad: Arc<Mutex<Option<GamepadInput>>>,
+    gamepad_config: Arc<Mutex<GamepadConfig>>,
+    // The last mission run, kept after it ends for its status
+    mission: Arc<Mutex<Option<MissionRun>>>,
+    script: Arc<Mutex<Option<ScriptRun>>>,
+    // Tello EDUs in station mode, flown apart from the single drone above
+    swarm: Arc<Mutex<Option<Swarm>>>,
+    swarm_config: Arc<Mutex<SwarmConfig>>,
+    swarm_simulators: Arc<Mutex<Vec<TelloSimulator>>>,
 }
 
 // Command/Response types
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 struct CommandResult {
     success: bool,
     message: String,
@@ -48,15 +128,26 @@ struct RCControl {
     yaw: i32,
 }
 
-#[derive(Serialize, Deserialize)]
+// Readings are None when their query failed; `errors` says why
+#[derive(Default, Serialize, Deserialize)]
 struct TelemetryData {
-    battery: i32,
-    temperature: i32,
-    height: i32,
-    pitch: i32,
-    roll: i32,
-    yaw: i32,
+    battery: Option<i32>,
+    temperature: Option<i32>,
+    height: Option<i32>,
+    pitch: Option<i32>,
+    roll: Option<i32>,
+    yaw: Option<i32>,
     tof: Option<i32>,
+    errors: Vec<String>,
+}
+
+// Payload of the `video-frame` event: one complete H.264 access unit
+#[derive(Serialize, Clone)]
+struct VideoFrame {
+    data: String,
+    keyframe: bool,
+    sequence: u64,
+    timestamp: u64, // microseconds since the first frame
 }
 
 // Face recognition types
@@ -86,77 +177,123 @@ struct BoundingBox {
     height: i32,
 }
 
+// The endpoint every networking path should use right now
+fn active_endpoint(state: &AppState) -> DroneEndpoint {
+    let mut endpoint = state.endpoint.lock().unwrap().clone();
+    
+    // With the simulator running the "drone" is on localhost, and it owns the
+    // command port there, so our end of the command link uses any free port instead
+    if let Some(addr) = state.simulator.lock().unwrap().as_ref().map(|sim| sim.command_addr()) {
+        endpoint.ip = addr.ip();
+        endpoint.command_port = addr.port();
+        endpoint.local_command_port = 0;
+    }
+    
+    endpoint
+}
+
 // Tauri commands
 
 #[tauri::command]
-async fn connect_drone(state: State<'_, AppState>) -> Result<CommandResult, String> {
+async fn connect_drone(state: State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<CommandResult, TelloError> {
+    if state.swarm.lock().unwrap().is_some() {
+        return Err(TelloError::InvalidState("Disconnect the swarm before connecting a single drone".to_string()));
+    }
+    
+    let endpoint = active_endpoint(&state);
+    let drone_addr = endpoint.command_addr();
+    let local_addr = endpoint.local_command_bind();
     // Check network configuration first
     println!("[Connect] 🌐 Checking network configuration...");
-    match std::process::Command::new("ipconfig").output() {
-        Ok(output) => {
-            let output_str = String::from_utf8_lossy(&output.stdout);
-            if !output_str.contains("192.168.10.") {
-                eprintln!("[Connect] ⚠️  WARNING: Not connected to Tello WiFi network!");
-                eprintln!("[Connect] 💡 Please connect to TELLO-XXXXXX WiFi first");
-                eprintln!("[Connect] 💡 Your IP should be 192.168.10.x, not 192.168.1.x");
-                return Err("Not connected to Tello WiFi network. Please connect to TELLO-XXXXXX WiFi and try again.".to_string());
-            } else {
-                println!("[Connect] ✅ Detected Tello network (192.168.10.x)");
-            }
-        }
-        Err(_) => {
-            println!("[Connect] ⚠️  Could not verify network - proceeding anyway");
-        }
+    let diagnostic = network::diagnose(endpoint.ip);
+    if !diagnostic.reachable {
+        eprintln!("[Connect] ❌ {}", diagnostic.message);
+        return Err(TelloError::NotOnTelloNetwork(diagnostic.message));
+    } else if diagnostic.same_subnet || endpoint.ip.is_loopback() {
+        println!("[Connect] ✅ {}", diagnostic.message);
+    } else {
+        // Routed setups are valid; only a drone that stays silent is reported
+        eprintln!("[Connect] ⚠️  {}", diagnostic.message);
     }
     
-    // First, clear any existing socket
-    *state.command_socket.lock().unwrap() = None;
+    // A replay would feed the UI a second, recorded drone
+    *state.replay.lock().unwrap() = None;
+    *state.mission.lock().unwrap() = None;
+    *state.script.lock().unwrap() = None;
+    
+    // First, clear any existing channel and state receiver (dropping them stops their socket tasks)
+    *state.watchdog.lock().unwrap() = None;
+    *state.rc_stream.lock().unwrap() = None;
+    *state.battery_monitor.lock().unwrap() = None;
+    *state.command_channel.lock().unwrap() = None;
+    *state.state_receiver.lock().unwrap() = None;
     
     // Small delay to ensure socket is released
     tokio::time::sleep(Duration::from_millis(100)).await;
     
-    println!("[Connect] Creating new UDP socket on 0.0.0.0:8889...");
+    println!("[Connect] Creating new UDP socket on {}...", local_addr);
     
-    let socket = match UdpSocket::bind("0.0.0.0:8889") {
-        Ok(s) => {
+    let channel = match CommandChannel::bind(&local_addr, drone_addr, Arc::clone(&state.flight_recorder)).await {
+        Ok(c) => {
             println!("[Connect] ✅ Socket bound successfully");
-            s
+            c
         }
         Err(e) => {
-            eprintln!("[Connect] ❌ Failed to bind socket: {}", e);
-            return Err(format!("Failed to bind socket: {}. Port 8889 may be in use. Close other Tello apps and try again.", e));
+            eprintln!("[Connect] ❌ {}", e);
+            return Err(e);
         }
     };
     
-    socket.set_read_timeout(Some(Duration::from_secs(5)))
-        .map_err(|e| format!("Failed to set timeout: {}", e))?;
-    
-    println!("[Connect] Sending 'command' to 192.168.10.1:8889...");
+    println!("[Connect] Sending 'command' to {}...", drone_addr);
     
     // Send command mode with retries
     for attempt in 1..=3 {
-        match socket.send_to(b"command", "192.168.10.1:8889") {
-            Ok(bytes) => {
-                println!("[Connect] Attempt {}: Sent {} bytes", attempt, bytes);
-            }
-            Err(e) => {
-                eprintln!("[Connect] ❌ Send error: {}", e);
-                return Err(format!("Failed to send command: {}", e));
-            }
-        }
+        println!("[Connect] Attempt {}: Sending 'command'", attempt);
         
         // Wait for response
-        let mut buf = [0u8; 1024];
-        match socket.recv_from(&mut buf) {
-            Ok((size, addr)) => {
-                let response = String::from_utf8_lossy(&buf[..size]);
-                let trimmed = response.trim();
-                
-                println!("[Connect] Received from {}: '{}' ({} bytes)", addr, trimmed, size);
+        match channel.request(&TelloCommand::Command.to_string(), Duration::from_secs(5)).await {
+            Ok(response) => {
+                println!("[Connect] Received: '{}'", response);
                 
                 // Accept "ok" or "OK" as valid response
-                if trimmed.eq_ignore_ascii_case("ok") {
-                    *state.command_socket.lock().unwrap() = Some(socket);
+                if response.eq_ignore_ascii_case("ok") {
+                    *state.command_channel.lock().unwrap() = Some(channel.clone());
+                    
+                    // The drone starts pushing state packets once in SDK mode
+                    match StateReceiver::start(
+                        &endpoint.local_state_bind(),
+                        endpoint.ip,
+                        Arc::clone(&state.drone),
+                        Arc::clone(&state.flight_recorder),
+                        app_handle.clone(),
+                    ).await {
+                        Ok(receiver) => *state.state_receiver.lock().unwrap() = Some(receiver),
+                        Err(e) => eprintln!("[Connect] ⚠️ State stream unavailable: {}", e),
+                    }
+                    
+                    *state.battery_monitor.lock().unwrap() = Some(BatteryMonitor::start(
+                        Arc::clone(&state.battery_policy),
+                        channel.clone(),
+                        Arc::clone(&state.drone),
+                        Arc::clone(&state.flight_recorder),
+                        app_handle.clone(),
+                    ));
+                    
+                    *state.rc_stream.lock().unwrap() = Some(RcStream::start(
+                        Arc::clone(&state.rc_stream_config),
+                        channel.clone(),
+                        Arc::clone(&state.drone),
+                        Arc::clone(&state.geofence),
+                    ));
+                    
+                    *state.watchdog.lock().unwrap() = Some(LinkWatchdog::start(
+                        Arc::clone(&state.watchdog_config),
+                        channel,
+                        Arc::clone(&state.state_receiver),
+                        Arc::clone(&state.drone),
+                        Arc::clone(&state.flight_recorder),
+                        app_handle,
+                    ));
                     
                     let mut drone = state.drone.lock().unwrap();
                     drone.connected = true;
@@ -169,7 +306,7 @@ async fn connect_drone(state: State<'_, AppState>) -> Result<CommandResult, Stri
                         message: "Connected to drone".to_string(),
                     });
                 } else {
-                    println!("[Connect] ⚠️ Unexpected response: '{}', retrying...", trimmed);
+                    println!("[Connect] ⚠️ Unexpected response: '{}', retrying...", response);
                     if attempt < 3 {
                         tokio::time::sleep(Duration::from_millis(300)).await;
                     }
@@ -179,18 +316,28 @@ async fn connect_drone(state: State<'_, AppState>) -> Result<CommandResult, Stri
                 println!("[Connect] ⚠️ Attempt {} timeout: {}", attempt, e);
                 if attempt < 3 {
                     tokio::time::sleep(Duration::from_millis(300)).await;
+                } else if !diagnostic.same_subnet && !endpoint.ip.is_loopback() {
+                    // Most likely the wrong WiFi rather than a drone that is off
+                    return Err(TelloError::NotOnTelloNetwork(diagnostic.message));
                 } else {
-                    return Err(format!("No response from drone after {} attempts. Make sure:\n1. Drone is powered on\n2. You're connected to TELLO-XXXXXX WiFi\n3. No other apps are using the drone", attempt));
+                    return Err(TelloError::NoResponse { attempts: attempt });
                 }
             }
         }
     }
     
-    Err("Failed to get valid response from drone. Check WiFi connection.".to_string())
+    Err(TelloError::NoResponse { attempts: 3 })
 }
 
 #[tauri::command]
-async fn disconnect_drone(state: State<'_, AppState>) -> Result<CommandResult, String> {
+async fn disconnect_drone(state: State<'_, AppState>) -> Result<CommandResult, TelloError> {
+    end_mission(&state, "Disconnected");
+    
+    // A deliberate disconnect is not a lost link
+    *state.watchdog.lock().unwrap() = None;
+    *state.rc_stream.lock().unwrap() = None;
+    *state.battery_monitor.lock().unwrap() = None;
+    
     // Land if flying
     let is_flying = {
         let drone = state.drone.lock().unwrap();
@@ -198,18 +345,21 @@ async fn disconnect_drone(state: State<'_, AppState>) -> Result<CommandResult, S
     };
     
     if is_flying {
-        let _ = send_command(state.clone(), "land".to_string()).await;
+        let _ = send_tello_command(&state, TelloCommand::Land).await;
         tokio::time::sleep(Duration::from_secs(2)).await;
     }
     
-    *state.command_socket.lock().unwrap() = None;
-    *state.state_socket.lock().unwrap() = None;
+    *state.command_channel.lock().unwrap() = None;
+    *state.state_receiver.lock().unwrap() = None;
     *state.video_capture.lock().unwrap() = None;
+    state.frame_history.lock().unwrap().clear();
+    flight_log::finish(&state.flight_recorder, "disconnected");
     
     let mut drone = state.drone.lock().unwrap();
     drone.connected = false;
     drone.flying = false;
     drone.video_active = false;
+    drone.mission_pad = None;
     
     Ok(CommandResult {
         success: true,
@@ -218,61 +368,311 @@ async fn disconnect_drone(state: State<'_, AppState>) -> Result<CommandResult, S
 }
 
 #[tauri::command]
-async fn send_command(state: State<'_, AppState>, command: String) -> Result<CommandResult, String> {
-    let socket = state.command_socket.lock().unwrap();
+async fn get_geofence(state: State<'_, AppState>) -> Result<GeofenceConfig, TelloError> {
+    Ok(state.geofence.lock().unwrap().clone())
+}
+
+#[tauri::command]
+async fn set_geofence(state: State<'_, AppState>, config: GeofenceConfig) -> Result<CommandResult, TelloError> {
+    config.validate()?;
+    
+    println!("[Geofence] ⚙️ {:?}", config);
+    *state.geofence.lock().unwrap() = config;
     
-    if socket.is_none() {
-        return Err("Not connected to drone".to_string());
+    Ok(CommandResult {
+        success: true,
+        message: "Geofence updated".to_string(),
+    })
+}
+
+#[tauri::command]
+async fn get_watchdog_config(state: State<'_, AppState>) -> Result<WatchdogConfig, TelloError> {
+    Ok(state.watchdog_config.lock().unwrap().clone())
+}
+
+#[tauri::command]
+async fn set_watchdog_config(state: State<'_, AppState>, config: WatchdogConfig) -> Result<CommandResult, TelloError> {
+    if config.degraded_after_ms == 0 || config.lost_after_ms <= config.degraded_after_ms {
+        return Err(TelloError::InvalidArgument("The link must be degraded before it is lost (0 < degraded < lost)".to_string()));
     }
     
-    let sock = socket.as_ref().unwrap();
+    // The running watchdog picks this up on its next tick
+    *state.watchdog_config.lock().unwrap() = config;
+    
+    Ok(CommandResult {
+        success: true,
+        message: "Watchdog settings updated".to_string(),
+    })
+}
+
+#[tauri::command]
+async fn get_rc_stream_config(state: State<'_, AppState>) -> Result<RcStreamConfig, TelloError> {
+    Ok(state.rc_stream_config.lock().unwrap().clone())
+}
+
+#[tauri::command]
+async fn set_rc_stream_config(state: State<'_, AppState>, config: RcStreamConfig) -> Result<CommandResult, TelloError> {
+    config.validate()?;
     
-    sock.send_to(command.as_bytes(), "192.168.10.1:8889")
-        .map_err(|e| format!("Send failed: {}", e))?;
+    // The running stream picks this up on its next tick
+    *state.rc_stream_config.lock().unwrap() = config;
     
-    let mut buf = [0u8; 1024];
-    match sock.recv_from(&mut buf) {
-        Ok((size, _)) => {
-            let response = String::from_utf8_lossy(&buf[..size]);
-            Ok(CommandResult {
-                success: response.trim() == "ok",
-                message: response.trim().to_string(),
+    Ok(CommandResult {
+        success: true,
+        message: "RC stream settings updated".to_string(),
+    })
+}
+
+fn command_channel(state: &AppState) -> Result<CommandChannel, TelloError> {
+    state.command_channel.lock().unwrap()
+        .clone()
+        .ok_or(TelloError::NotConnected)
+}
+
+// Raw commands from the UI get the same checks as the ones built here
+#[tauri::command]
+async fn send_command(state: State<'_, AppState>, command: String) -> Result<CommandResult, TelloError> {
+    let command: TelloCommand = command.parse()?;
+    send_tello_command(&state, command).await
+}
+
+async fn send_tello_command(state: &AppState, command: TelloCommand) -> Result<CommandResult, TelloError> {
+    let channel = command_channel(state)?;
+    
+    let (command, note) = match apply_geofence(state, command.to_string()) {
+        Ok(fenced) => fenced,
+        Err(reason) => {
+            return Ok(CommandResult {
+                success: false,
+                message: reason,
             })
         }
-        Err(e) => Err(format!("Command timeout: {}", e)),
+    };
+    
+    let result = match command_channel::response_timeout(&command) {
+        Some(timeout) => {
+            let response = channel.request(&command, timeout).await?;
+            CommandResult {
+                success: response == "ok",
+                message: response,
+            }
+        }
+        None => {
+            channel.send(&command).await?;
+            CommandResult {
+                success: true,
+                message: "sent".to_string(),
+            }
+        }
+    };
+    
+    Ok(with_geofence_note(result, note))
+}
+
+// Run an outgoing command past the geofence. Returns the command to send (maybe
+// clamped) and why it was changed, or why it must not be sent at all.
+fn apply_geofence(state: &AppState, command: String) -> Result<(String, Option<String>), String> {
+    let config = state.geofence.lock().unwrap().clone();
+    let verdict = geofence::check(&config, &state.drone.lock().unwrap(), &command);
+    
+    match verdict {
+        Verdict::Allow => Ok((command, None)),
+        Verdict::Clamp(clamped, reason) => {
+            println!("[Geofence] ✂️ '{}' -> '{}': {}", command, clamped, reason);
+            Ok((clamped, Some(reason)))
+        }
+        Verdict::Reject(reason) => {
+            println!("[Geofence] 🚫 '{}' rejected: {}", command, reason);
+            Err(reason)
+        }
+    }
+}
+
+fn with_geofence_note(mut result: CommandResult, note: Option<String>) -> CommandResult {
+    if let Some(note) = note {
+        result.message = format!("{} (geofence: {})", result.message, note);
     }
+    result
 }
 
 #[tauri::command]
-async fn takeoff(state: State<'_, AppState>) -> Result<CommandResult, String> {
-    let result = send_command(state.clone(), "takeoff".to_string()).await?;
+async fn takeoff(state: State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<CommandResult, TelloError> {
+    takeoff_drone(&state, &app_handle).await
+}
+
+// Shared by the takeoff command and gamepad buttons
+async fn takeoff_drone(state: &AppState, app_handle: &tauri::AppHandle) -> Result<CommandResult, TelloError> {
+    let battery = state.drone.lock().unwrap().battery;
+    state.battery_policy.lock().unwrap().allows_takeoff(battery)?;
     
-    if result.success {
+    start_flight_log(state, app_handle);
+    
+    let result = match send_tello_command(state, TelloCommand::Takeoff).await {
+        Ok(result) => result,
+        Err(e) => {
+            flight_log::finish(&state.flight_recorder, "aborted");
+            return Err(e);
+        }
+    };
+    
+    if !result.success {
+        flight_log::finish(&state.flight_recorder, "aborted");
+    } else {
         let mut drone = state.drone.lock().unwrap();
         drone.flying = true;
+        // Distance limits are measured from here
+        drone.reset_position();
     }
     
     Ok(result)
 }
 
+// A log that cannot be written never keeps the drone on the ground
+fn start_flight_log(state: &AppState, app_handle: &tauri::AppHandle) {
+    let Some(app_data_dir) = app_handle.path_resolver().app_data_dir() else {
+        eprintln!("[FlightLog] ⚠️ Could not find app data directory, flight not recorded");
+        return;
+    };
+    
+    // A takeoff without a landing in between leaves the previous log open
+    flight_log::finish(&state.flight_recorder, "restarted");
+    
+    match FlightRecorder::start(&FlightRecorder::directory(&app_data_dir)) {
+        Ok(mut recorder) => {
+            recorder.record(flight_log::mode_event("takeoff", None));
+            *state.flight_recorder.lock().unwrap() = Some(recorder);
+        }
+        Err(e) => eprintln!("[FlightLog] ⚠️ Flight not recorded: {}", e),
+    }
+}
+
+#[tauri::command]
+async fn list_flight_logs(app_handle: tauri::AppHandle) -> Result<Vec<FlightLogInfo>, TelloError> {
+    let directory = flight_log_directory(&app_handle)?;
+    tauri::async_runtime::spawn_blocking(move || flight_log::list(&directory))
+        .await
+        .map_err(|e| TelloError::Internal(format!("Flight log task failed: {}", e)))?
+}
+
+fn flight_log_directory(app_handle: &tauri::AppHandle) -> Result<PathBuf, TelloError> {
+    let app_data_dir = app_handle.path_resolver().app_data_dir()
+        .ok_or_else(|| TelloError::Io("Could not find app data directory".to_string()))?;
+    Ok(FlightRecorder::directory(&app_data_dir))
+}
+
+// CSV plus a KML or GPX track; files go to ~/Documents/Tello unless a directory is given
+#[tauri::command]
+async fn export_flight_log(
+    app_handle: tauri::AppHandle,
+    path: String,
+    origin: GeoOrigin,
+    format: TrackFormat,
+    directory: Option<String>,
+) -> Result<FlightExport, TelloError> {
+    origin.validate()?;
+    let path = flight_log::resolve(&flight_log_directory(&app_handle)?, &path)?;
+    let directory = media_directory(directory, dirs::document_dir())?;
+    
+    tauri::async_runtime::spawn_blocking(move || {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+        let entries = flight_log::read(&path)?;
+        flight_export::export(&name, &entries, &origin, format, &directory)
+    })
+    .await
+    .map_err(|e| TelloError::Internal(format!("Export task failed: {}", e)))?
+}
+
+// Replays drive the same drone-state events as a live flight, so they only
+// run while no drone is connected
+#[tauri::command]
+async fn start_replay(
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    path: String,
+    speed: Option<f64>,
+) -> Result<ReplayStatus, TelloError> {
+    if state.command_channel.lock().unwrap().is_some() {
+        return Err(TelloError::InvalidState("Disconnect from the drone before replaying a flight".to_string()));
+    }
+    
+    let path = flight_log::resolve(&flight_log_directory(&app_handle)?, &path)?;
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let entries = tauri::async_runtime::spawn_blocking(move || flight_log::read(&path))
+        .await
+        .map_err(|e| TelloError::Internal(format!("Flight log task failed: {}", e)))??;
+    
+    let replay = FlightReplay::start(name, entries, speed.unwrap_or(1.0), app_handle)?;
+    let status = replay.status();
+    *state.replay.lock().unwrap() = Some(replay);
+    Ok(status)
+}
+
+#[tauri::command]
+async fn pause_replay(state: State<'_, AppState>, paused: bool) -> Result<ReplayStatus, TelloError> {
+    let replay = state.replay.lock().unwrap();
+    let replay = replay.as_ref().ok_or_else(|| TelloError::InvalidState("No replay running".to_string()))?;
+    Ok(replay.set_paused(paused))
+}
+
+#[tauri::command]
+async fn seek_replay(state: State<'_, AppState>, position: f64) -> Result<CommandResult, TelloError> {
+    let replay = state.replay.lock().unwrap();
+    replay.as_ref().ok_or_else(|| TelloError::InvalidState("No replay running".to_string()))?.seek(position)?;
+    
+    Ok(CommandResult {
+        success: true,
+        message: format!("Seeking to {:.1}s", position),
+    })
+}
+
 #[tauri::command]
-async fn land(state: State<'_, AppState>) -> Result<CommandResult, String> {
-    let result = send_command(state.clone(), "land".to_string()).await?;
+async fn set_replay_speed(state: State<'_, AppState>, speed: f64) -> Result<ReplayStatus, TelloError> {
+    let replay = state.replay.lock().unwrap();
+    replay.as_ref().ok_or_else(|| TelloError::InvalidState("No replay running".to_string()))?.set_speed(speed)
+}
+
+#[tauri::command]
+async fn stop_replay(state: State<'_, AppState>) -> Result<CommandResult, TelloError> {
+    *state.replay.lock().unwrap() = None;
+    
+    Ok(CommandResult {
+        success: true,
+        message: "Replay stopped".to_string(),
+    })
+}
+
+#[tauri::command]
+async fn land(state: State<'_, AppState>) -> Result<CommandResult, TelloError> {
+    end_mission(&state, "Landed by the pilot");
+    land_drone(&state).await
+}
+
+async fn land_drone(state: &AppState) -> Result<CommandResult, TelloError> {
+    stop_rc_stream(state);
+    let result = send_tello_command(state, TelloCommand::Land).await?;
     
     if result.success {
         let mut drone = state.drone.lock().unwrap();
         drone.flying = false;
+        flight_log::finish(&state.flight_recorder, "landed");
     }
     
     Ok(result)
 }
 
 #[tauri::command]
-async fn emergency(state: State<'_, AppState>) -> Result<CommandResult, String> {
-    let result = send_command(state.clone(), "emergency".to_string()).await?;
+async fn emergency(state: State<'_, AppState>) -> Result<CommandResult, TelloError> {
+    end_mission(&state, "Emergency stop");
+    emergency_stop(&state).await
+}
+
+async fn emergency_stop(state: &AppState) -> Result<CommandResult, TelloError> {
+    stop_rc_stream(state);
+    let result = send_tello_command(state, TelloCommand::Emergency).await?;
     
     let mut drone = state.drone.lock().unwrap();
     drone.flying = false;
+    flight_log::finish(&state.flight_recorder, "emergency");
     
     Ok(result)
 }
@@ -284,15 +684,60 @@ async fn send_rc_control(
     forward_back: i32,
     up_down: i32,
     yaw: i32,
-) -> Result<(), String> {
-    let command = format!("rc {} {} {} {}", left_right, forward_back, up_down, yaw);
-    let _ = send_command(state, command).await;
-    Ok(())
+) -> Result<CommandResult, TelloError> {
+    let setpoint = RcSetpoint::new(left_right, forward_back, up_down, yaw)?;
+    let stream = state.rc_stream.lock().unwrap();
+    let stream = stream.as_ref().ok_or(TelloError::NotConnected)?;
+    
+    // The stream clamps every tick as the fence changes; this is only so the
+    // pilot hears why a stick is not doing what they asked
+    let note = match apply_geofence(&state, setpoint.to_string()) {
+        Ok((_, note)) => note,
+        Err(reason) => {
+            stream.stop();
+            return Ok(CommandResult {
+                success: false,
+                message: reason,
+            });
+        }
+    };
+    stream.set(RcSource::Ui, setpoint)?;
+    
+    // Sent by the stream on its next tick; the drone never replies to rc
+    Ok(with_geofence_note(CommandResult {
+        success: true,
+        message: "sent".to_string(),
+    }, note))
+}
+
+// Centre the sticks so a held setpoint does not fight a landing
+fn stop_rc_stream(state: &AppState) {
+    if let Some(stream) = state.rc_stream.lock().unwrap().as_ref() {
+        stream.stop();
+    }
+}
+
+// For automatic safety actions: the sticks stay centred until the pilot
+// centres them too, however often the UI or gamepad repeats a held stick
+fn lock_rc_stream(state: &AppState, reason: &str) {
+    if let Some(stream) = state.rc_stream.lock().unwrap().as_ref() {
+        stream.neutral(reason);
+    }
+}
+
+// An automatic safety action takes the drone from whatever was flying it: a
+// running mission or script ends without another command, and the sticks lock
+fn take_over(state: &AppState, reason: &str) {
+    end_mission(state, reason);
+    if let Some(script) = state.script.lock().unwrap().as_ref() {
+        script.hand_over();
+    }
+    lock_rc_stream(state, reason);
 }
 
 #[tauri::command]
-async fn set_speed(state: State<'_, AppState>, speed: i32) -> Result<CommandResult, String> {
-    let result = send_command(state.clone(), format!("speed {}", speed)).await?;
+async fn set_speed(state: State<'_, AppState>, speed: i32) -> Result<CommandResult, TelloError> {
+    let result = send_tello_command(&state, TelloCommand::speed(speed)?).await?;
     
     if result.success {
         let mut drone = state.drone.lock().unwrap();
@@ -303,85 +748,128 @@ async fn set_speed(state: State<'_, AppState>, speed: i32) -> Result<CommandResu
 }
 
 #[tauri::command]
-async fn flip(state: State<'_, AppState>, direction: String) -> Result<CommandResult, String> {
-    send_command(state, format!("flip {}", direction)).await
+async fn flip(state: State<'_, AppState>, direction: String) -> Result<CommandResult, TelloError> {
+    send_tello_command(&state, TelloCommand::flip(&direction)?).await
 }
 
 #[tauri::command]
-async fn get_drone_state(state: State<'_, AppState>) -> Result<DroneState, String> {
-    let drone = state.drone.lock().unwrap();
-    Ok(drone.clone())
+async fn enable_mission_pads(state: State<'_, AppState>, direction: i32) -> Result<CommandResult, TelloError> {
+    enable_pad_detection(&state, direction).await
 }
 
-#[tauri::command]
-async fn get_battery(state: State<'_, AppState>) -> Result<i32, String> {
-    let result = send_command(state.clone(), "battery?".to_string()).await?;
+async fn enable_pad_detection(state: &AppState, direction: i32) -> Result<CommandResult, TelloError> {
+    let direction_command = TelloCommand::mission_pad_direction(direction)?;
     
-    if let Ok(battery) = result.message.trim().parse::<i32>() {
-        let mut drone = state.drone.lock().unwrap();
-        drone.battery = battery;
-        Ok(battery)
-    } else {
-        Err("Failed to parse battery".to_string())
+    let result = send_tello_command(state, TelloCommand::MissionPadOn).await?;
+    if !result.success {
+        return Ok(result);
+    }
+    
+    let result = send_tello_command(state, direction_command).await?;
+    if result.success {
+        state.drone.lock().unwrap().mission_pad_direction = Some(direction);
+        println!("[MissionPad] 🎯 Pad detection on (direction {})", direction);
     }
+    
+    Ok(result)
 }
 
 #[tauri::command]
-async fn get_telemetry(state: State<'_, AppState>) -> Result<TelemetryData, String> {
-    // Query multiple telemetry values
-    let socket = state.command_socket.lock().unwrap();
+async fn disable_mission_pads(state: State<'_, AppState>) -> Result<CommandResult, TelloError> {
+    disable_pad_detection(&state).await
+}
+
+async fn disable_pad_detection(state: &AppState) -> Result<CommandResult, TelloError> {
+    let result = send_tello_command(state, TelloCommand::MissionPadOff).await?;
     
-    if socket.is_none() {
-        return Err("Not connected".to_string());
+    if result.success {
+        let mut drone = state.drone.lock().unwrap();
+        drone.mission_pad_direction = None;
+        drone.mission_pad = None;
     }
     
-    let mut telemetry = TelemetryData {
-        battery: 0,
-        temperature: 0,
-        height: 0,
-        pitch: 0,
-        roll: 0,
-        yaw: 0,
-        tof: None,
-    };
+    Ok(result)
+}
+
+#[tauri::command]
+async fn get_drone_state(state: State<'_, AppState>) -> Result<DroneState, TelloError> {
+    let drone = state.drone.lock().unwrap();
+    Ok(drone.clone())
+}
+
+// Send a read command and parse its reply
+async fn read(channel: &CommandChannel, query: Query) -> Result<Reading, TelloError> {
+    let command = TelloCommand::Query(query).to_string();
+    let timeout = command_channel::response_timeout(&command).unwrap_or(Duration::from_secs(3));
+    let reply = channel.request(&command, timeout).await?;
+    tello_response::parse(query, &reply)
+}
+
+#[tauri::command]
+async fn get_battery(state: State<'_, AppState>) -> Result<i32, TelloError> {
+    let channel = command_channel(&state)?;
     
-    // Helper to query single value
-    let query = |cmd: &str| -> Option<i32> {
-        let sock = socket.as_ref().unwrap();
-        sock.send_to(cmd.as_bytes(), "192.168.10.1:8889").ok()?;
-        let mut buf = [0u8; 1024];
-        let (size, _) = sock.recv_from(&mut buf).ok()?;
-        let response = String::from_utf8_lossy(&buf[..size]);
-        response.trim().parse::<i32>().ok()
+    let reading = read(&channel, Query::Battery).await?;
+    let Reading::Battery { percent } = reading else {
+        return Err(TelloError::Internal(format!("battery? parsed to {:?}", reading)));
     };
     
-    telemetry.battery = query("battery?").unwrap_or(0);
-    telemetry.temperature = query("temp?").unwrap_or(0);
-    telemetry.height = query("height?").unwrap_or(0);
-    telemetry.pitch = query("pitch?").unwrap_or(0);
-    telemetry.roll = query("roll?").unwrap_or(0);
-    telemetry.yaw = query("yaw?").unwrap_or(0);
-    telemetry.tof = query("tof?");
+    state.drone.lock().unwrap().battery = percent;
+    Ok(percent)
+}
+
+#[tauri::command]
+async fn get_telemetry(state: State<'_, AppState>) -> Result<TelemetryData, TelloError> {
+    let channel = command_channel(&state)?;
+    let mut telemetry = TelemetryData::default();
     
-    // Update state
-    let mut drone = state.drone.lock().unwrap();
-    drone.battery = telemetry.battery;
-    drone.temperature = telemetry.temperature;
-    drone.height = telemetry.height;
-    drone.pitch = telemetry.pitch;
-    drone.roll = telemetry.roll;
-    drone.yaw = telemetry.yaw;
+    for query in [Query::Battery, Query::Temperature, Query::Height, Query::Attitude, Query::Tof] {
+        match read(&channel, query).await {
+            Ok(Reading::Battery { percent }) => telemetry.battery = Some(percent),
+            Ok(Reading::Temperature { low, high }) => telemetry.temperature = Some((low + high) / 2),
+            Ok(Reading::Height { cm }) => telemetry.height = Some(cm),
+            Ok(Reading::Attitude { pitch, roll, yaw }) => {
+                telemetry.pitch = Some(pitch);
+                telemetry.roll = Some(roll);
+                telemetry.yaw = Some(yaw);
+            }
+            Ok(Reading::Tof { cm }) => telemetry.tof = Some(cm),
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("[Telemetry] ⚠️ {} failed: {}", query.keyword(), e);
+                telemetry.errors.push(format!("{}: {}", query.keyword(), e));
+            }
+        }
+    }
+    
+    // Only overwrite what was actually read
+    let mut guard = state.drone.lock().unwrap();
+    let drone = &mut *guard;
+    let readings = [
+        (&mut drone.battery, telemetry.battery),
+        (&mut drone.temperature, telemetry.temperature),
+        (&mut drone.height, telemetry.height),
+        (&mut drone.pitch, telemetry.pitch),
+        (&mut drone.roll, telemetry.roll),
+        (&mut drone.yaw, telemetry.yaw),
+        (&mut drone.tof, telemetry.tof),
+    ];
+    for (field, reading) in readings {
+        if let Some(value) = reading {
+            *field = value;
+        }
+    }
     
     Ok(telemetry)
 }
 
 #[tauri::command]
-async fn start_video_stream(state: State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<CommandResult, String> {
+async fn start_video_stream(state: State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<CommandResult, TelloError> {
     println!("[VideoStream] 🎥 Starting video stream...");
     
     // Send streamon command
     println!("[VideoStream] 📡 Sending 'streamon' command to drone...");
-    let result = send_command(state.clone(), "streamon".to_string()).await?;
+    let result = send_tello_command(&state, TelloCommand::StreamOn).await?;
     
     println!("[VideoStream] ✅ streamon response: {:?}", result);
     
@@ -390,66 +878,60 @@ async fn start_video_stream(state: State<'_, AppState>, app_handle: tauri::AppHa
         
         // Create and start video capture (like Python's TelloVideo)
         let mut capture = TelloVideoCapture::new();
-        capture.start();
+        capture.start(&active_endpoint(&state))?;
+        let frames = capture.take_frames().ok_or_else(|| TelloError::Internal("Video capture has no frame queue".to_string()))?;
         
         // Store capture in state
         *state.video_capture.lock().unwrap() = Some(capture);
         
-        // Start frame polling thread (like Python's update_frame loop)
-        let video_capture = Arc::clone(&state.video_capture);
+        // Forward every assembled frame to the UI (and the recorder, if one is
+        // running), in order, keeping the latest ones around for snapshots. The loop ends when the capture is dropped and its
+        // frame queue closes.
+        let recorder = Arc::clone(&state.recorder);
+        let frame_history = Arc::clone(&state.frame_history);
         std::thread::spawn(move || {
-            println!("[PacketForwarder] 🔄 Starting H.264 packet forwarding at 30fps...");
-            let mut packet_num = 0;
-            let mut no_packet_count = 0;
+            println!("[FrameForwarder] 🔄 Starting H.264 frame forwarding...");
+            let mut frame_num = 0u64;
+            let mut stream_start = None;
             
-            loop {
-                std::thread::sleep(Duration::from_millis(33)); // ~30fps
+            for frame in frames {
+                if frame_num == 0 {
+                    println!("[FrameForwarder] 🎉 First frame ready! ({} bytes, keyframe: {})", frame.data.len(), frame.keyframe);
+                }
                 
-                let packet_data = {
-                    let capture_lock = video_capture.lock().unwrap();
-                    if let Some(ref capture) = *capture_lock {
-                        capture.get_packet()
-                    } else {
-                        println!("[PacketForwarder] ⚠️ Capture is None, stopping...");
-                        break; // Capture stopped
+                frame_num += 1;
+                let start = *stream_start.get_or_insert(frame.received_at);
+                
+                frame_history.lock().unwrap().push(&frame);
+                
+                if let Some(recorder) = recorder.lock().unwrap().as_mut() {
+                    if let Err(e) = recorder.push(&frame) {
+                        eprintln!("[FrameForwarder] ❌ Recording error: {}", e);
                     }
+                }
+                
+                // Encode to base64 and emit as one complete H.264 access unit
+                use base64::{Engine as _, engine::general_purpose};
+                let payload = VideoFrame {
+                    data: general_purpose::STANDARD.encode(&frame.data),
+                    keyframe: frame.keyframe,
+                    sequence: frame.sequence,
+                    timestamp: frame.received_at.duration_since(start).as_micros() as u64,
                 };
                 
-                if let Some(h264_bytes) = packet_data {
-                    if packet_num == 0 {
-                        println!("[PacketForwarder] 🎉 First packet ready! ({} bytes)", h264_bytes.len());
-                        println!("[PacketForwarder] 📦 First 16 bytes: {:02x?}", &h264_bytes[..16.min(h264_bytes.len())]);
-                    }
-                    
-                    packet_num += 1;
-                    no_packet_count = 0;
-                    
-                    // Encode to base64 and emit as raw H.264
-                    use base64::{Engine as _, engine::general_purpose};
-                    let encoded = general_purpose::STANDARD.encode(&h264_bytes);
-                    
-                    if let Err(e) = app_handle.emit_all("video-packet", encoded) {
-                        eprintln!("[PacketForwarder] ❌ Failed to emit packet: {}", e);
-                    }
-                    
-                    if packet_num % 30 == 0 {
-                        println!("[PacketForwarder] 📊 {} packets forwarded", packet_num);
-                    }
-                } else {
-                    no_packet_count += 1;
-                    if no_packet_count == 1 {
-                        println!("[PacketForwarder] ⏳ Waiting for first packet from UDP receiver...");
-                    } else if no_packet_count % 30 == 0 {
-                        println!("[PacketForwarder] ⚠️ Still no packets after {} checks (~{} seconds)", 
-                                 no_packet_count, no_packet_count / 30);
-                    }
+                if let Err(e) = app_handle.emit_all("video-frame", payload) {
+                    eprintln!("[FrameForwarder] ❌ Failed to emit frame: {}", e);
+                }
+                
+                if frame_num.is_multiple_of(30) {
+                    println!("[FrameForwarder] 📊 {} frames forwarded", frame_num);
                 }
             }
             
-            println!("[PacketForwarder] 🛑 Packet forwarding stopped");
+            println!("[FrameForwarder] 🛑 Frame forwarding stopped");
         });
         
-        println!("[VideoStream] ✅ Video capture and polling started");
+        println!("[VideoStream] ✅ Video capture and forwarding started");
     } else {
         println!("[VideoStream] ❌ streamon command failed: {}", result.message);
     }
@@ -458,31 +940,690 @@ async fn start_video_stream(state: State<'_, AppState>, app_handle: tauri::AppHa
 }
 
 #[tauri::command]
-async fn stop_video_stream(state: State<'_, AppState>) -> Result<CommandResult, String> {
+async fn stop_video_stream(state: State<'_, AppState>) -> Result<CommandResult, TelloError> {
     println!("[VideoStream] 🛑 Stopping video stream...");
     
     // Stop video capture (this will trigger Drop, cleaning up thread)
     *state.video_capture.lock().unwrap() = None;
+    state.frame_history.lock().unwrap().clear();
+    
+    // Without video there is nothing left to record
+    if let Some(recorder) = state.recorder.lock().unwrap().take() {
+        match recorder.finish() {
+            Ok(info) => println!("[VideoStream] 💾 Recording saved to {}", info.path),
+            Err(e) => eprintln!("[VideoStream] ⚠️ Recording discarded: {}", e),
+        }
+    }
     
     // Send streamoff command
-    send_command(state, "streamoff".to_string()).await
+    send_tello_command(&state, TelloCommand::StreamOff).await
 }
 
 #[tauri::command]
-async fn set_video_bitrate(state: State<'_, AppState>, bitrate: i32) -> Result<CommandResult, String> {
-    send_command(state, format!("setbitrate {}", bitrate)).await
+async fn set_video_bitrate(state: State<'_, AppState>, bitrate: i32) -> Result<CommandResult, TelloError> {
+    let result = send_tello_command(&state, TelloCommand::set_bitrate(bitrate)?).await?;
+    
+    if result.success {
+        let mut drone = state.drone.lock().unwrap();
+        drone.video.bitrate = u8::try_from(bitrate).ok();
+        drone.video.quality = None;
+    }
+    
+    Ok(result)
+}
+
+async fn apply_video_setting(channel: &CommandChannel, command: TelloCommand) -> Result<(), TelloError> {
+    let command = command.to_string();
+    let timeout = command_channel::response_timeout(&command).unwrap_or(Duration::from_secs(5));
+    let response = channel.request(&command, timeout).await?;
+    if response != "ok" {
+        eprintln!("[VideoQuality] ❌ '{}' rejected: {}", command, response);
+        return Err(TelloError::DroneError { command, reason: format!("{} (needs Tello SDK 2.0 or later)", response) });
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_video_quality(state: State<'_, AppState>, quality: String) -> Result<CommandResult, TelloError> {
+    let quality = VideoQuality::parse(&quality)?;
+    let (bitrate, resolution, fps) = quality.parameters();
+    let channel = command_channel(&state)?;
+    
+    println!("[VideoQuality] 🎚️ Applying '{}' preset...", quality.name());
+    
+    // The preset no longer holds if any step fails part way through
+    state.drone.lock().unwrap().video.quality = None;
+    
+    apply_video_setting(&channel, TelloCommand::set_bitrate(bitrate.into())?).await?;
+    state.drone.lock().unwrap().video.bitrate = Some(bitrate);
+    
+    apply_video_setting(&channel, TelloCommand::SetResolution(resolution)).await?;
+    state.drone.lock().unwrap().video.resolution = Some(resolution.name().to_string());
+    
+    apply_video_setting(&channel, TelloCommand::SetFps(fps)).await?;
+    state.drone.lock().unwrap().video.fps = Some(fps.name().to_string());
+    
+    state.drone.lock().unwrap().video.quality = Some(quality.name().to_string());
+    println!("[VideoQuality] ✅ {} Mbps, {} resolution, {} fps", bitrate, resolution.name(), fps.name());
+    
+    Ok(CommandResult {
+        success: true,
+        message: format!("Video quality set to {}", quality.name()),
+    })
+}
+
+// Resolve a user-configured media folder ("~/Videos/Tello"), falling back to
+// `<default_base>/Tello`
+fn media_directory(configured: Option<String>, default_base: Option<PathBuf>) -> Result<PathBuf, TelloError> {
+    match configured.filter(|dir| !dir.trim().is_empty()) {
+        Some(dir) => match dir.strip_prefix("~") {
+            Some(rest) => {
+                let home = dirs::home_dir().ok_or_else(|| TelloError::Io("Could not find home directory".to_string()))?;
+                Ok(home.join(rest.trim_start_matches(['/', '\\'])))
+            }
+            None => Ok(PathBuf::from(dir)),
+        },
+        None => Ok(default_base.ok_or_else(|| TelloError::Io("Could not find media directory".to_string()))?.join("Tello")),
+    }
+}
+
+#[tauri::command]
+async fn start_recording(state: State<'_, AppState>, directory: Option<String>) -> Result<CommandResult, TelloError> {
+    start_recorder(&state, directory)
+}
+
+// Shared by the recording commands and mission steps
+fn start_recorder(state: &AppState, directory: Option<String>) -> Result<CommandResult, TelloError> {
+    if state.video_capture.lock().unwrap().is_none() {
+        return Err(TelloError::InvalidState("Start the video stream before recording".to_string()));
+    }
+    
+    let mut recorder_slot = state.recorder.lock().unwrap();
+    if recorder_slot.is_some() {
+        return Err(TelloError::InvalidState("Already recording".to_string()));
+    }
+    
+    let recorder = Recorder::new(&media_directory(directory, dirs::video_dir())?)?;
+    let message = format!("Recording to {}", recorder.path().display());
+    *recorder_slot = Some(recorder);
+    
+    Ok(CommandResult {
+        success: true,
+        message,
+    })
+}
+
+#[tauri::command]
+async fn stop_recording(state: State<'_, AppState>) -> Result<RecordingInfo, TelloError> {
+    stop_recorder(&state)
+}
+
+fn stop_recorder(state: &AppState) -> Result<RecordingInfo, TelloError> {
+    let recorder = state.recorder.lock().unwrap().take().ok_or_else(|| TelloError::InvalidState("Not recording".to_string()))?;
+    recorder.finish()
+}
+
+#[tauri::command]
+async fn take_picture(state: State<'_, AppState>, directory: Option<String>) -> Result<CapturedImage, TelloError> {
+    capture_picture(&state, directory).await
+}
+
+async fn capture_picture(state: &AppState, directory: Option<String>) -> Result<CapturedImage, TelloError> {
+    if state.video_capture.lock().unwrap().is_none() {
+        return Err(TelloError::InvalidState("Start the video stream before taking a picture".to_string()));
+    }
+    
+    let frames = state.frame_history.lock().unwrap().frames().to_vec();
+    let directory = media_directory(directory, dirs::picture_dir())?;
+    
+    // Software decoding takes a while, keep it off the async runtime
+    tauri::async_runtime::spawn_blocking(move || snapshot::take_picture(&frames, &directory))
+        .await
+        .map_err(|e| TelloError::Internal(format!("Snapshot task failed: {}", e)))?
+}
+
+// Gallery commands
+
+// The directories are the ones from settings, so the gallery shows what the
+// capture commands actually wrote
+fn open_gallery(app_handle: &tauri::AppHandle, picture_directory: Option<String>, video_directory: Option<String>) -> Result<Gallery, TelloError> {
+    let thumbnail_dir = app_handle
+        .path_resolver()
+        .app_cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("thumbnails");
+    
+    Ok(Gallery::new(
+        media_directory(picture_directory, dirs::picture_dir())?,
+        media_directory(video_directory, dirs::video_dir())?,
+        thumbnail_dir,
+    ))
+}
+
+#[tauri::command]
+async fn list_gallery_items(app_handle: tauri::AppHandle, picture_directory: Option<String>, video_directory: Option<String>) -> Result<Vec<GalleryItem>, TelloError> {
+    let gallery = open_gallery(&app_handle, picture_directory, video_directory)?;
+    tauri::async_runtime::spawn_blocking(move || gallery.list())
+        .await
+        .map_err(|e| TelloError::Internal(format!("Gallery task failed: {}", e)))?
+}
+
+#[tauri::command]
+async fn delete_gallery_item(app_handle: tauri::AppHandle, path: String, picture_directory: Option<String>, video_directory: Option<String>) -> Result<CommandResult, TelloError> {
+    open_gallery(&app_handle, picture_directory, video_directory)?.delete(&path)?;
+    
+    Ok(CommandResult {
+        success: true,
+        message: format!("Deleted {}", path),
+    })
+}
+
+#[tauri::command]
+async fn get_thumbnail(app_handle: tauri::AppHandle, path: String, picture_directory: Option<String>, video_directory: Option<String>) -> Result<String, TelloError> {
+    let gallery = open_gallery(&app_handle, picture_directory, video_directory)?;
+    tauri::async_runtime::spawn_blocking(move || gallery.thumbnail(&path))
+        .await
+        .map_err(|e| TelloError::Internal(format!("Thumbnail task failed: {}", e)))?
+}
+
+// Simulator commands
+
+#[tauri::command]
+async fn start_simulator(state: State<'_, AppState>) -> Result<CommandResult, TelloError> {
+    if state.simulator.lock().unwrap().is_some() {
+        return Ok(CommandResult {
+            success: true,
+            message: "Simulator already running".to_string(),
+        });
+    }
+    
+    // The simulator pushes state and video to the same local ports the real drone would
+    let endpoint = state.endpoint.lock().unwrap().clone();
+    let config = SimulatorConfig {
+        state_port: endpoint.state_port,
+        video_port: endpoint.video_port,
+        ..SimulatorConfig::default()
+    };
+    
+    let simulator = TelloSimulator::start(config).await?;
+    let addr = simulator.command_addr();
+    *state.simulator.lock().unwrap() = Some(simulator);
+    
+    Ok(CommandResult {
+        success: true,
+        message: format!("Simulator running on {}. Connect to fly it.", addr),
+    })
+}
+
+#[tauri::command]
+async fn stop_simulator(state: State<'_, AppState>) -> Result<CommandResult, TelloError> {
+    *state.simulator.lock().unwrap() = None;
+    
+    Ok(CommandResult {
+        success: true,
+        message: "Simulator stopped".to_string(),
+    })
+}
+
+#[tauri::command]
+async fn diagnose_network(state: State<'_, AppState>) -> Result<NetworkDiagnostic, TelloError> {
+    Ok(network::diagnose(active_endpoint(&state).ip))
+}
+
+// Endpoint commands
+
+#[tauri::command]
+async fn get_drone_endpoint(state: State<'_, AppState>) -> Result<DroneEndpoint, TelloError> {
+    Ok(state.endpoint.lock().unwrap().clone())
+}
+
+#[tauri::command]
+async fn set_drone_endpoint(
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    endpoint: DroneEndpoint,
+) -> Result<CommandResult, TelloError> {
+    endpoint.validate()?;
+    
+    let app_data_dir = app_handle.path_resolver().app_data_dir()
+        .ok_or_else(|| TelloError::Io("Could not find app data directory".to_string()))?;
+    endpoint.save(&DroneEndpoint::path(&app_data_dir))?;
+    
+    let message = format!("Drone endpoint set to {}. Reconnect to apply.", endpoint.command_addr());
+    *state.endpoint.lock().unwrap() = endpoint;
+    
+    Ok(CommandResult {
+        success: true,
+        message,
+    })
+}
+
+// Battery policy commands
+
+#[tauri::command]
+async fn get_battery_policy(state: State<'_, AppState>) -> Result<BatteryPolicy, TelloError> {
+    Ok(state.battery_policy.lock().unwrap().clone())
+}
+
+#[tauri::command]
+async fn set_battery_policy(
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    policy: BatteryPolicy,
+) -> Result<CommandResult, TelloError> {
+    policy.validate()?;
+    
+    let app_data_dir = app_handle.path_resolver().app_data_dir()
+        .ok_or_else(|| TelloError::Io("Could not find app data directory".to_string()))?;
+    policy.save(&BatteryPolicy::path(&app_data_dir))?;
+    
+    println!("[Battery] ⚙️ Policy: warn at {:?}%, critical at {}%, auto-land {}", policy.warning_levels, policy.critical_level, policy.auto_land);
+    // The running monitor picks this up on its next tick
+    *state.battery_policy.lock().unwrap() = policy;
+    
+    Ok(CommandResult {
+        success: true,
+        message: "Battery policy updated".to_string(),
+    })
+}
+
+// Mission commands
+
+fn mission_directory(app_handle: &tauri::AppHandle) -> Result<PathBuf, TelloError> {
+    let app_data_dir = app_handle.path_resolver().app_data_dir()
+        .ok_or_else(|| TelloError::Io("Could not find app data directory".to_string()))?;
+    Ok(Mission::directory(&app_data_dir))
+}
+
+#[tauri::command]
+async fn list_missions(app_handle: tauri::AppHandle) -> Result<Vec<Mission>, TelloError> {
+    mission::list(&mission_directory(&app_handle)?)
+}
+
+#[tauri::command]
+async fn save_mission(app_handle: tauri::AppHandle, mission: Mission) -> Result<CommandResult, TelloError> {
+    mission.save(&mission_directory(&app_handle)?)?;
+    
+    Ok(CommandResult {
+        success: true,
+        message: format!("Saved mission '{}'", mission.name),
+    })
+}
+
+#[tauri::command]
+async fn delete_mission(app_handle: tauri::AppHandle, name: String) -> Result<CommandResult, TelloError> {
+    mission::delete(&mission_directory(&app_handle)?, &name)?;
+    
+    Ok(CommandResult {
+        success: true,
+        message: format!("Deleted mission '{}'", name),
+    })
+}
+
+#[tauri::command]
+async fn start_mission(
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    name: String,
+    picture_directory: Option<String>,
+    video_directory: Option<String>,
+) -> Result<MissionStatus, TelloError> {
+    command_channel(&state)?;
+    let mission = Mission::load(&mission_directory(&app_handle)?, &name)?;
+    mission.validate()?;
+    
+    // Locked in the same order as run_script: mission, then script
+    let mut slot = state.mission.lock().unwrap();
+    if slot.as_ref().is_some_and(|run| !run.status().is_finished()) {
+        return Err(TelloError::InvalidState("Another mission is running".to_string()));
+    }
+    if state.script.lock().unwrap().as_ref().is_some_and(|script| !script.is_finished()) {
+        return Err(TelloError::InvalidState("A script is flying the drone; stop it first".to_string()));
+    }
+    
+    let directories = MediaDirectories {
+        pictures: picture_directory,
+        videos: video_directory,
+    };
+    let run = MissionRun::start(mission, directories, app_handle);
+    let status = run.status();
+    *slot = Some(run);
+    Ok(status)
+}
+
+#[tauri::command]
+async fn pause_mission(state: State<'_, AppState>, paused: bool) -> Result<MissionStatus, TelloError> {
+    let mission = state.mission.lock().unwrap();
+    mission.as_ref().ok_or_else(|| TelloError::InvalidState("No mission is running".to_string()))?.set_paused(paused)
+}
+
+// Abort leaves the drone hovering where it is
+#[tauri::command]
+async fn abort_mission(state: State<'_, AppState>) -> Result<MissionStatus, TelloError> {
+    let status = {
+        let mission = state.mission.lock().unwrap();
+        mission.as_ref().ok_or_else(|| TelloError::InvalidState("No mission is running".to_string()))?.abort("Aborted")
+    };
+    
+    if state.drone.lock().unwrap().flying {
+        send_tello_command(&state, TelloCommand::Stop).await?;
+    }
+    Ok(status)
+}
+
+#[tauri::command]
+async fn get_mission_status(state: State<'_, AppState>) -> Result<Option<MissionStatus>, TelloError> {
+    Ok(state.mission.lock().unwrap().as_ref().map(MissionRun::status))
+}
+
+// The pilot taking over ends a running mission
+fn end_mission(state: &AppState, reason: &str) {
+    if let Some(mission) = state.mission.lock().unwrap().as_ref() {
+        mission.abort(reason);
+    }
+}
+
+// Script commands; output arrives as `script-output` events
+
+#[tauri::command]
+async fn run_script(state: State<'_, AppState>, app_handle: tauri::AppHandle, source: String) -> Result<CommandResult, TelloError> {
+    // Held until the script has started, so a mission cannot start in between
+    let mission = state.mission.lock().unwrap();
+    if mission.as_ref().is_some_and(|run| !run.status().is_finished()) {
+        return Err(TelloError::InvalidState("A mission is flying the drone; abort it first".to_string()));
+    }
+    let mut slot = state.script.lock().unwrap();
+    if slot.as_ref().is_some_and(|script| !script.is_finished()) {
+        return Err(TelloError::InvalidState("A script is already running".to_string()));
+    }
+    *slot = Some(ScriptRun::start(&source, app_handle)?);
+    
+    Ok(CommandResult {
+        success: true,
+        message: "Script started".to_string(),
+    })
+}
+
+#[tauri::command]
+async fn stop_script(state: State<'_, AppState>) -> Result<CommandResult, TelloError> {
+    let script = state.script.lock().unwrap();
+    match script.as_ref().filter(|script| !script.is_finished()) {
+        Some(script) => script.stop(),
+        None => return Err(TelloError::InvalidState("No script is running".to_string())),
+    }
+    
+    Ok(CommandResult {
+        success: true,
+        message: "Stopping script".to_string(),
+    })
+}
+
+// Swarm commands
+
+// Local state ports for simulated swarms; each simulator on localhost needs its own
+const SWARM_SIMULATOR_STATE_PORT: u16 = 8891;
+const SWARM_SIMULATOR_VIDEO_PORT: u16 = 11112;
+const MAX_SWARM_SIMULATORS: u16 = 8;
+
+fn swarm(state: &AppState) -> Result<Swarm, TelloError> {
+    state.swarm.lock().unwrap().clone().ok_or(TelloError::NotConnected)
+}
+
+#[tauri::command]
+async fn get_swarm_config(state: State<'_, AppState>) -> Result<SwarmConfig, TelloError> {
+    Ok(state.swarm_config.lock().unwrap().clone())
+}
+
+#[tauri::command]
+async fn set_swarm_config(
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    config: SwarmConfig,
+) -> Result<CommandResult, TelloError> {
+    config.validate()?;
+    
+    let app_data_dir = app_handle.path_resolver().app_data_dir()
+        .ok_or_else(|| TelloError::Io("Could not find app data directory".to_string()))?;
+    config.save(&SwarmConfig::path(&app_data_dir))?;
+    
+    let message = format!("Swarm of {} drones saved. Reconnect the swarm to apply.", config.members.len());
+    *state.swarm_config.lock().unwrap() = config;
+    
+    Ok(CommandResult {
+        success: true,
+        message,
+    })
+}
+
+#[tauri::command]
+async fn connect_swarm(state: State<'_, AppState>) -> Result<Vec<SwarmDroneStatus>, TelloError> {
+    // Both listen for state packets on the same local port
+    if state.command_channel.lock().unwrap().is_some() {
+        return Err(TelloError::InvalidState("Disconnect the single drone before connecting the swarm".to_string()));
+    }
+    
+    // Dropping the old swarm frees its sockets
+    *state.swarm.lock().unwrap() = None;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    
+    let config = state.swarm_config.lock().unwrap().clone();
+    let safety = SwarmSafety {
+        geofence: Arc::clone(&state.geofence),
+        battery_policy: Arc::clone(&state.battery_policy),
+        watchdog: Arc::clone(&state.watchdog_config),
+    };
+    let swarm = Swarm::connect(&config, safety).await?;
+    let status = swarm.status();
+    *state.swarm.lock().unwrap() = Some(swarm);
+    
+    Ok(status)
+}
+
+#[tauri::command]
+async fn disconnect_swarm(state: State<'_, AppState>) -> Result<CommandResult, TelloError> {
+    let swarm = swarm(&state)?;
+    // A run would carry on with its next step after the landing
+    swarm.stop();
+    
+    // Land whatever is still up, like a single drone disconnect does
+    let flying = swarm.flying();
+    if !flying.is_empty() {
+        let land = SwarmStep {
+            commands: flying.into_iter().map(|drone| SwarmCommand { drone: Some(drone), command: TelloCommand::Land.to_string() }).collect(),
+        };
+        for reply in swarm.step(&land).await? {
+            if !reply.success {
+                eprintln!("[Swarm] ⚠️ {} did not land: {}", reply.drone, reply.message);
+            }
+        }
+    }
+    
+    *state.swarm.lock().unwrap() = None;
+    
+    Ok(CommandResult {
+        success: true,
+        message: "Swarm disconnected".to_string(),
+    })
+}
+
+#[tauri::command]
+async fn get_swarm_status(state: State<'_, AppState>) -> Result<Vec<SwarmDroneStatus>, TelloError> {
+    Ok(state.swarm.lock().unwrap().as_ref().map(Swarm::status).unwrap_or_default())
+}
+
+// One drone by id or serial, or every drone when `drone` is None
+#[tauri::command]
+async fn swarm_command(state: State<'_, AppState>, drone: Option<String>, command: String) -> Result<SwarmReport, TelloError> {
+    let swarm = swarm(&state)?;
+    // After an emergency, a running sequence must not send its next step
+    if matches!(command.parse::<TelloCommand>(), Ok(TelloCommand::Emergency)) {
+        swarm.stop();
+    }
+    let step = SwarmStep {
+        commands: vec![SwarmCommand { drone, command }],
+    };
+    swarm.run(&[step]).await
+}
+
+#[tauri::command]
+async fn run_swarm_steps(state: State<'_, AppState>, steps: Vec<SwarmStep>) -> Result<SwarmReport, TelloError> {
+    swarm(&state)?.run(&steps).await
+}
+
+// The step in flight still finishes; no further step is sent
+#[tauri::command]
+async fn stop_swarm_run(state: State<'_, AppState>) -> Result<CommandResult, TelloError> {
+    swarm(&state)?.stop();
+    Ok(CommandResult {
+        success: true,
+        message: "Swarm run stopping after the current step".to_string(),
+    })
+}
+
+// Starts `count` simulators on localhost and points the swarm at them (not saved)
+#[tauri::command]
+async fn start_swarm_simulators(state: State<'_, AppState>, count: u16) -> Result<SwarmConfig, TelloError> {
+    if !(1..=MAX_SWARM_SIMULATORS).contains(&count) {
+        return Err(TelloError::InvalidArgument(format!("Simulate 1-{} drones, got {}", MAX_SWARM_SIMULATORS, count)));
+    }
+    
+    state.swarm_simulators.lock().unwrap().clear();
+    
+    let mut simulators = Vec::new();
+    let mut members = Vec::new();
+    for index in 0..count {
+        let simulator = TelloSimulator::start(SimulatorConfig {
+            command_addr: "127.0.0.1:0".parse().unwrap(),
+            state_port: SWARM_SIMULATOR_STATE_PORT + index,
+            video_port: SWARM_SIMULATOR_VIDEO_PORT + index,
+            serial: format!("0TQZSIM{:02}", index + 1),
+            ..SimulatorConfig::default()
+        }).await?;
+        members.push(SwarmMember {
+            ip: simulator.command_addr().ip(),
+            command_port: simulator.command_addr().port(),
+            state_port: SWARM_SIMULATOR_STATE_PORT + index,
+        });
+        simulators.push(simulator);
+    }
+    
+    let config = SwarmConfig { members };
+    *state.swarm_simulators.lock().unwrap() = simulators;
+    *state.swarm_config.lock().unwrap() = config.clone();
+    
+    Ok(config)
+}
+
+#[tauri::command]
+async fn stop_swarm_simulators(state: State<'_, AppState>, app_handle: tauri::AppHandle) -> Result<CommandResult, TelloError> {
+    *state.swarm.lock().unwrap() = None;
+    state.swarm_simulators.lock().unwrap().clear();
+    
+    // Back to the saved swarm
+    if let Some(app_data_dir) = app_handle.path_resolver().app_data_dir() {
+        *state.swarm_config.lock().unwrap() = SwarmConfig::load(&SwarmConfig::path(&app_data_dir));
+    }
+    
+    Ok(CommandResult {
+        success: true,
+        message: "Swarm simulators stopped".to_string(),
+    })
+}
+
+// Mapped gamepad buttons take the same paths as the UI buttons; the result is
+// reported as `gamepad-action` so the UI can follow along
+async fn run_gamepad_action(app_handle: tauri::AppHandle, action: GamepadAction) {
+    let state = app_handle.state::<AppState>();
+    println!("[Gamepad] 🎮 {:?}", action);
+    
+    let result = match action {
+        GamepadAction::Takeoff => takeoff_drone(&state, &app_handle).await,
+        GamepadAction::Land => {
+            end_mission(&state, "Landed from the gamepad");
+            land_drone(&state).await
+        }
+        GamepadAction::Emergency => {
+            end_mission(&state, "Emergency stop from the gamepad");
+            emergency_stop(&state).await
+        }
+        _ => match action.flip_direction() {
+            Some(direction) => send_tello_command(&state, TelloCommand::Flip(direction)).await,
+            None => return,
+        },
+    };
+    
+    let payload = match result {
+        Ok(result) => result,
+        Err(e) => CommandResult {
+            success: false,
+            message: e.to_string(),
+        },
+    };
+    if let Err(e) = app_handle.emit_all("gamepad-action", GamepadActionEvent { action, result: payload }) {
+        eprintln!("[Gamepad] ❌ Failed to emit gamepad-action: {}", e);
+    }
+}
+
+#[derive(Serialize, Clone)]
+struct GamepadActionEvent {
+    action: GamepadAction,
+    result: CommandResult,
+}
+
+#[tauri::command]
+async fn get_gamepad_config(state: State<'_, AppState>) -> Result<GamepadConfig, TelloError> {
+    Ok(state.gamepad_config.lock().unwrap().clone())
+}
+
+#[tauri::command]
+async fn set_gamepad_config(
+    state: State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+    config: GamepadConfig,
+) -> Result<CommandResult, TelloError> {
+    config.validate()?;
+    
+    let app_data_dir = app_handle.path_resolver().app_data_dir()
+        .ok_or_else(|| TelloError::Io("Could not find app data directory".to_string()))?;
+    config.save(&GamepadConfig::path(&app_data_dir))?;
+    
+    // The input task picks this up on its next tick
+    *state.gamepad_config.lock().unwrap() = config;
+    
+    Ok(CommandResult {
+        success: true,
+        message: "Gamepad mapping updated".to_string(),
+    })
+}
+
+#[tauri::command]
+async fn get_gamepad_status(state: State<'_, AppState>) -> Result<GamepadStatus, TelloError> {
+    let gamepad = state.gamepad.lock().unwrap();
+    Ok(gamepad.as_ref().map(GamepadInput::status).unwrap_or_default())
+}
+
+// Virtual input device: drives the gamepad pipeline without hardware
+#[tauri::command]
+async fn send_virtual_gamepad_event(state: State<'_, AppState>, event: PadEvent) -> Result<CommandResult, TelloError> {
+    let gamepad = state.gamepad.lock().unwrap();
+    gamepad.as_ref().ok_or_else(|| TelloError::InvalidState("Gamepad input is not running".to_string()))?.inject(event);
+    
+    Ok(CommandResult {
+        success: true,
+        message: "sent".to_string(),
+    })
 }
 
 // Face recognition commands
 
 #[tauri::command]
-async fn list_face_models() -> Result<Vec<FaceModel>, String> {
+async fn list_face_models() -> Result<Vec<FaceModel>, TelloError> {
     // TODO: Implement by calling Python script or listing model files
     Ok(vec![])
 }
 
 #[tauri::command]
-async fn train_face_model(name: String) -> Result<CommandResult, String> {
+async fn train_face_model(name: String) -> Result<CommandResult, TelloError> {
     // TODO: Call Python training script
     println!("Training face model for: {}", name);
     Ok(CommandResult {
@@ -492,7 +1633,7 @@ async fn train_face_model(name: String) -> Result<CommandResult, String> {
 }
 
 #[tauri::command]
-async fn delete_face_model(name: String) -> Result<CommandResult, String> {
+async fn delete_face_model(name: String) -> Result<CommandResult, TelloError> {
     // TODO: Delete model files
     println!("Deleting face model: {}", name);
     Ok(CommandResult {
@@ -502,7 +1643,7 @@ async fn delete_face_model(name: String) -> Result<CommandResult, String> {
 }
 
 #[tauri::command]
-async fn start_face_recognition(model_name: String) -> Result<CommandResult, String> {
+async fn start_face_recognition(model_name: String) -> Result<CommandResult, TelloError> {
     // TODO: Start recognition loop
     println!("Starting face recognition with model: {}", model_name);
     Ok(CommandResult {
@@ -512,7 +1653,7 @@ async fn start_face_recognition(model_name: String) -> Result<CommandResult, Str
 }
 
 #[tauri::command]
-async fn stop_face_recognition() -> Result<CommandResult, String> {
+async fn stop_face_recognition() -> Result<CommandResult, TelloError> {
     // TODO: Stop recognition
     Ok(CommandResult {
         success: true,
@@ -521,18 +1662,18 @@ async fn stop_face_recognition() -> Result<CommandResult, String> {
 }
 
 #[tauri::command]
-async fn open_images_folder() -> Result<CommandResult, String> {
+async fn open_images_folder() -> Result<CommandResult, TelloError> {
     use std::process::Command;
     
     // Get the standard pictures directory
     let pictures_dir = dirs::picture_dir()
-        .ok_or("Could not find pictures directory")?;
+        .ok_or_else(|| TelloError::Io("Could not find pictures directory".to_string()))?;
     
     let tello_dir = pictures_dir.join("Tello");
     
     // Create directory if it doesn't exist
     std::fs::create_dir_all(&tello_dir)
-        .map_err(|e| format!("Failed to create directory: {}", e))?;
+        .map_err(|e| TelloError::Io(format!("Failed to create directory: {}", e)))?;
     
     // Open folder with system default file manager
     #[cfg(target_os = "windows")]
@@ -540,7 +1681,7 @@ async fn open_images_folder() -> Result<CommandResult, String> {
         Command::new("explorer")
             .arg(&tello_dir)
             .spawn()
-            .map_err(|e| format!("Failed to open folder: {}", e))?;
+            .map_err(|e| TelloError::Io(format!("Failed to open folder: {}", e)))?;
     }
     
     #[cfg(target_os = "macos")]
@@ -548,7 +1689,7 @@ async fn open_images_folder() -> Result<CommandResult, String> {
         Command::new("open")
             .arg(&tello_dir)
             .spawn()
-            .map_err(|e| format!("Failed to open folder: {}", e))?;
+            .map_err(|e| TelloError::Io(format!("Failed to open folder: {}", e)))?;
     }
     
     #[cfg(target_os = "linux")]
@@ -556,7 +1697,7 @@ async fn open_images_folder() -> Result<CommandResult, String> {
         Command::new("xdg-open")
             .arg(&tello_dir)
             .spawn()
-            .map_err(|e| format!("Failed to open folder: {}", e))?;
+            .map_err(|e| TelloError::Io(format!("Failed to open folder: {}", e)))?;
     }
     
     Ok(CommandResult {
@@ -568,6 +1709,20 @@ async fn open_images_folder() -> Result<CommandResult, String> {
 fn main() {
     tauri::Builder::default()
         .manage(AppState::default())
+        .setup(|app| {
+            if let Some(app_data_dir) = app.path_resolver().app_data_dir() {
+                let endpoint = DroneEndpoint::load(&DroneEndpoint::path(&app_data_dir));
+                println!("[Setup] 📡 Drone endpoint: {}", endpoint.command_addr());
+                *app.state::<AppState>().endpoint.lock().unwrap() = endpoint;
+                *app.state::<AppState>().battery_policy.lock().unwrap() = BatteryPolicy::load(&BatteryPolicy::path(&app_data_dir));
+                *app.state::<AppState>().gamepad_config.lock().unwrap() = GamepadConfig::load(&GamepadConfig::path(&app_data_dir));
+                *app.state::<AppState>().swarm_config.lock().unwrap() = SwarmConfig::load(&SwarmConfig::path(&app_data_dir));
+            }
+            
+            let state = app.state::<AppState>();
+            *state.gamepad.lock().unwrap() = Some(GamepadInput::start(Arc::clone(&state.gamepad_config), app.handle()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             connect_drone,
             disconnect_drone,
@@ -578,12 +1733,64 @@ fn main() {
             send_rc_control,
             set_speed,
             flip,
+            enable_mission_pads,
+            disable_mission_pads,
             get_drone_state,
             get_battery,
             get_telemetry,
             start_video_stream,
             stop_video_stream,
             set_video_bitrate,
+            set_video_quality,
+            start_recording,
+            stop_recording,
+            take_picture,
+            list_gallery_items,
+            delete_gallery_item,
+            get_thumbnail,
+            list_flight_logs,
+            export_flight_log,
+            start_replay,
+            pause_replay,
+            seek_replay,
+            set_replay_speed,
+            stop_replay,
+            start_simulator,
+            stop_simulator,
+            get_drone_endpoint,
+            set_drone_endpoint,
+            diagnose_network,
+            get_geofence,
+            set_geofence,
+            get_watchdog_config,
+            set_watchdog_config,
+            get_rc_stream_config,
+            set_rc_stream_config,
+            get_battery_policy,
+            set_battery_policy,
+            get_gamepad_config,
+            set_gamepad_config,
+            get_gamepad_status,
+            send_virtual_gamepad_event,
+            list_missions,
+            save_mission,
+            delete_mission,
+            start_mission,
+            pause_mission,
+            abort_mission,
+            get_mission_status,
+            run_script,
+            stop_script,
+            get_swarm_config,
+            set_swarm_config,
+            connect_swarm,
+            disconnect_swarm,
+            get_swarm_status,
+            swarm_command,
+            run_swarm_steps,
+            stop_swarm_run,
+            start_swarm_simulators,
+            stop_swarm_simulators,
             list_face_models,
             train_face_model,
             delete_face_model,
@@ -0,0 +1,502 @@
+// Waypoint missions
+// A mission is a named list of steps flown one after another over the command
+// channel; every step waits for the drone's reply before the next one starts.
+// Missions are kept as JSON files in the app data directory. Pause takes
+// effect between steps (a hover keeps counting down only while running).
+// Abort stops the executor between steps and ends the wait for a manoeuvre's
+// reply, but never the bookkeeping after a takeoff or landing that was sent.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Manager;
+use tokio::sync::watch;
+
+use crate::error::TelloError;
+use crate::tello_command::{Direction, MissionPad, Rotation, TelloCommand};
+use crate::{AppState, CommandResult};
+
+const DIRECTORY: &str = "missions";
+const MAX_NAME_LEN: usize = 64;
+const MAX_HOVER_SECS: f64 = 600.0;
+// How often a paused mission or a hover checks its controls
+const POLL: Duration = Duration::from_millis(100);
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MissionStep {
+    Takeoff,
+    Move { direction: Direction, distance: i32 },
+    // Relative to the drone: x forward, y left, z up (cm). With a mission pad
+    // (1-8, -1 any, -2 nearest) relative to that pad instead, z above it
+    Go {
+        x: i32,
+        y: i32,
+        z: i32,
+        speed: i32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pad: Option<i32>,
+    },
+    Curve {
+        x1: i32,
+        y1: i32,
+        z1: i32,
+        x2: i32,
+        y2: i32,
+        z2: i32,
+        speed: i32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pad: Option<i32>,
+    },
+    // Fly to (x, y, z) relative to pad1, find pad2 there and turn to yaw
+    Jump { x: i32, y: i32, z: i32, speed: i32, yaw: i32, pad1: i32, pad2: i32 },
+    // Mission pad detection (Tello EDU): 0 = downward camera, 1 = forward, 2 = both
+    PadsOn { direction: i32 },
+    PadsOff,
+    Rotate { rotation: Rotation, degrees: i32 },
+    Hover { seconds: f64 },
+    TakePicture,
+    StartRecording,
+    StopRecording,
+    Land,
+}
+
+impl MissionStep {
+    // The SDK command a flight step sends; None for steps the app runs itself
+    pub fn command(&self) -> Result<Option<TelloCommand>, TelloError> {
+        let command = match *self {
+            Self::Takeoff => TelloCommand::Takeoff,
+            Self::Land => TelloCommand::Land,
+            Self::Move { direction, distance } => TelloCommand::movement(direction, distance)?,
+            Self::Go { x, y, z, speed, pad } => TelloCommand::go(x, y, z, speed, pad.map(MissionPad::new).transpose()?)?,
+            Self::Curve { x1, y1, z1, x2, y2, z2, speed, pad } => {
+                TelloCommand::curve(x1, y1, z1, x2, y2, z2, speed, pad.map(MissionPad::new).transpose()?)?
+            }
+            Self::Jump { x, y, z, speed, yaw, pad1, pad2 } => {
+                TelloCommand::jump(x, y, z, speed, yaw, MissionPad::new(pad1)?, MissionPad::new(pad2)?)?
+            }
+            Self::Rotate { rotation, degrees } => TelloCommand::rotate(rotation, degrees)?,
+            Self::PadsOff => TelloCommand::MissionPadOff,
+            Self::PadsOn { .. } | Self::Hover { .. } | Self::TakePicture | Self::StartRecording | Self::StopRecording => return Ok(None),
+        };
+        Ok(Some(command))
+    }
+
+    fn validate(&self) -> Result<(), TelloError> {
+        match *self {
+            Self::Hover { seconds } if !(seconds > 0.0 && seconds <= MAX_HOVER_SECS) => {
+                return Err(TelloError::InvalidArgument(format!("Hover must be between 0 and {}s, got {}", MAX_HOVER_SECS, seconds)));
+            }
+            Self::PadsOn { direction } => {
+                TelloCommand::mission_pad_direction(direction)?;
+            }
+            _ => {}
+        }
+        self.command().map(|_| ())
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Hover { seconds } => format!("hover {}s", seconds),
+            Self::PadsOn { direction } => format!("mon, mdirection {}", direction),
+            Self::TakePicture => "take picture".to_string(),
+            Self::StartRecording => "start recording".to_string(),
+            Self::StopRecording => "stop recording".to_string(),
+            _ => match self.command() {
+                Ok(Some(command)) => command.to_string(),
+                _ => format!("{:?}", self),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Mission {
+    pub name: String,
+    pub steps: Vec<MissionStep>,
+}
+
+impl Mission {
+    pub fn validate(&self) -> Result<(), TelloError> {
+        check_name(&self.name)?;
+        if self.steps.is_empty() {
+            return Err(TelloError::InvalidArgument("A mission needs at least one step".to_string()));
+        }
+        for (index, step) in self.steps.iter().enumerate() {
+            step.validate().map_err(|e| TelloError::InvalidArgument(format!("Step {}: {}", index + 1, e)))?;
+        }
+        Ok(())
+    }
+
+    pub fn directory(app_data_dir: &Path) -> PathBuf {
+        app_data_dir.join(DIRECTORY)
+    }
+
+    pub fn load(directory: &Path, name: &str) -> Result<Self, TelloError> {
+        let path = mission_path(directory, name)?;
+        let json = std::fs::read_to_string(&path).map_err(|e| TelloError::Io(format!("Failed to read mission '{}': {}", name, e)))?;
+        serde_json::from_str(&json).map_err(|e| TelloError::Io(format!("Invalid mission file {}: {}", path.display(), e)))
+    }
+
+    pub fn save(&self, directory: &Path) -> Result<(), TelloError> {
+        self.validate()?;
+        std::fs::create_dir_all(directory).map_err(|e| TelloError::Io(format!("Failed to create directory: {}", e)))?;
+        let json = serde_json::to_string_pretty(self).map_err(|e| TelloError::Io(format!("Failed to serialize mission: {}", e)))?;
+        std::fs::write(mission_path(directory, &self.name)?, json).map_err(|e| TelloError::Io(format!("Failed to save mission: {}", e)))
+    }
+}
+
+// Every readable mission in `directory`, by name
+pub fn list(directory: &Path) -> Result<Vec<Mission>, TelloError> {
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(TelloError::Io(format!("Failed to read {}: {}", directory.display(), e))),
+    };
+
+    let mut missions: Vec<Mission> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|path| {
+            let mission = std::fs::read_to_string(&path).map_err(|e| e.to_string())
+                .and_then(|json| serde_json::from_str(&json).map_err(|e| e.to_string()));
+            mission.map_err(|e| eprintln!("[Mission] ⚠️ Skipping {}: {}", path.display(), e)).ok()
+        })
+        .collect();
+    missions.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(missions)
+}
+
+pub fn delete(directory: &Path, name: &str) -> Result<(), TelloError> {
+    std::fs::remove_file(mission_path(directory, name)?).map_err(|e| TelloError::Io(format!("Failed to delete mission '{}': {}", name, e)))
+}
+
+// The name doubles as the file name, so it must not reach outside `directory`
+fn check_name(name: &str) -> Result<(), TelloError> {
+    let valid_chars = name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, ' ' | '-' | '_'));
+    if name.trim().is_empty() || name.len() > MAX_NAME_LEN || !valid_chars {
+        return Err(TelloError::InvalidArgument(format!(
+            "Mission names must be 1-{} letters, digits, spaces, '-' or '_'",
+            MAX_NAME_LEN
+        )));
+    }
+    Ok(())
+}
+
+fn mission_path(directory: &Path, name: &str) -> Result<PathBuf, TelloError> {
+    check_name(name)?;
+    Ok(directory.join(format!("{}.json", name)))
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MissionState {
+    Running,
+    Paused,
+    Completed,
+    Aborted,
+    Failed,
+}
+
+// Payload of the `mission-progress` event
+#[derive(Serialize, Clone, Debug)]
+pub struct MissionStatus {
+    pub name: String,
+    // Index of the current step; equals `total` once completed
+    pub step: usize,
+    pub total: usize,
+    pub state: MissionState,
+    pub message: String,
+}
+
+impl MissionStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, MissionState::Completed | MissionState::Aborted | MissionState::Failed)
+    }
+}
+
+struct MissionControl {
+    paused: bool,
+    status: MissionStatus,
+}
+
+// Where take_picture and recording steps save to; None uses the defaults
+pub struct MediaDirectories {
+    pub pictures: Option<String>,
+    pub videos: Option<String>,
+}
+
+// The executor is never aborted from outside: cancelling it at an arbitrary
+// `.await` could leave a drone that took off marked as landed. It watches
+// `cancel` instead and winds down at the next safe point.
+pub struct MissionRun {
+    control: Arc<Mutex<MissionControl>>,
+    cancel: watch::Sender<bool>,
+    app_handle: tauri::AppHandle,
+}
+
+impl MissionRun {
+    pub fn start(mission: Mission, directories: MediaDirectories, app_handle: tauri::AppHandle) -> Self {
+        println!("[Mission] 🗺️ Starting '{}' ({} steps)", mission.name, mission.steps.len());
+
+        let control = Arc::new(Mutex::new(MissionControl {
+            paused: false,
+            status: MissionStatus {
+                name: mission.name.clone(),
+                step: 0,
+                total: mission.steps.len(),
+                state: MissionState::Running,
+                message: "Starting".to_string(),
+            },
+        }));
+
+        let (cancel, mut cancelled) = watch::channel(false);
+        let task_control = Arc::clone(&control);
+        let task_handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            let total = mission.steps.len();
+            for (index, step) in mission.steps.iter().enumerate() {
+                wait_while_paused(&task_control, &cancelled).await;
+                if *cancelled.borrow() {
+                    return;
+                }
+
+                let description = step.describe();
+                println!("[Mission] ▶️ '{}' step {}/{}: {}", mission.name, index + 1, total, description);
+                update(&task_control, &task_handle, |status| {
+                    status.step = index;
+                    status.message = description.clone();
+                });
+
+                let result = run_step(step, &directories, &task_control, &task_handle, &mut cancelled).await;
+                // An aborted step usually fails ("error Stopped"); the abort already said why
+                if *cancelled.borrow() {
+                    return;
+                }
+                if let Err(message) = result {
+                    eprintln!("[Mission] ❌ '{}' failed at step {} ({}): {}", mission.name, index + 1, description, message);
+                    finish(&task_control, &task_handle, |status| {
+                        status.state = MissionState::Failed;
+                        status.message = format!("Step {} ({}) failed: {}", index + 1, description, message);
+                    });
+                    return;
+                }
+            }
+
+            println!("[Mission] ✅ '{}' completed", mission.name);
+            finish(&task_control, &task_handle, |status| {
+                status.step = total;
+                status.state = MissionState::Completed;
+                status.message = "Completed".to_string();
+            });
+        });
+
+        Self { control, cancel, app_handle }
+    }
+
+    pub fn set_paused(&self, paused: bool) -> Result<MissionStatus, TelloError> {
+        if self.status().is_finished() {
+            return Err(TelloError::InvalidState("The mission has already ended".to_string()));
+        }
+        self.control.lock().unwrap().paused = paused;
+        println!("[Mission] {} Mission {}", if paused { "⏸️" } else { "▶️" }, if paused { "paused" } else { "resumed" });
+        Ok(update(&self.control, &self.app_handle, |status| {
+            status.state = if paused { MissionState::Paused } else { MissionState::Running };
+        }))
+    }
+
+    // No further step is sent; what the drone does next is up to the caller
+    pub fn abort(&self, reason: &str) -> MissionStatus {
+        if self.status().is_finished() {
+            return self.status();
+        }
+        self.cancel.send_replace(true);
+        println!("[Mission] ⏹️ Aborted '{}': {}", self.status().name, reason);
+
+        finish(&self.control, &self.app_handle, |status| {
+            status.state = MissionState::Aborted;
+            status.message = reason.to_string();
+        })
+    }
+
+    pub fn status(&self) -> MissionStatus {
+        self.control.lock().unwrap().status.clone()
+    }
+}
+
+impl Drop for MissionRun {
+    fn drop(&mut self) {
+        self.cancel.send_replace(true);
+    }
+}
+
+// Takeoff, landing and pad detection update the drone state once the drone has
+// answered, so they always run to the end; an abort only cuts short the wait
+// for a plain manoeuvre's reply, which stays queued on the command channel.
+async fn run_step(
+    step: &MissionStep,
+    directories: &MediaDirectories,
+    control: &Mutex<MissionControl>,
+    app_handle: &tauri::AppHandle,
+    cancelled: &mut watch::Receiver<bool>,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+
+    let result = match step {
+        MissionStep::Takeoff => crate::takeoff_drone(&state, app_handle).await,
+        MissionStep::Land => crate::land_drone(&state).await,
+        MissionStep::Hover { seconds } => {
+            hover(Duration::from_secs_f64(*seconds), control, cancelled).await;
+            return Ok(());
+        }
+        MissionStep::TakePicture => crate::capture_picture(&state, directories.pictures.clone())
+            .await
+            .map(|image| succeeded(format!("Saved {}", image.path))),
+        MissionStep::StartRecording => crate::start_recorder(&state, directories.videos.clone()),
+        MissionStep::StopRecording => crate::stop_recorder(&state).map(|info| succeeded(format!("Saved {}", info.path))),
+        MissionStep::PadsOn { direction } => crate::enable_pad_detection(&state, *direction).await,
+        MissionStep::PadsOff => crate::disable_pad_detection(&state).await,
+        _ => match step.command() {
+            Ok(Some(command)) => tokio::select! {
+                result = crate::send_tello_command(&state, command) => result,
+                _ = cancelled.wait_for(|cancelled| *cancelled) => return Ok(()),
+            },
+            Ok(None) => return Ok(()),
+            Err(e) => Err(e),
+        },
+    };
+
+    match result {
+        Ok(result) if result.success => Ok(()),
+        Ok(result) => Err(result.message),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn succeeded(message: String) -> CommandResult {
+    CommandResult { success: true, message }
+}
+
+// The hover only counts down while the mission is running
+async fn hover(duration: Duration, control: &Mutex<MissionControl>, cancelled: &watch::Receiver<bool>) {
+    let mut remaining = duration;
+    while !remaining.is_zero() && !*cancelled.borrow() {
+        let tick = remaining.min(POLL);
+        tokio::time::sleep(tick).await;
+        if !control.lock().unwrap().paused {
+            remaining -= tick;
+        }
+    }
+}
+
+async fn wait_while_paused(control: &Mutex<MissionControl>, cancelled: &watch::Receiver<bool>) {
+    while control.lock().unwrap().paused && !*cancelled.borrow() {
+        tokio::time::sleep(POLL).await;
+    }
+}
+
+// Changes the status and sends it as `mission-progress`
+fn update(control: &Mutex<MissionControl>, app_handle: &tauri::AppHandle, change: impl FnOnce(&mut MissionStatus)) -> MissionStatus {
+    let status = {
+        let mut control = control.lock().unwrap();
+        change(&mut control.status);
+        control.status.clone()
+    };
+    if let Err(e) = app_handle.emit_all("mission-progress", status.clone()) {
+        eprintln!("[Mission] ❌ Failed to emit mission-progress: {}", e);
+    }
+    status
+}
+
+// Moves a running mission to its final state; whichever of the executor and
+// an abort gets here first decides it
+fn finish(control: &Mutex<MissionControl>, app_handle: &tauri::AppHandle, change: impl FnOnce(&mut MissionStatus)) -> MissionStatus {
+    update(control, app_handle, |status| {
+        if !status.is_finished() {
+            change(status);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_validates_steps() {
+        let json = r#"{
+            "name": "Roof inspection",
+            "steps": [
+                { "type": "takeoff" },
+                { "type": "move", "direction": "up", "distance": 50 },
+                { "type": "go", "x": 100, "y": 0, "z": 0, "speed": 30 },
+                { "type": "rotate", "rotation": "counter_clockwise", "degrees": 90 },
+                { "type": "hover", "seconds": 2.5 },
+                { "type": "take_picture" },
+                { "type": "land" }
+            ]
+        }"#;
+        let mission: Mission = serde_json::from_str(json).unwrap();
+        assert!(mission.validate().is_ok());
+        assert_eq!(mission.steps[2].describe(), "go 100 0 0 30");
+        assert_eq!(mission.steps[3].describe(), "ccw 90");
+        assert_eq!(mission.steps[4].command().unwrap(), None);
+
+        let bad = Mission {
+            steps: vec![MissionStep::Takeoff, MissionStep::Move { direction: Direction::Forward, distance: 5 }],
+            ..mission.clone()
+        };
+        let error = bad.validate().unwrap_err().to_string();
+        assert!(error.contains("Step 2"), "{}", error);
+
+        assert!(Mission { steps: vec![MissionStep::Hover { seconds: 0.0 }], ..mission.clone() }.validate().is_err());
+        assert!(Mission { steps: Vec::new(), ..mission.clone() }.validate().is_err());
+    }
+
+    #[test]
+    fn builds_mission_pad_steps() {
+        let json = r#"[
+            { "type": "pads_on", "direction": 0 },
+            { "type": "go", "x": 0, "y": 0, "z": 80, "speed": 30, "pad": 1 },
+            { "type": "jump", "x": 100, "y": 0, "z": 80, "speed": 40, "yaw": 0, "pad1": 1, "pad2": 2 },
+            { "type": "pads_off" }
+        ]"#;
+        let steps: Vec<MissionStep> = serde_json::from_str(json).unwrap();
+        let describe: Vec<String> = steps.iter().map(MissionStep::describe).collect();
+        assert_eq!(describe, ["mon, mdirection 0", "go 0 0 80 30 m1", "jump 100 0 80 40 0 m1 m2", "moff"]);
+
+        // A pad-less go round-trips without a pad field
+        let go = MissionStep::Go { x: 100, y: 0, z: 0, speed: 30, pad: None };
+        assert!(!serde_json::to_string(&go).unwrap().contains("pad"));
+
+        assert!(MissionStep::Go { x: 100, y: 0, z: 0, speed: 30, pad: Some(9) }.validate().is_err());
+        assert!(MissionStep::PadsOn { direction: 3 }.validate().is_err());
+    }
+
+    #[test]
+    fn rejects_names_that_escape_the_directory() {
+        assert!(check_name("Survey 2_north-side").is_ok());
+        for name in ["", "   ", "../escape", "a/b", "dot.json", &"x".repeat(MAX_NAME_LEN + 1)] {
+            assert!(check_name(name).is_err(), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn saves_lists_and_deletes() {
+        let directory = std::env::temp_dir().join(format!("tello_missions_{}", std::process::id()));
+        let mission = Mission {
+            name: "Square".to_string(),
+            steps: vec![MissionStep::Takeoff, MissionStep::Hover { seconds: 1.0 }, MissionStep::Land],
+        };
+
+        mission.save(&directory).unwrap();
+        assert_eq!(Mission::load(&directory, "Square").unwrap(), mission);
+        assert_eq!(list(&directory).unwrap(), vec![mission]);
+
+        delete(&directory, "Square").unwrap();
+        assert!(list(&directory).unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
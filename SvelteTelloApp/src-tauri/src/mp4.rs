@@ -0,0 +1,543 @@
+// Fragmented MP4 writer for H.264 video
+// Writes the header (ftyp + moov) up front and then one moof/mdat pair per
+// fragment, so a file cut short by a crash still plays up to the last fragment.
+// The reader at the bottom pulls enough back out of a file for the gallery.
+
+use std::fs::File;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+pub const TIMESCALE: u32 = 90_000;
+
+// Start a new fragment at every keyframe, or after this long without one
+const MAX_FRAGMENT_DURATION: u64 = TIMESCALE as u64;
+
+const SAMPLE_FLAGS_SYNC: u32 = 0x0200_0000;
+const SAMPLE_FLAGS_NON_SYNC: u32 = 0x0101_0000;
+
+struct Sample {
+    data: Vec<u8>,
+    duration: u32,
+    keyframe: bool,
+}
+
+pub struct Mp4Writer {
+    file: BufWriter<File>,
+    pending: Vec<Sample>,
+    pending_duration: u64,
+    decode_time: u64,
+    sequence: u32,
+}
+
+impl Mp4Writer {
+    // `sps` and `pps` are NAL units including their header byte. Fails if
+    // `path` exists: an earlier recording is never overwritten.
+    pub fn create(path: &Path, sps: &[u8], pps: &[u8], width: u32, height: u32) -> std::io::Result<Self> {
+        let mut file = BufWriter::new(File::options().write(true).create_new(true).open(path)?);
+
+        file.write_all(&ftyp())?;
+        file.write_all(&moov(sps, pps, width, height))?;
+        file.flush()?;
+
+        Ok(Self {
+            file,
+            pending: Vec::new(),
+            pending_duration: 0,
+            decode_time: 0,
+            sequence: 0,
+        })
+    }
+
+    // `data` is one access unit in AVCC form (length-prefixed NAL units)
+    pub fn write_sample(&mut self, data: Vec<u8>, duration: u32, keyframe: bool) -> std::io::Result<()> {
+        if !self.pending.is_empty() && (keyframe || self.pending_duration >= MAX_FRAGMENT_DURATION) {
+            self.flush_fragment()?;
+        }
+
+        self.pending_duration += duration as u64;
+        self.pending.push(Sample { data, duration, keyframe });
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> std::io::Result<()> {
+        self.flush_fragment()?;
+        self.file.flush()?;
+        self.file.get_ref().sync_all()
+    }
+
+    fn flush_fragment(&mut self) -> std::io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        self.sequence += 1;
+        let samples = std::mem::take(&mut self.pending);
+
+        // trun's data offset points past the moof and the mdat header, so build
+        // the moof once to learn its size and then again with the real offset
+        let moof_size = moof(self.sequence, self.decode_time, &samples, 0).len();
+        let moof = moof(self.sequence, self.decode_time, &samples, moof_size as u32 + 8);
+
+        let payload_size: usize = samples.iter().map(|s| s.data.len()).sum();
+        self.file.write_all(&moof)?;
+        self.file.write_all(&((payload_size + 8) as u32).to_be_bytes())?;
+        self.file.write_all(b"mdat")?;
+        for sample in &samples {
+            self.file.write_all(&sample.data)?;
+        }
+        self.file.flush()?;
+
+        self.decode_time += self.pending_duration;
+        self.pending_duration = 0;
+        Ok(())
+    }
+}
+
+fn mp4_box(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 8);
+    out.extend_from_slice(&((payload.len() + 8) as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(payload);
+    out
+}
+
+fn full_box(kind: &[u8; 4], version: u8, flags: u32, payload: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(payload.len() + 4);
+    body.extend_from_slice(&((version as u32) << 24 | flags).to_be_bytes());
+    body.extend_from_slice(payload);
+    mp4_box(kind, &body)
+}
+
+fn container(kind: &[u8; 4], children: &[Vec<u8>]) -> Vec<u8> {
+    mp4_box(kind, &children.concat())
+}
+
+const UNITY_MATRIX: [u32; 9] = [0x0001_0000, 0, 0, 0, 0x0001_0000, 0, 0, 0, 0x4000_0000];
+
+fn matrix() -> Vec<u8> {
+    UNITY_MATRIX.iter().flat_map(|v| v.to_be_bytes()).collect()
+}
+
+fn ftyp() -> Vec<u8> {
+    let mut payload = Vec::new();
+    payload.extend_from_slice(b"isom");
+    payload.extend_from_slice(&0x200u32.to_be_bytes());
+    for brand in [b"isom", b"iso6", b"avc1", b"mp41"] {
+        payload.extend_from_slice(brand);
+    }
+    mp4_box(b"ftyp", &payload)
+}
+
+fn moov(sps: &[u8], pps: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let mut mvhd = Vec::new();
+    mvhd.extend_from_slice(&[0; 8]); // creation/modification time
+    mvhd.extend_from_slice(&1000u32.to_be_bytes()); // timescale
+    mvhd.extend_from_slice(&0u32.to_be_bytes()); // duration (unknown, fragmented)
+    mvhd.extend_from_slice(&0x0001_0000u32.to_be_bytes()); // rate 1.0
+    mvhd.extend_from_slice(&0x0100u16.to_be_bytes()); // volume 1.0
+    mvhd.extend_from_slice(&[0; 10]);
+    mvhd.extend(matrix());
+    mvhd.extend_from_slice(&[0; 24]);
+    mvhd.extend_from_slice(&2u32.to_be_bytes()); // next_track_ID
+
+    let mut tkhd = Vec::new();
+    tkhd.extend_from_slice(&[0; 8]);
+    tkhd.extend_from_slice(&1u32.to_be_bytes()); // track_ID
+    tkhd.extend_from_slice(&[0; 4]);
+    tkhd.extend_from_slice(&0u32.to_be_bytes()); // duration
+    tkhd.extend_from_slice(&[0; 8]);
+    tkhd.extend_from_slice(&[0; 8]); // layer, alternate_group, volume, reserved
+    tkhd.extend(matrix());
+    tkhd.extend_from_slice(&(width << 16).to_be_bytes());
+    tkhd.extend_from_slice(&(height << 16).to_be_bytes());
+
+    let mut mdhd = Vec::new();
+    mdhd.extend_from_slice(&[0; 8]);
+    mdhd.extend_from_slice(&TIMESCALE.to_be_bytes());
+    mdhd.extend_from_slice(&0u32.to_be_bytes());
+    mdhd.extend_from_slice(&0x55c4u16.to_be_bytes()); // language "und"
+    mdhd.extend_from_slice(&[0; 2]);
+
+    let mut hdlr = Vec::new();
+    hdlr.extend_from_slice(&[0; 4]);
+    hdlr.extend_from_slice(b"vide");
+    hdlr.extend_from_slice(&[0; 12]);
+    hdlr.extend_from_slice(b"VideoHandler\0");
+
+    let dref = full_box(b"dref", 0, 0, &[&1u32.to_be_bytes()[..], &full_box(b"url ", 0, 1, &[])].concat());
+
+    let empty_table = 0u32.to_be_bytes();
+    let stbl = container(
+        b"stbl",
+        &[
+            full_box(b"stsd", 0, 0, &[&1u32.to_be_bytes()[..], &avc1(sps, pps, width, height)].concat()),
+            full_box(b"stts", 0, 0, &empty_table),
+            full_box(b"stsc", 0, 0, &empty_table),
+            full_box(b"stsz", 0, 0, &[0u8; 8]),
+            full_box(b"stco", 0, 0, &empty_table),
+        ],
+    );
+
+    let minf = container(
+        b"minf",
+        &[full_box(b"vmhd", 0, 1, &[0; 8]), container(b"dinf", &[dref]), stbl],
+    );
+
+    let trak = container(
+        b"trak",
+        &[
+            full_box(b"tkhd", 0, 3, &tkhd),
+            container(b"mdia", &[full_box(b"mdhd", 0, 0, &mdhd), full_box(b"hdlr", 0, 0, &hdlr), minf]),
+        ],
+    );
+
+    let mut trex = Vec::new();
+    trex.extend_from_slice(&1u32.to_be_bytes()); // track_ID
+    trex.extend_from_slice(&1u32.to_be_bytes()); // default_sample_description_index
+    trex.extend_from_slice(&[0; 12]);
+
+    container(
+        b"moov",
+        &[full_box(b"mvhd", 0, 0, &mvhd), trak, container(b"mvex", &[full_box(b"trex", 0, 0, &trex)])],
+    )
+}
+
+fn avc1(sps: &[u8], pps: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let mut avcc = vec![1, sps[1], sps[2], sps[3], 0xff, 0xe1];
+    avcc.extend_from_slice(&(sps.len() as u16).to_be_bytes());
+    avcc.extend_from_slice(sps);
+    avcc.push(1);
+    avcc.extend_from_slice(&(pps.len() as u16).to_be_bytes());
+    avcc.extend_from_slice(pps);
+
+    let mut entry = Vec::new();
+    entry.extend_from_slice(&[0; 6]);
+    entry.extend_from_slice(&1u16.to_be_bytes()); // data_reference_index
+    entry.extend_from_slice(&[0; 16]);
+    entry.extend_from_slice(&(width as u16).to_be_bytes());
+    entry.extend_from_slice(&(height as u16).to_be_bytes());
+    entry.extend_from_slice(&0x0048_0000u32.to_be_bytes()); // 72 dpi
+    entry.extend_from_slice(&0x0048_0000u32.to_be_bytes());
+    entry.extend_from_slice(&[0; 4]);
+    entry.extend_from_slice(&1u16.to_be_bytes()); // frame_count
+    entry.extend_from_slice(&[0; 32]); // compressorname
+    entry.extend_from_slice(&0x0018u16.to_be_bytes()); // depth
+    entry.extend_from_slice(&0xffffu16.to_be_bytes());
+    entry.extend(mp4_box(b"avcC", &avcc));
+
+    mp4_box(b"avc1", &entry)
+}
+
+fn moof(sequence: u32, decode_time: u64, samples: &[Sample], data_offset: u32) -> Vec<u8> {
+    let mut trun = Vec::new();
+    trun.extend_from_slice(&(samples.len() as u32).to_be_bytes());
+    trun.extend_from_slice(&data_offset.to_be_bytes());
+    for sample in samples {
+        trun.extend_from_slice(&sample.duration.to_be_bytes());
+        trun.extend_from_slice(&(sample.data.len() as u32).to_be_bytes());
+        let flags = if sample.keyframe { SAMPLE_FLAGS_SYNC } else { SAMPLE_FLAGS_NON_SYNC };
+        trun.extend_from_slice(&flags.to_be_bytes());
+    }
+
+    let traf = container(
+        b"traf",
+        &[
+            // default-base-is-moof
+            full_box(b"tfhd", 0, 0x02_0000, &1u32.to_be_bytes()),
+            full_box(b"tfdt", 1, 0, &decode_time.to_be_bytes()),
+            // data-offset, sample-duration, sample-size and sample-flags present
+            full_box(b"trun", 0, 0x000701, &trun),
+        ],
+    );
+
+    container(b"moof", &[full_box(b"mfhd", 0, 0, &sequence.to_be_bytes()), traf])
+}
+
+// Reading
+
+pub struct Mp4Info {
+    pub width: u32,
+    pub height: u32,
+    pub duration: f64, // seconds
+}
+
+fn invalid(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+}
+
+// A top-level box: where it starts and how big it is
+struct BoxHeader {
+    kind: [u8; 4],
+    offset: u64,
+    header_len: u64,
+    size: u64,
+}
+
+// Walk the top-level boxes without reading their payloads (mdat can be huge)
+fn top_level_boxes(file: &mut File) -> std::io::Result<Vec<BoxHeader>> {
+    let file_len = file.metadata()?.len();
+    let mut boxes = Vec::new();
+    let mut offset = 0;
+
+    while offset + 8 <= file_len {
+        file.seek(SeekFrom::Start(offset))?;
+        let mut header = [0; 8];
+        file.read_exact(&mut header)?;
+
+        let (size, header_len) = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+            0 => (file_len - offset, 8),
+            1 => {
+                let mut large = [0; 8];
+                file.read_exact(&mut large)?;
+                (u64::from_be_bytes(large), 16)
+            }
+            size => (size as u64, 8),
+        };
+        if size < header_len {
+            return Err(invalid("Corrupt box header"));
+        }
+
+        boxes.push(BoxHeader {
+            kind: [header[4], header[5], header[6], header[7]],
+            offset,
+            header_len,
+            size,
+        });
+        offset += size;
+    }
+    Ok(boxes)
+}
+
+fn read_payload(file: &mut File, header: &BoxHeader) -> std::io::Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(header.offset + header.header_len))?;
+    let mut payload = vec![0; (header.size - header.header_len) as usize];
+    file.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+// Child boxes of an in-memory container payload
+fn children(mut data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+    let mut boxes = Vec::new();
+    while data.len() >= 8 {
+        let size = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        let size = if size == 0 { data.len() } else { size };
+        if size < 8 || size > data.len() {
+            break;
+        }
+        boxes.push(([data[4], data[5], data[6], data[7]], &data[8..size]));
+        data = &data[size..];
+    }
+    boxes
+}
+
+fn child<'a>(data: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    children(data).into_iter().find(|(k, _)| k == kind).map(|(_, payload)| payload)
+}
+
+// Follow a path of nested boxes, e.g. trak/mdia/mdhd
+fn find<'a>(data: &'a [u8], path: &[&[u8; 4]]) -> Option<&'a [u8]> {
+    path.iter().try_fold(data, |data, kind| child(data, kind))
+}
+
+fn be_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn be_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn be_u64(data: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(at..at + 8)?.try_into().ok()?))
+}
+
+// The first video track of a moov payload
+fn video_track(moov: &[u8]) -> Option<&[u8]> {
+    children(moov)
+        .into_iter()
+        .filter(|(kind, _)| kind == b"trak")
+        .map(|(_, trak)| trak)
+        .find(|trak| find(trak, &[b"mdia", b"hdlr"]).and_then(|hdlr| hdlr.get(8..12)) == Some(b"vide"))
+}
+
+// (timescale, duration) from mdhd
+fn media_time(trak: &[u8]) -> Option<(u32, u64)> {
+    let mdhd = find(trak, &[b"mdia", b"mdhd"])?;
+    match mdhd[0] {
+        1 => Some((be_u32(mdhd, 20)?, be_u64(mdhd, 24)?)),
+        _ => Some((be_u32(mdhd, 12)?, be_u32(mdhd, 16)? as u64)),
+    }
+}
+
+// Picture size from tkhd (16.16 fixed point)
+fn track_dimensions(trak: &[u8]) -> Option<(u32, u32)> {
+    let tkhd = find(trak, &[b"tkhd"])?;
+    let at = if tkhd[0] == 1 { 4 + 32 } else { 4 + 20 } + 8 + 8 + 36;
+    Some((be_u32(tkhd, at)? >> 16, be_u32(tkhd, at + 4)? >> 16))
+}
+
+// One track run of a fragment
+struct TrackRun {
+    data_offset: u64, // absolute position of the first sample
+    durations: Vec<u32>,
+    sizes: Vec<u32>,
+}
+
+fn track_run(moof: &[u8], moof_offset: u64) -> Option<TrackRun> {
+    let traf = child(moof, b"traf")?;
+    let tfhd = child(traf, b"tfhd")?;
+    let trun = child(traf, b"trun")?;
+
+    let tfhd_flags = be_u32(tfhd, 0)? & 0x00ff_ffff;
+    let mut at = 8;
+    let base = if tfhd_flags & 0x01 != 0 {
+        at += 8;
+        be_u64(tfhd, 8)?
+    } else {
+        moof_offset
+    };
+    if tfhd_flags & 0x02 != 0 {
+        at += 4;
+    }
+    let default_duration = if tfhd_flags & 0x08 != 0 {
+        at += 4;
+        be_u32(tfhd, at - 4)?
+    } else {
+        0
+    };
+    let default_size = if tfhd_flags & 0x10 != 0 { be_u32(tfhd, at)? } else { 0 };
+
+    let flags = be_u32(trun, 0)? & 0x00ff_ffff;
+    let count = be_u32(trun, 4)?;
+    let mut at = 8;
+    let data_offset = if flags & 0x01 != 0 {
+        at += 4;
+        be_u32(trun, 8)? as i32 as i64
+    } else {
+        0
+    };
+    if flags & 0x04 != 0 {
+        at += 4;
+    }
+
+    let mut run = TrackRun {
+        data_offset: base.checked_add_signed(data_offset)?,
+        durations: Vec::new(),
+        sizes: Vec::new(),
+    };
+    for _ in 0..count {
+        let mut field = |present: u32, default: u32| -> Option<u32> {
+            if flags & present == 0 {
+                return Some(default);
+            }
+            at += 4;
+            be_u32(trun, at - 4)
+        };
+        run.durations.push(field(0x100, default_duration)?);
+        run.sizes.push(field(0x200, default_size)?);
+        field(0x400, 0)?;
+        field(0x800, 0)?;
+    }
+    Some(run)
+}
+
+pub fn read_info(path: &Path) -> std::io::Result<Mp4Info> {
+    let mut file = File::open(path)?;
+    let boxes = top_level_boxes(&mut file)?;
+
+    let moov_header = boxes.iter().find(|b| &b.kind == b"moov").ok_or_else(|| invalid("No moov box"))?;
+    let moov = read_payload(&mut file, moov_header)?;
+    let trak = video_track(&moov).ok_or_else(|| invalid("No video track"))?;
+    let (width, height) = track_dimensions(trak).ok_or_else(|| invalid("Corrupt tkhd box"))?;
+    let (timescale, mut duration) = media_time(trak).ok_or_else(|| invalid("Corrupt mdhd box"))?;
+
+    // Fragmented files leave the header duration at zero; add up the fragments
+    if duration == 0 {
+        for header in boxes.iter().filter(|b| &b.kind == b"moof") {
+            let moof = read_payload(&mut file, header)?;
+            if let Some(run) = track_run(&moof, header.offset) {
+                duration += run.durations.iter().map(|&d| d as u64).sum::<u64>();
+            }
+        }
+    }
+
+    Ok(Mp4Info {
+        width,
+        height,
+        duration: duration as f64 / timescale.max(1) as f64,
+    })
+}
+
+// The first frame as an Annex B stream with its parameter sets, ready to decode
+pub fn read_first_frame(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let boxes = top_level_boxes(&mut file)?;
+
+    let moov_header = boxes.iter().find(|b| &b.kind == b"moov").ok_or_else(|| invalid("No moov box"))?;
+    let moov = read_payload(&mut file, moov_header)?;
+    let trak = video_track(&moov).ok_or_else(|| invalid("No video track"))?;
+    let stbl = find(trak, &[b"mdia", b"minf", b"stbl"]).ok_or_else(|| invalid("No stbl box"))?;
+
+    // stsd holds one avc1 entry; its avcC box follows 78 bytes of sample entry fields
+    let stsd = child(stbl, b"stsd").ok_or_else(|| invalid("No stsd box"))?;
+    let avc1 = child(stsd.get(8..).unwrap_or_default(), b"avc1").ok_or_else(|| invalid("Not H.264 video"))?;
+    let avcc = child(avc1.get(78..).unwrap_or_default(), b"avcC").ok_or_else(|| invalid("No avcC box"))?;
+    let (length_size, mut frame) = parameter_sets(avcc).ok_or_else(|| invalid("Corrupt avcC box"))?;
+
+    // Fragmented files keep samples in moof/mdat, plain files in the stbl tables
+    let (offset, size) = match boxes.iter().find(|b| &b.kind == b"moof") {
+        Some(header) => {
+            let moof = read_payload(&mut file, header)?;
+            let run = track_run(&moof, header.offset).ok_or_else(|| invalid("Corrupt moof box"))?;
+            let size = *run.sizes.first().ok_or_else(|| invalid("Empty fragment"))?;
+            (run.data_offset, size)
+        }
+        None => {
+            let stsz = child(stbl, b"stsz").ok_or_else(|| invalid("No stsz box"))?;
+            let stco = child(stbl, b"stco").ok_or_else(|| invalid("No stco box"))?;
+            let size = match be_u32(stsz, 4) {
+                Some(0) => be_u32(stsz, 12),
+                size => size,
+            };
+            (
+                be_u32(stco, 8).ok_or_else(|| invalid("Empty stco box"))? as u64,
+                size.ok_or_else(|| invalid("Empty stsz box"))?,
+            )
+        }
+    };
+
+    file.seek(SeekFrom::Start(offset))?;
+    let mut sample = vec![0; size as usize];
+    file.read_exact(&mut sample)?;
+
+    // Length-prefixed NAL units back to start codes
+    let mut rest = &sample[..];
+    while rest.len() >= length_size {
+        let len = rest[..length_size].iter().fold(0usize, |len, &byte| len << 8 | byte as usize);
+        let nal = rest.get(length_size..length_size + len).ok_or_else(|| invalid("Truncated sample"))?;
+        frame.extend_from_slice(&[0, 0, 0, 1]);
+        frame.extend_from_slice(nal);
+        rest = &rest[length_size + len..];
+    }
+    Ok(frame)
+}
+
+// NAL length size and the SPS/PPS from an avcC payload, as Annex B
+fn parameter_sets(avcc: &[u8]) -> Option<(usize, Vec<u8>)> {
+    let length_size = (*avcc.get(4)? & 0x03) as usize + 1;
+    let mut out = Vec::new();
+    let mut at = 5;
+
+    // SPS count is in the low five bits, PPS count is a whole byte
+    for mask in [0x1f, 0xff] {
+        let count = *avcc.get(at)? & mask;
+        at += 1;
+        for _ in 0..count {
+            let len = be_u16(avcc, at)? as usize;
+            out.extend_from_slice(&[0, 0, 0, 1]);
+            out.extend_from_slice(avcc.get(at + 2..at + 2 + len)?);
+            at += 2 + len;
+        }
+    }
+    Some((length_size, out))
+}
@@ -0,0 +1,152 @@
+// Network diagnostics
+// Works out which local interface the OS would use to reach the drone, without
+// shelling out to platform tools like ipconfig.
+
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, UdpSocket};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NetworkDiagnostic {
+    pub drone_ip: IpAddr,
+    // Interface and address the OS routes drone traffic through
+    pub interface: Option<String>,
+    pub local_ip: Option<IpAddr>,
+    // The drone is on the interface's own subnet (no gateway in between)
+    pub same_subnet: bool,
+    // The OS has a route and an interface for the drone's address
+    pub reachable: bool,
+    pub message: String,
+}
+
+pub fn diagnose(drone_ip: IpAddr) -> NetworkDiagnostic {
+    let mut diagnostic = NetworkDiagnostic {
+        drone_ip,
+        interface: None,
+        local_ip: None,
+        same_subnet: false,
+        reachable: false,
+        message: String::new(),
+    };
+
+    let local_ip = match route_source(drone_ip) {
+        Ok(ip) => ip,
+        Err(e) => {
+            diagnostic.message = format!("No route to the drone at {}: {}", drone_ip, e);
+            return diagnostic;
+        }
+    };
+    diagnostic.local_ip = Some(local_ip);
+
+    let interfaces = match if_addrs::get_if_addrs() {
+        Ok(interfaces) => interfaces,
+        Err(e) => {
+            diagnostic.message = format!("Could not list network interfaces: {}", e);
+            return diagnostic;
+        }
+    };
+
+    let Some(interface) = interfaces.iter().find(|iface| iface.ip() == local_ip) else {
+        diagnostic.message = format!("No interface owns {}, the address used to reach {}", local_ip, drone_ip);
+        return diagnostic;
+    };
+
+    diagnostic.interface = Some(interface.name.clone());
+    diagnostic.same_subnet = match &interface.addr {
+        if_addrs::IfAddr::V4(addr) => match drone_ip {
+            IpAddr::V4(drone) => same_subnet_v4(addr.ip, addr.netmask, drone),
+            IpAddr::V6(_) => false,
+        },
+        if_addrs::IfAddr::V6(addr) => match drone_ip {
+            IpAddr::V6(drone) => same_subnet_v6(addr.ip, addr.netmask, drone),
+            IpAddr::V4(_) => false,
+        },
+    };
+
+    // A drone behind a gateway is unusual but valid (station mode on a routed
+    // network), so a mismatch is only a warning; the `command` handshake decides
+    diagnostic.reachable = true;
+    diagnostic.message = if diagnostic.same_subnet || drone_ip.is_loopback() {
+        format!("Drone {} is reachable via {} ({})", drone_ip, interface.name, local_ip)
+    } else {
+        format!(
+            "Drone {} is not on the network of {} ({}) and is routed through a gateway. If it does not answer, connect to TELLO-XXXXXX WiFi or the drone's router and try again.",
+            drone_ip, interface.name, local_ip
+        )
+    };
+
+    diagnostic
+}
+
+// Ask the OS which source address it would use for the drone. Connecting a UDP
+// socket only consults the routing table; nothing is sent.
+fn route_source(drone_ip: IpAddr) -> std::io::Result<IpAddr> {
+    let bind_addr: IpAddr = match drone_ip {
+        IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.into(),
+        IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED.into(),
+    };
+
+    let socket = UdpSocket::bind((bind_addr, 0))?;
+    socket.connect((drone_ip, 9))?;
+    Ok(socket.local_addr()?.ip())
+}
+
+fn same_subnet_v4(ip: Ipv4Addr, netmask: Ipv4Addr, other: Ipv4Addr) -> bool {
+    let mask = u32::from(netmask);
+    u32::from(ip) & mask == u32::from(other) & mask
+}
+
+fn same_subnet_v6(ip: Ipv6Addr, netmask: Ipv6Addr, other: Ipv6Addr) -> bool {
+    let mask = u128::from(netmask);
+    u128::from(ip) & mask == u128::from(other) & mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_v4_addresses_under_the_netmask() {
+        let ip = Ipv4Addr::new(192, 168, 10, 2);
+        let mask = Ipv4Addr::new(255, 255, 255, 0);
+
+        assert!(same_subnet_v4(ip, mask, Ipv4Addr::new(192, 168, 10, 1)));
+        assert!(same_subnet_v4(ip, mask, Ipv4Addr::new(192, 168, 10, 255)));
+        assert!(!same_subnet_v4(ip, mask, Ipv4Addr::new(192, 168, 11, 1)));
+        assert!(!same_subnet_v4(ip, mask, Ipv4Addr::new(10, 0, 0, 1)));
+
+        // Masks that do not end on an octet boundary
+        let mask = Ipv4Addr::new(255, 255, 255, 240);
+        assert!(same_subnet_v4(ip, mask, Ipv4Addr::new(192, 168, 10, 15)));
+        assert!(!same_subnet_v4(ip, mask, Ipv4Addr::new(192, 168, 10, 16)));
+
+        // A /32 only matches itself, a /0 matches everything
+        assert!(same_subnet_v4(ip, Ipv4Addr::BROADCAST, ip));
+        assert!(!same_subnet_v4(ip, Ipv4Addr::BROADCAST, Ipv4Addr::new(192, 168, 10, 1)));
+        assert!(same_subnet_v4(ip, Ipv4Addr::UNSPECIFIED, Ipv4Addr::new(8, 8, 8, 8)));
+    }
+
+    #[test]
+    fn compares_v6_addresses_under_the_netmask() {
+        let ip: Ipv6Addr = "fe80::1:2:3:4".parse().unwrap();
+        let mask: Ipv6Addr = "ffff:ffff:ffff:ffff::".parse().unwrap();
+
+        assert!(same_subnet_v6(ip, mask, "fe80::aaaa:bbbb:cccc:dddd".parse().unwrap()));
+        assert!(!same_subnet_v6(ip, mask, "fe80:0:0:1::1".parse().unwrap()));
+        assert!(!same_subnet_v6(ip, mask, "2001:db8::1".parse().unwrap()));
+
+        let mask: Ipv6Addr = "ffff:ffff:ffff:ff00::".parse().unwrap();
+        assert!(same_subnet_v6(ip, mask, "fe80:0:0:ff::".parse().unwrap()));
+        assert!(!same_subnet_v6(ip, mask, "fe80:0:0:100::".parse().unwrap()));
+
+        assert!(same_subnet_v6(ip, Ipv6Addr::UNSPECIFIED, Ipv6Addr::LOCALHOST));
+    }
+
+    #[test]
+    fn routes_to_loopback() {
+        let diagnostic = diagnose(Ipv4Addr::LOCALHOST.into());
+
+        assert!(diagnostic.reachable, "{}", diagnostic.message);
+        assert!(diagnostic.same_subnet);
+        assert_eq!(diagnostic.local_ip, Some(Ipv4Addr::LOCALHOST.into()));
+    }
+}
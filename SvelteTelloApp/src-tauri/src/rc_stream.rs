@@ -0,0 +1,334 @@
+// RC streaming
+// The Tello never answers `rc` and only holds a stick position briefly, so it
+// has to be repeated. This keeps the current setpoint, written by the UI, the
+// gamepad or an autopilot (last writer wins), and sends it as `rc` at a fixed
+// rate without waiting for replies. A setpoint that is not refreshed within the
+// dead-man timeout is zeroed, so a stalled UI or source cannot leave the drone
+// drifting. When a safety action (watchdog, battery) takes over, the sticks are
+// zeroed and locked until the pilot centres them, so a held stick cannot fight
+// the recovery.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+use crate::command_channel::CommandChannel;
+use crate::error::TelloError;
+use crate::geofence::{self, GeofenceConfig, Verdict};
+use crate::tello_command::TelloCommand;
+use crate::DroneState;
+
+// A stop is sent a few times over, a single UDP packet may be lost
+const ZERO_REPEATS: u32 = 3;
+
+// Stick positions in `rc a b c d` order, -100..=100. Only `new` and
+// `default` build one, so the range always holds.
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq)]
+pub struct RcSetpoint {
+    left_right: i32,
+    forward_back: i32,
+    up_down: i32,
+    yaw: i32,
+}
+
+impl RcSetpoint {
+    pub fn new(left_right: i32, forward_back: i32, up_down: i32, yaw: i32) -> Result<Self, TelloError> {
+        // Same range checks as a typed rc command
+        TelloCommand::rc(left_right, forward_back, up_down, yaw)?;
+        Ok(Self { left_right, forward_back, up_down, yaw })
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+
+    fn command(&self) -> TelloCommand {
+        TelloCommand::rc(self.left_right, self.forward_back, self.up_down, self.yaw).unwrap_or_else(|_| TelloCommand::hover())
+    }
+}
+
+impl std::fmt::Display for RcSetpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.command())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RcSource {
+    Ui,
+    Gamepad,
+    Autopilot,
+}
+
+impl RcSource {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Ui => "ui",
+            Self::Gamepad => "gamepad",
+            Self::Autopilot => "autopilot",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RcStreamConfig {
+    // rc packets per second while a setpoint is held
+    pub rate_hz: u32,
+    // A setpoint not refreshed for this long is zeroed
+    pub deadman_ms: u64,
+}
+
+impl Default for RcStreamConfig {
+    fn default() -> Self {
+        Self {
+            rate_hz: 30,
+            deadman_ms: 750,
+        }
+    }
+}
+
+impl RcStreamConfig {
+    pub fn validate(&self) -> Result<(), TelloError> {
+        if !(20..=50).contains(&self.rate_hz) {
+            return Err(TelloError::InvalidArgument(format!("RC rate must be 20-50 Hz, got {}", self.rate_hz)));
+        }
+        if !(200..=5000).contains(&self.deadman_ms) {
+            return Err(TelloError::InvalidArgument(format!("Dead-man timeout must be 200-5000 ms, got {}", self.deadman_ms)));
+        }
+        Ok(())
+    }
+}
+
+// The current setpoint and how long it has been held
+#[derive(Default)]
+struct Sticks {
+    setpoint: RcSetpoint,
+    source: Option<RcSource>,
+    updated: Option<Instant>,
+    zeros_left: u32,
+    // Why the sticks were locked; cleared by a centred setpoint
+    locked: Option<String>,
+}
+
+impl Sticks {
+    // A locked stream only takes a centred setpoint, which unlocks it
+    fn set(&mut self, source: RcSource, setpoint: RcSetpoint, now: Instant) -> Result<(), String> {
+        if let Some(reason) = &self.locked {
+            if !setpoint.is_zero() {
+                return Err(reason.clone());
+            }
+            println!("[RC] 🔓 Sticks centred by {}, control handed back", source.name());
+            self.locked = None;
+        }
+        if setpoint.is_zero() && !self.setpoint.is_zero() {
+            self.zeros_left = ZERO_REPEATS;
+        }
+        self.setpoint = setpoint;
+        self.source = Some(source);
+        self.updated = Some(now);
+        Ok(())
+    }
+
+    fn lock(&mut self, reason: &str) {
+        self.stop();
+        self.locked = Some(reason.to_string());
+    }
+
+    fn stop(&mut self) {
+        if !self.setpoint.is_zero() {
+            self.zeros_left = ZERO_REPEATS;
+        }
+        self.setpoint = RcSetpoint::default();
+    }
+
+    // Zeroes a setpoint that went stale; returns whose it was
+    fn expire(&mut self, now: Instant, deadman: Duration) -> Option<RcSource> {
+        let stale = self.updated.is_some_and(|updated| now.duration_since(updated) > deadman);
+        if !stale || self.setpoint.is_zero() {
+            return None;
+        }
+        self.stop();
+        self.source
+    }
+
+    // What to send this tick: the held setpoint, then the zeros after a stop
+    fn next(&mut self) -> Option<RcSetpoint> {
+        if !self.setpoint.is_zero() {
+            return Some(self.setpoint);
+        }
+        if self.zeros_left == 0 {
+            return None;
+        }
+        self.zeros_left -= 1;
+        Some(self.setpoint)
+    }
+}
+
+pub struct RcStream {
+    task: JoinHandle<()>,
+    sticks: Arc<Mutex<Sticks>>,
+}
+
+impl RcStream {
+    // `config` is read on every tick, so changes apply without a restart
+    pub fn start(
+        config: Arc<Mutex<RcStreamConfig>>,
+        channel: CommandChannel,
+        drone: Arc<Mutex<DroneState>>,
+        geofence: Arc<Mutex<GeofenceConfig>>,
+    ) -> Self {
+        let sticks = Arc::new(Mutex::new(Sticks::default()));
+        let task_sticks = Arc::clone(&sticks);
+
+        let task = tokio::spawn(async move {
+            let mut rate_hz = config.lock().unwrap().rate_hz;
+            println!("[RC] 🕹️ Streaming rc at {} Hz", rate_hz);
+            let mut interval = tokio::time::interval(Duration::from_secs_f64(1.0 / rate_hz as f64));
+
+            loop {
+                interval.tick().await;
+
+                let config = config.lock().unwrap().clone();
+                if config.rate_hz != rate_hz {
+                    rate_hz = config.rate_hz;
+                    interval = tokio::time::interval(Duration::from_secs_f64(1.0 / rate_hz as f64));
+                }
+
+                let setpoint = {
+                    let mut sticks = task_sticks.lock().unwrap();
+                    if let Some(source) = sticks.expire(Instant::now(), Duration::from_millis(config.deadman_ms)) {
+                        println!("[RC] ⏱️ No setpoint from {} for {} ms, zeroing the sticks", source.name(), config.deadman_ms);
+                    }
+                    // Only a flying drone is steered; setpoints made on the ground are
+                    // dropped, but a lock holds until the sticks are centred
+                    if !drone.lock().unwrap().flying {
+                        *sticks = Sticks { locked: sticks.locked.take(), ..Sticks::default() };
+                        continue;
+                    }
+                    sticks.next()
+                };
+                let Some(setpoint) = setpoint else { continue };
+
+                // Backstop: the fence may have moved since the setpoint was made
+                let command = setpoint.to_string();
+                let verdict = geofence::check(&geofence.lock().unwrap(), &drone.lock().unwrap(), &command);
+                let command = match verdict {
+                    Verdict::Allow => command,
+                    Verdict::Clamp(clamped, _) => clamped,
+                    Verdict::Reject(_) => RcSetpoint::default().to_string(),
+                };
+
+                // Fire and forget: the drone does not reply to rc
+                if let Err(e) = channel.send(&command).await {
+                    eprintln!("[RC] ❌ Failed to send '{}': {}", command, e);
+                }
+            }
+        });
+
+        Self { task, sticks }
+    }
+
+    pub fn set(&self, source: RcSource, setpoint: RcSetpoint) -> Result<(), TelloError> {
+        self.sticks.lock().unwrap().set(source, setpoint, Instant::now()).map_err(|reason| {
+            TelloError::InvalidState(format!("Sticks locked ({}); centre them to take control again", reason))
+        })
+    }
+
+    // Centre the sticks at once, e.g. before landing
+    pub fn stop(&self) {
+        self.sticks.lock().unwrap().stop();
+    }
+
+    // Centre the sticks and ignore setpoints until the pilot centres them too
+    pub fn neutral(&self, reason: &str) {
+        println!("[RC] 🔒 Sticks locked: {}", reason);
+        self.sticks.lock().unwrap().lock(reason);
+    }
+}
+
+impl Drop for RcStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setpoint(forward_back: i32) -> RcSetpoint {
+        RcSetpoint { forward_back, ..RcSetpoint::default() }
+    }
+
+    #[test]
+    fn holds_setpoint_then_repeats_stop() {
+        let mut sticks = Sticks::default();
+        let now = Instant::now();
+        assert_eq!(sticks.next(), None);
+
+        sticks.set(RcSource::Ui, setpoint(40), now).unwrap();
+        assert_eq!(sticks.next(), Some(setpoint(40)));
+        assert_eq!(sticks.next(), Some(setpoint(40)));
+
+        sticks.set(RcSource::Ui, RcSetpoint::default(), now).unwrap();
+        for _ in 0..ZERO_REPEATS {
+            assert_eq!(sticks.next(), Some(RcSetpoint::default()));
+        }
+        assert_eq!(sticks.next(), None);
+
+        // Centring again while already centred sends nothing more
+        sticks.set(RcSource::Gamepad, RcSetpoint::default(), now).unwrap();
+        assert_eq!(sticks.next(), None);
+    }
+
+    #[test]
+    fn deadman_zeroes_stale_setpoint() {
+        let mut sticks = Sticks::default();
+        let now = Instant::now();
+        let deadman = Duration::from_millis(500);
+
+        sticks.set(RcSource::Autopilot, setpoint(-30), now).unwrap();
+        assert_eq!(sticks.expire(now + Duration::from_millis(400), deadman), None);
+        assert_eq!(sticks.next(), Some(setpoint(-30)));
+
+        assert_eq!(sticks.expire(now + Duration::from_millis(600), deadman), Some(RcSource::Autopilot));
+        assert_eq!(sticks.next(), Some(RcSetpoint::default()));
+        // Already zeroed; does not fire again
+        assert_eq!(sticks.expire(now + Duration::from_millis(700), deadman), None);
+
+        // A refresh restarts the clock
+        sticks.set(RcSource::Autopilot, setpoint(10), now + Duration::from_millis(700)).unwrap();
+        assert_eq!(sticks.expire(now + Duration::from_millis(1000), deadman), None);
+    }
+
+    #[test]
+    fn lock_holds_until_sticks_are_centred() {
+        let mut sticks = Sticks::default();
+        let now = Instant::now();
+
+        sticks.set(RcSource::Ui, setpoint(60), now).unwrap();
+        sticks.lock("link lost");
+        assert_eq!(sticks.next(), Some(RcSetpoint::default()));
+
+        // The UI keeps refreshing the held stick; it is ignored, not obeyed
+        assert_eq!(sticks.set(RcSource::Ui, setpoint(60), now), Err("link lost".to_string()));
+        assert_eq!(sticks.setpoint, RcSetpoint::default());
+
+        sticks.set(RcSource::Ui, RcSetpoint::default(), now).unwrap();
+        sticks.set(RcSource::Ui, setpoint(20), now).unwrap();
+        assert_eq!(sticks.next(), Some(setpoint(20)));
+    }
+
+    #[test]
+    fn validates_setpoint_and_config() {
+        assert!(RcSetpoint::new(100, -100, 0, 50).is_ok());
+        assert!(RcSetpoint::new(0, 101, 0, 0).is_err());
+
+        assert!(RcStreamConfig::default().validate().is_ok());
+        assert!(RcStreamConfig { rate_hz: 10, ..RcStreamConfig::default() }.validate().is_err());
+        assert!(RcStreamConfig { deadman_ms: 50, ..RcStreamConfig::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,227 @@
+// Video recording
+// Muxes the assembled H.264 frames from TelloVideoCapture into a fragmented MP4.
+// Timestamps come from frame arrival times, which is what the drone gives us.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::error::TelloError;
+use crate::h264::{self, AccessUnit, NAL_AUD, NAL_PPS, NAL_SPS};
+use crate::mp4::{Mp4Writer, TIMESCALE};
+
+// Used for the last frame, whose real duration we never learn
+const DEFAULT_FRAME_DURATION: u32 = TIMESCALE / 30;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecordingInfo {
+    pub path: String,
+    pub duration: f64, // seconds
+    pub size: u64,     // bytes
+    pub frames: u64,
+}
+
+// A frame waiting for the next one to arrive so we know its duration
+struct HeldFrame {
+    sample: Vec<u8>,
+    keyframe: bool,
+    received_at: Instant,
+}
+
+pub struct Recorder {
+    path: PathBuf,
+    writer: Option<Mp4Writer>,
+    held: Option<HeldFrame>,
+    frames: u64,
+    duration: u64,
+}
+
+impl Recorder {
+    pub fn new(directory: &Path) -> Result<Self, TelloError> {
+        std::fs::create_dir_all(directory).map_err(|e| TelloError::io("Failed to create directory", e))?;
+
+        // Milliseconds, so a recording started right after another gets its own file
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let path = directory.join(format!("tello_{}.mp4", timestamp));
+
+        Ok(Self {
+            path,
+            writer: None,
+            held: None,
+            frames: 0,
+            duration: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn push(&mut self, frame: &AccessUnit) -> Result<(), TelloError> {
+        // The file can only start at a keyframe: it carries the SPS/PPS the
+        // header needs, and nothing before it can be decoded anyway
+        if self.writer.is_none() {
+            if !frame.keyframe {
+                return Ok(());
+            }
+            self.writer = Some(self.create_writer(frame)?);
+            println!("[Recorder] 🔴 Recording to {}", self.path.display());
+        }
+
+        if let Some(held) = self.held.take() {
+            let elapsed = frame.received_at.duration_since(held.received_at).as_secs_f64();
+            let duration = ((elapsed * TIMESCALE as f64).round() as u32).max(1);
+            self.write(held, duration)?;
+        }
+
+        self.held = Some(HeldFrame {
+            sample: to_avcc(&frame.data),
+            keyframe: frame.keyframe,
+            received_at: frame.received_at,
+        });
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<RecordingInfo, TelloError> {
+        if let Some(held) = self.held.take() {
+            self.write(held, DEFAULT_FRAME_DURATION)?;
+        }
+
+        match self.writer.take() {
+            Some(writer) => writer.finish().map_err(|e| TelloError::io("Failed to finish recording", e))?,
+            None => return Err(TelloError::Media("No video was recorded (no keyframe received)".to_string())),
+        }
+
+        let size = std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        println!("[Recorder] ⏹️ Saved {} ({} frames, {} bytes)", self.path.display(), self.frames, size);
+
+        Ok(RecordingInfo {
+            path: self.path.to_string_lossy().to_string(),
+            duration: self.duration as f64 / TIMESCALE as f64,
+            size,
+            frames: self.frames,
+        })
+    }
+
+    fn create_writer(&self, keyframe: &AccessUnit) -> Result<Mp4Writer, TelloError> {
+        let nals = h264::split_nal_units(&keyframe.data);
+        let find = |nal_type| nals.iter().find(|nal| nal[0] & 0x1f == nal_type).copied();
+        let media = |message: &str| TelloError::Media(message.to_string());
+
+        let sps = find(NAL_SPS).ok_or_else(|| media("Keyframe has no SPS"))?;
+        let pps = find(NAL_PPS).ok_or_else(|| media("Keyframe has no PPS"))?;
+        let (width, height) = h264::sps_dimensions(sps).ok_or_else(|| media("Could not parse SPS"))?;
+
+        Mp4Writer::create(&self.path, sps, pps, width, height)
+            .map_err(|e| TelloError::io(format!("Failed to create {}", self.path.display()), e))
+    }
+
+    fn write(&mut self, frame: HeldFrame, duration: u32) -> Result<(), TelloError> {
+        let writer = self.writer.as_mut().ok_or_else(|| TelloError::InvalidState("Recording not started".to_string()))?;
+        writer
+            .write_sample(frame.sample, duration, frame.keyframe)
+            .map_err(|e| TelloError::io("Failed to write frame", e))?;
+        self.frames += 1;
+        self.duration += duration as u64;
+        Ok(())
+    }
+}
+
+// Annex B to length-prefixed NAL units; parameter sets live in the avcC box
+fn to_avcc(annex_b: &[u8]) -> Vec<u8> {
+    let mut sample = Vec::with_capacity(annex_b.len());
+    for nal in h264::split_nal_units(annex_b) {
+        if matches!(nal[0] & 0x1f, NAL_SPS | NAL_PPS | NAL_AUD) {
+            continue;
+        }
+        sample.extend_from_slice(&(nal.len() as u32).to_be_bytes());
+        sample.extend_from_slice(nal);
+    }
+    sample
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp4;
+    use std::time::Duration;
+
+    // Baseline 960x720, the Tello's own stream size
+    const SPS: [u8; 9] = [0x67, 0x42, 0xc0, 0x1f, 0xda, 0x03, 0xc0, 0x5b, 0x90];
+    const PPS: [u8; 3] = [0x68, 0xce, 0x38];
+    const IDR: [u8; 5] = [0x65, 0x88, 0x84, 0x21, 0xa0];
+    const SLICE: [u8; 4] = [0x41, 0x9a, 0x21, 0x6c];
+
+    fn annex_b(nals: &[&[u8]]) -> Vec<u8> {
+        nals.iter().flat_map(|nal| [&[0, 0, 0, 1][..], nal].concat()).collect()
+    }
+
+    fn frame(data: Vec<u8>, keyframe: bool, received_at: Instant) -> AccessUnit {
+        AccessUnit { data, keyframe, sequence: 0, received_at }
+    }
+
+    fn directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("tello_recording_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        directory
+    }
+
+    #[test]
+    fn writes_an_mp4_the_gallery_can_read_back() {
+        let directory = directory("round_trip");
+        let mut recorder = Recorder::new(&directory).unwrap();
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        // Nothing before the first keyframe can be decoded
+        recorder.push(&frame(annex_b(&[&SLICE]), false, at(0))).unwrap();
+        recorder.push(&frame(annex_b(&[&[NAL_AUD, 0xf0], &SPS, &PPS, &IDR]), true, at(10))).unwrap();
+        recorder.push(&frame(annex_b(&[&SLICE]), false, at(50))).unwrap();
+        recorder.push(&frame(annex_b(&[&SLICE]), false, at(90))).unwrap();
+        recorder.push(&frame(annex_b(&[&SPS, &PPS, &IDR]), true, at(130))).unwrap();
+        let path = recorder.path().to_path_buf();
+        let recording = recorder.finish().unwrap();
+
+        // Three 40 ms gaps, then the default duration for the last frame
+        let ticks = 3 * 3600 + DEFAULT_FRAME_DURATION as u64;
+        let seconds = ticks as f64 / TIMESCALE as f64;
+        assert_eq!(recording.frames, 4);
+        assert!((recording.duration - seconds).abs() < 1e-9, "{}", recording.duration);
+        assert_eq!(recording.size, std::fs::metadata(&path).unwrap().len());
+
+        let info = mp4::read_info(&path).unwrap();
+        assert_eq!((info.width, info.height), (960, 720));
+        assert!((info.duration - seconds).abs() < 1e-9, "{}", info.duration);
+
+        // The first sample, without the AUD, with the parameter sets from avcC
+        assert_eq!(mp4::read_first_frame(&path).unwrap(), annex_b(&[&SPS, &PPS, &IDR]));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn never_overwrites_an_earlier_recording() {
+        let directory = directory("existing");
+        let mut recorder = Recorder::new(&directory).unwrap();
+        std::fs::write(recorder.path(), b"earlier").unwrap();
+
+        let result = recorder.push(&frame(annex_b(&[&SPS, &PPS, &IDR]), true, Instant::now()));
+
+        assert!(matches!(result, Err(TelloError::Io(_))), "{:?}", result);
+        assert_eq!(std::fs::read(recorder.path()).unwrap(), b"earlier");
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn refuses_to_save_without_a_keyframe() {
+        let directory = directory("no_keyframe");
+        let mut recorder = Recorder::new(&directory).unwrap();
+        recorder.push(&frame(annex_b(&[&SLICE]), false, Instant::now())).unwrap();
+
+        assert!(matches!(recorder.finish(), Err(TelloError::Media(_))));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,372 @@
+// Flight log replay
+// Plays a recorded flight back through the same events the live link produces:
+// every state packet is re-emitted as `drone-state` and every command, reply
+// and mode change as `flight-event`, on the original timeline scaled by the
+// playback speed. No drone or socket is involved.
+
+use serde::Serialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Manager;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::error::TelloError;
+use crate::flight_log::{FlightEvent, FlightLogEntry};
+
+const TICK: Duration = Duration::from_millis(20);
+// How often `replay-status` is sent while playing
+const STATUS_INTERVAL: Duration = Duration::from_millis(250);
+const MIN_SPEED: f64 = 0.1;
+const MAX_SPEED: f64 = 16.0;
+
+// Payload of the `replay-status` event
+#[derive(Serialize, Clone, Debug)]
+pub struct ReplayStatus {
+    pub name: String,
+    pub position: f64, // seconds into the flight
+    pub duration: f64, // seconds
+    pub paused: bool,
+    pub speed: f64,
+    pub finished: bool,
+}
+
+struct ReplayControl {
+    paused: bool,
+    speed: f64,
+    // Requested position in ms, picked up on the next tick
+    seek: Option<u64>,
+    status: ReplayStatus,
+}
+
+// Where playback is on the flight's own clock and which entry is due next.
+// Wall-clock time since the last call moves it, scaled by the playback speed.
+struct Timeline {
+    entries: Vec<FlightLogEntry>,
+    start: u64,
+    duration_ms: u64,
+    position: f64, // ms since the first entry
+    next: usize,
+    last_tick: Instant,
+}
+
+impl Timeline {
+    fn new(mut entries: Vec<FlightLogEntry>) -> Option<Self> {
+        // Timestamps are wall-clock; keep the timeline monotonic even if the clock stepped
+        entries.sort_by_key(|entry| entry.timestamp);
+        let start = entries.first()?.timestamp;
+        let duration_ms = entries.last()?.timestamp - start;
+        Some(Self {
+            entries,
+            start,
+            duration_ms,
+            position: 0.0,
+            next: 0,
+            last_tick: Instant::now(),
+        })
+    }
+
+    // The entries that came due since the last call
+    fn advance(&mut self, speed: f64) -> &[FlightLogEntry] {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_tick).as_secs_f64() * 1000.0;
+        self.last_tick = now;
+
+        let first = self.next;
+        if self.finished() {
+            return &[];
+        }
+        self.position = (self.position + elapsed * speed).min(self.duration_ms as f64);
+        while self.next < self.entries.len() && (self.entries[self.next].timestamp - self.start) as f64 <= self.position {
+            self.next += 1;
+        }
+        &self.entries[first..self.next]
+    }
+
+    // Time spent paused does not move the flight along
+    fn hold(&mut self) {
+        self.last_tick = Instant::now();
+    }
+
+    // Jump to `target` ms; returns everything played up to there
+    fn seek(&mut self, target: u64) -> &[FlightLogEntry] {
+        self.position = target.min(self.duration_ms) as f64;
+        self.next = self.entries.partition_point(|entry| entry.timestamp - self.start <= target);
+        &self.entries[..self.next]
+    }
+
+    fn finished(&self) -> bool {
+        self.next == self.entries.len()
+    }
+}
+
+pub struct FlightReplay {
+    task: JoinHandle<()>,
+    control: Arc<Mutex<ReplayControl>>,
+    // Wakes the task while it is parked, paused or at the end of the flight
+    wake: Arc<Notify>,
+}
+
+impl FlightReplay {
+    pub fn start(name: String, entries: Vec<FlightLogEntry>, speed: f64, app_handle: tauri::AppHandle) -> Result<Self, TelloError> {
+        validate_speed(speed)?;
+        let entry_count = entries.len();
+        let Some(mut timeline) = Timeline::new(entries) else {
+            return Err(TelloError::InvalidArgument(format!("{} has no entries", name)));
+        };
+        let duration = timeline.duration_ms as f64 / 1000.0;
+
+        println!("[Replay] ▶️ Replaying {} ({} entries, {:.1}s) at {}x", name, entry_count, duration, speed);
+
+        let control = Arc::new(Mutex::new(ReplayControl {
+            paused: false,
+            speed,
+            seek: None,
+            status: ReplayStatus {
+                name,
+                position: 0.0,
+                duration,
+                paused: false,
+                speed,
+                finished: false,
+            },
+        }));
+        let wake = Arc::new(Notify::new());
+        let task_control = Arc::clone(&control);
+        let task_wake = Arc::clone(&wake);
+
+        let task = tokio::spawn(async move {
+            let mut last_status: Option<Instant> = None;
+            let mut interval = tokio::time::interval(TICK);
+
+            loop {
+                // Nothing moves while paused or once the flight is over, so
+                // sleep until a control call instead of ticking
+                let idle = {
+                    let control = task_control.lock().unwrap();
+                    control.seek.is_none() && (control.paused || timeline.finished())
+                };
+                if idle {
+                    task_wake.notified().await;
+                    timeline.hold();
+                    interval.reset();
+                    continue;
+                }
+
+                interval.tick().await;
+                let now = Instant::now();
+
+                let (paused, speed, seek) = {
+                    let mut control = task_control.lock().unwrap();
+                    (control.paused, control.speed, control.seek.take())
+                };
+
+                let mut changed = false;
+                if let Some(target) = seek {
+                    restore(timeline.seek(target), &app_handle);
+                    changed = true;
+                } else if paused {
+                    timeline.hold();
+                } else {
+                    for entry in timeline.advance(speed) {
+                        emit_entry(entry, &app_handle);
+                    }
+                    changed = timeline.finished();
+                }
+
+                let finished = timeline.finished();
+                if changed || (!paused && !finished && last_status.is_none_or(|at| now.duration_since(at) >= STATUS_INTERVAL)) {
+                    last_status = Some(now);
+                    let status = {
+                        let mut control = task_control.lock().unwrap();
+                        control.status.position = timeline.position / 1000.0;
+                        control.status.paused = control.paused;
+                        control.status.speed = control.speed;
+                        control.status.finished = finished;
+                        control.status.clone()
+                    };
+                    if finished && seek.is_none() {
+                        println!("[Replay] ⏹️ Reached the end of {}", status.name);
+                    }
+                    emit(&app_handle, "replay-status", &status);
+                }
+            }
+        });
+
+        Ok(Self { task, control, wake })
+    }
+
+    pub fn set_paused(&self, paused: bool) -> ReplayStatus {
+        let mut control = self.control.lock().unwrap();
+        control.paused = paused;
+        control.status.paused = paused;
+        self.wake.notify_one();
+        control.status.clone()
+    }
+
+    pub fn set_speed(&self, speed: f64) -> Result<ReplayStatus, TelloError> {
+        validate_speed(speed)?;
+        let mut control = self.control.lock().unwrap();
+        control.speed = speed;
+        control.status.speed = speed;
+        Ok(control.status.clone())
+    }
+
+    // `position` in seconds from the start of the flight
+    pub fn seek(&self, position: f64) -> Result<(), TelloError> {
+        if !position.is_finite() || position < 0.0 {
+            return Err(TelloError::InvalidArgument(format!("Cannot seek to {}s", position)));
+        }
+        self.control.lock().unwrap().seek = Some((position * 1000.0) as u64);
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    pub fn status(&self) -> ReplayStatus {
+        self.control.lock().unwrap().status.clone()
+    }
+}
+
+impl Drop for FlightReplay {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn validate_speed(speed: f64) -> Result<(), TelloError> {
+    if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        return Err(TelloError::InvalidArgument(format!(
+            "Replay speed must be between {}x and {}x",
+            MIN_SPEED, MAX_SPEED
+        )));
+    }
+    Ok(())
+}
+
+fn emit_entry(entry: &FlightLogEntry, app_handle: &tauri::AppHandle) {
+    match &entry.event {
+        FlightEvent::State(packet) => emit(app_handle, "drone-state", packet),
+        _ => emit(app_handle, "flight-event", entry),
+    }
+}
+
+// After a seek, bring the UI to where the flight was at that point: replay the
+// mode changes so far (a handful per flight) and the last state packet
+fn restore(played: &[FlightLogEntry], app_handle: &tauri::AppHandle) {
+    for entry in played.iter().filter(|entry| matches!(entry.event, FlightEvent::Mode { .. })) {
+        emit_entry(entry, app_handle);
+    }
+    if let Some(entry) = played.iter().rev().find(|entry| matches!(entry.event, FlightEvent::State(_))) {
+        emit_entry(entry, app_handle);
+    }
+}
+
+fn emit<S: Serialize + Clone>(app_handle: &tauri::AppHandle, event: &str, payload: S) {
+    if let Err(e) = app_handle.emit_all(event, payload) {
+        eprintln!("[Replay] ❌ Failed to emit {}: {}", event, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode(timestamp: u64, mode: &str) -> FlightLogEntry {
+        FlightLogEntry {
+            timestamp,
+            event: FlightEvent::Mode { mode: mode.to_string(), detail: None },
+        }
+    }
+
+    fn modes(entries: &[FlightLogEntry]) -> Vec<&str> {
+        entries
+            .iter()
+            .map(|entry| match &entry.event {
+                FlightEvent::Mode { mode, .. } => mode.as_str(),
+                _ => "",
+            })
+            .collect()
+    }
+
+    // Logged out of order, as after a wall-clock step
+    fn timeline() -> Timeline {
+        Timeline::new(vec![
+            mode(1_000, "free_fly"),
+            mode(1_100, "takeoff"),
+            mode(2_000, "landed"),
+            mode(1_250, "auto_land"),
+        ])
+        .unwrap()
+    }
+
+    async fn advance(ms: u64) {
+        tokio::time::advance(Duration::from_millis(ms)).await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn plays_entries_on_the_flights_own_clock() {
+        let mut timeline = timeline();
+        assert_eq!(timeline.duration_ms, 1_000);
+
+        assert_eq!(modes(timeline.advance(1.0)), ["free_fly"]);
+        advance(99).await;
+        assert!(timeline.advance(1.0).is_empty());
+        advance(1).await;
+        assert_eq!(modes(timeline.advance(1.0)), ["takeoff"]);
+
+        // Twice as fast: 100 ms of wall clock is 200 ms of flight
+        advance(100).await;
+        assert_eq!(modes(timeline.advance(2.0)), ["auto_land"]);
+        assert_eq!(timeline.position, 300.0);
+        assert!(!timeline.finished());
+
+        // Past the end the position stops at the duration
+        advance(5_000).await;
+        assert_eq!(modes(timeline.advance(1.0)), ["landed"]);
+        assert_eq!(timeline.position, 1_000.0);
+        assert!(timeline.finished());
+        advance(100).await;
+        assert!(timeline.advance(1.0).is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn time_on_hold_does_not_count() {
+        let mut timeline = timeline();
+        timeline.advance(1.0);
+
+        advance(60_000).await;
+        timeline.hold();
+        advance(50).await;
+        assert!(timeline.advance(1.0).is_empty());
+        assert_eq!(timeline.position, 50.0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn seeks_back_and_forth() {
+        let mut timeline = timeline();
+
+        assert_eq!(modes(timeline.seek(260)), ["free_fly", "takeoff", "auto_land"]);
+        assert_eq!(timeline.position, 260.0);
+        advance(740).await;
+        assert_eq!(modes(timeline.advance(1.0)), ["landed"]);
+        assert!(timeline.finished());
+
+        // Back from the end, the flight plays on again
+        assert_eq!(modes(timeline.seek(100)), ["free_fly", "takeoff"]);
+        assert!(!timeline.finished());
+        advance(150).await;
+        assert_eq!(modes(timeline.advance(1.0)), ["auto_land"]);
+
+        assert_eq!(timeline.seek(60_000).len(), 4);
+        assert_eq!(timeline.position, 1_000.0);
+        assert!(timeline.finished());
+    }
+
+    #[test]
+    fn needs_at_least_one_entry() {
+        assert!(Timeline::new(Vec::new()).is_none());
+        let single = Timeline::new(vec![mode(5, "takeoff")]).unwrap();
+        assert_eq!(single.duration_ms, 0);
+    }
+}
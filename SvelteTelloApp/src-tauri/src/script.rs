@@ -0,0 +1,400 @@
+// Flight scripts
+// Custom routines written in Rhai, e.g.
+//     while tof() < 150 { up(20); }
+//     cw(90);
+// The engine is sandboxed: no imports, no eval, bounded operations, memory and
+// run time. Drone functions go through the same paths as the UI (geofence,
+// battery policy, RC stream), so a script cannot do anything the pilot could
+// not. A failed command raises a script error, and a script that stops early
+// leaves the drone hovering. print() and debug() are sent to the UI as
+// `script-output`.
+
+use rhai::{Dynamic, Engine, EvalAltResult, FLOAT, INT};
+use serde::Serialize;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::Manager;
+
+use crate::error::TelloError;
+use crate::rc_stream::{RcSetpoint, RcSource};
+use crate::tello_command::{Direction, Rotation, TelloCommand};
+use crate::AppState;
+
+const MAX_RUNTIME: Duration = Duration::from_secs(15 * 60);
+const MAX_OPERATIONS: u64 = 50_000_000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_STRING_SIZE: usize = 64 * 1024;
+const MAX_COLLECTION_SIZE: usize = 10_000;
+// How often a blocking call checks for a stop request
+const POLL: Duration = Duration::from_millis(50);
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+type Reading = fn(&crate::DroneState) -> INT;
+
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputKind {
+    Print,
+    Debug,
+    Error,
+    Finished,
+}
+
+// Payload of the `script-output` event
+#[derive(Serialize, Clone, Debug)]
+pub struct ScriptOutput {
+    pub kind: OutputKind,
+    pub message: String,
+}
+
+pub struct ScriptRun {
+    stop: Arc<AtomicBool>,
+    // Set when a safety action has taken the drone; the script then ends
+    // without sending the stop that normally follows
+    handed_over: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+}
+
+impl ScriptRun {
+    // Syntax errors are reported here, before anything runs
+    pub fn start(source: &str, app_handle: tauri::AppHandle) -> Result<Self, TelloError> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let handed_over = Arc::new(AtomicBool::new(false));
+        let finished = Arc::new(AtomicBool::new(false));
+        let deadline = Instant::now() + MAX_RUNTIME;
+        let ast = sandbox(Arc::clone(&stop), deadline)
+            .compile(source)
+            .map_err(|e| TelloError::InvalidArgument(format!("Script error: {}", e)))?;
+
+        let drone = Drone {
+            app_handle: app_handle.clone(),
+            runtime: tokio::runtime::Handle::current(),
+            stop: Arc::clone(&stop),
+            deadline,
+        };
+        let task_handed_over = Arc::clone(&handed_over);
+        let task_finished = Arc::clone(&finished);
+
+        // Scripts block between calls, so they get a thread of their own
+        tauri::async_runtime::spawn_blocking(move || {
+            println!("[Script] 📜 Running script");
+            let started = Instant::now();
+            let mut engine = sandbox(Arc::clone(&drone.stop), deadline);
+            let print_handle = app_handle.clone();
+            engine.on_print(move |text| output(&print_handle, OutputKind::Print, text.to_string()));
+            let debug_handle = app_handle.clone();
+            engine.on_debug(move |text, _, position| output(&debug_handle, OutputKind::Debug, format!("{} {}", position, text)));
+            register(&mut engine, &drone);
+
+            let result = engine.run_ast(&ast);
+            let stopped = drone.stop.load(Ordering::Relaxed);
+            if (result.is_err() || stopped) && !task_handed_over.load(Ordering::Relaxed) {
+                drone.halt();
+            }
+
+            match result {
+                Ok(()) => output(&app_handle, OutputKind::Finished, format!("Finished in {:.1}s", started.elapsed().as_secs_f64())),
+                Err(_) if stopped => output(&app_handle, OutputKind::Finished, "Stopped".to_string()),
+                Err(e) => {
+                    let message = match *e {
+                        EvalAltResult::ErrorTerminated(..) => format!("Script ran longer than {} minutes", MAX_RUNTIME.as_secs() / 60),
+                        _ => e.to_string(),
+                    };
+                    output(&app_handle, OutputKind::Error, message);
+                }
+            }
+            task_finished.store(true, Ordering::Relaxed);
+        });
+
+        Ok(Self { stop, handed_over, finished })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Relaxed)
+    }
+
+    // Takes effect at the script's next operation or blocking call
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    // Stop without touching the drone, which something else now controls
+    pub fn hand_over(&self) {
+        self.handed_over.store(true, Ordering::Relaxed);
+        self.stop();
+    }
+}
+
+impl Drop for ScriptRun {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// An engine with the limits applied but no drone functions
+fn sandbox(stop: Arc<AtomicBool>, deadline: Instant) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_module_resolver(rhai::module_resolvers::DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(MAX_CALL_LEVELS);
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.set_max_array_size(MAX_COLLECTION_SIZE);
+    engine.set_max_map_size(MAX_COLLECTION_SIZE);
+    engine.on_progress(move |_| {
+        if stop.load(Ordering::Relaxed) || Instant::now() >= deadline {
+            Some(Dynamic::UNIT)
+        } else {
+            None
+        }
+    });
+    engine
+}
+
+// What the script functions need to reach the drone
+#[derive(Clone)]
+struct Drone {
+    app_handle: tauri::AppHandle,
+    runtime: tokio::runtime::Handle,
+    stop: Arc<AtomicBool>,
+    deadline: Instant,
+}
+
+impl Drone {
+    // Runs `call` on the async runtime; a stop request or the time limit cuts it short
+    fn wait<T>(&self, call: impl Future<Output = Result<T, TelloError>>) -> ScriptResult<T> {
+        self.runtime.block_on(async {
+            tokio::select! {
+                result = call => result.map_err(script_error),
+                _ = self.interrupted() => Err("Script stopped".into()),
+            }
+        })
+    }
+
+    async fn interrupted(&self) {
+        while !self.stop.load(Ordering::Relaxed) && Instant::now() < self.deadline {
+            tokio::time::sleep(POLL).await;
+        }
+    }
+
+    fn send(&self, command: TelloCommand) -> ScriptResult<String> {
+        let state = self.app_handle.state::<AppState>();
+        let is_query = matches!(command, TelloCommand::Query(_));
+        let description = command.to_string();
+        let result = self.wait(crate::send_tello_command(&state, command))?;
+        // Reads answer with a value instead of "ok"
+        if !result.success && !is_query {
+            return Err(format!("'{}' failed: {}", description, result.message).into());
+        }
+        Ok(result.message)
+    }
+
+    // Takeoff, landing and emergency are never cut short: once the command is
+    // out, the drone's `flying` flag and the flight log must follow its reply
+    fn run(&self, call: impl Future<Output = Result<crate::CommandResult, TelloError>>) -> ScriptResult<()> {
+        let result = self.runtime.block_on(call).map_err(script_error)?;
+        if !result.success {
+            return Err(result.message.into());
+        }
+        Ok(())
+    }
+
+    fn takeoff(&self) -> ScriptResult<()> {
+        let state = self.app_handle.state::<AppState>();
+        self.run(crate::takeoff_drone(&state, &self.app_handle))
+    }
+
+    fn land(&self) -> ScriptResult<()> {
+        self.run(crate::land_drone(&self.app_handle.state::<AppState>()))
+    }
+
+    // command(): anything the pilot could type, except what would take the
+    // drone's network, streams or motors away from the app's own bookkeeping
+    fn raw(&self, command: TelloCommand) -> ScriptResult<String> {
+        match command {
+            TelloCommand::Takeoff => self.takeoff().map(|_| "ok".to_string()),
+            TelloCommand::Land => self.land().map(|_| "ok".to_string()),
+            TelloCommand::Emergency => Err("Use emergency() instead of command(\"emergency\")".into()),
+            TelloCommand::Command
+            | TelloCommand::MotorOn
+            | TelloCommand::MotorOff
+            | TelloCommand::ThrowFly
+            | TelloCommand::Wifi { .. }
+            | TelloCommand::Ap { .. }
+            | TelloCommand::Port { .. }
+            | TelloCommand::StreamOn
+            | TelloCommand::StreamOff
+            | TelloCommand::DownVision(_)
+            | TelloCommand::SetFps(_)
+            | TelloCommand::SetResolution(_)
+            | TelloCommand::SetBitrate(_)
+            | TelloCommand::MissionPadOn
+            | TelloCommand::MissionPadOff
+            | TelloCommand::MissionPadDirection(_) => {
+                let keyword = command.to_string();
+                let keyword = keyword.split_whitespace().next().unwrap_or_default();
+                Err(format!("'{}' is not available to scripts", keyword).into())
+            }
+            _ => self.send(command),
+        }
+    }
+
+    fn sleep(&self, seconds: f64) -> ScriptResult<()> {
+        if !seconds.is_finite() || seconds < 0.0 {
+            return Err(format!("Cannot sleep for {}s", seconds).into());
+        }
+        let duration = Duration::from_secs_f64(seconds);
+        self.wait(async {
+            tokio::time::sleep(duration).await;
+            Ok(())
+        })
+    }
+
+    fn reading(&self, read: Reading) -> INT {
+        read(&self.app_handle.state::<AppState>().drone.lock().unwrap())
+    }
+
+    // After an error or stop: centre the sticks and hover. `stop` skips the
+    // command queue, so it also cuts short a manoeuvre still in flight
+    fn halt(&self) {
+        let state = self.app_handle.state::<AppState>();
+        crate::stop_rc_stream(&state);
+        if state.drone.lock().unwrap().flying {
+            if let Err(e) = self.runtime.block_on(crate::send_tello_command(&state, TelloCommand::Stop)) {
+                eprintln!("[Script] ❌ Failed to stop the drone: {}", e);
+            }
+        }
+    }
+}
+
+fn register(engine: &mut Engine, drone: &Drone) {
+    let d = drone.clone();
+    engine.register_fn("takeoff", move || d.takeoff());
+    let d = drone.clone();
+    engine.register_fn("land", move || d.land());
+    let d = drone.clone();
+    engine.register_fn("emergency", move || d.run(crate::emergency_stop(&d.app_handle.state::<AppState>())));
+    let d = drone.clone();
+    engine.register_fn("stop", move || d.send(TelloCommand::Stop).map(|_| ()));
+
+    let moves = [
+        ("up", Direction::Up),
+        ("down", Direction::Down),
+        ("left", Direction::Left),
+        ("right", Direction::Right),
+        ("forward", Direction::Forward),
+        ("back", Direction::Back),
+    ];
+    for (name, direction) in moves {
+        let d = drone.clone();
+        engine.register_fn(name, move |distance: INT| {
+            d.send(TelloCommand::movement(direction, int(distance)?).map_err(script_error)?).map(|_| ())
+        });
+    }
+    for (name, rotation) in [("cw", Rotation::Clockwise), ("ccw", Rotation::CounterClockwise)] {
+        let d = drone.clone();
+        engine.register_fn(name, move |degrees: INT| {
+            d.send(TelloCommand::rotate(rotation, int(degrees)?).map_err(script_error)?).map(|_| ())
+        });
+    }
+
+    let d = drone.clone();
+    engine.register_fn("flip", move |direction: &str| d.send(TelloCommand::flip(direction).map_err(script_error)?).map(|_| ()));
+    let d = drone.clone();
+    engine.register_fn("go", move |x: INT, y: INT, z: INT, speed: INT| {
+        let command = TelloCommand::go(int(x)?, int(y)?, int(z)?, int(speed)?, None).map_err(script_error)?;
+        d.send(command).map(|_| ())
+    });
+    let d = drone.clone();
+    engine.register_fn("speed", move |speed: INT| d.send(TelloCommand::speed(int(speed)?).map_err(script_error)?).map(|_| ()));
+    // Flight and read commands; returns the drone's reply, e.g. command("battery?")
+    let d = drone.clone();
+    engine.register_fn("command", move |command: &str| d.raw(command.parse().map_err(script_error)?));
+
+    // Held by the RC stream until the next rc() or the dead-man timeout
+    let d = drone.clone();
+    engine.register_fn("rc", move |left_right: INT, forward_back: INT, up_down: INT, yaw: INT| -> ScriptResult<()> {
+        let setpoint = RcSetpoint::new(int(left_right)?, int(forward_back)?, int(up_down)?, int(yaw)?).map_err(script_error)?;
+        let state = d.app_handle.state::<AppState>();
+        let stream = state.rc_stream.lock().unwrap();
+        stream.as_ref().ok_or_else(|| script_error(TelloError::NotConnected))?.set(RcSource::Autopilot, setpoint).map_err(script_error)
+    });
+
+    let d = drone.clone();
+    engine.register_fn("sleep", move |seconds: INT| d.sleep(seconds as f64));
+    let d = drone.clone();
+    engine.register_fn("sleep", move |seconds: FLOAT| d.sleep(seconds));
+
+    let d = drone.clone();
+    engine.register_fn("take_picture", move || -> ScriptResult<String> {
+        let state = d.app_handle.state::<AppState>();
+        Ok(d.wait(crate::capture_picture(&state, None))?.path)
+    });
+
+    // Telemetry from the state stream, without a round trip to the drone
+    let readings: [(&str, Reading); 7] = [
+        ("battery", |drone| drone.battery as INT),
+        ("height", |drone| drone.height as INT),
+        ("tof", |drone| drone.tof as INT),
+        ("yaw", |drone| drone.yaw as INT),
+        ("pitch", |drone| drone.pitch as INT),
+        ("roll", |drone| drone.roll as INT),
+        ("temperature", |drone| drone.temperature as INT),
+    ];
+    for (name, read) in readings {
+        let d = drone.clone();
+        engine.register_fn(name, move || d.reading(read));
+    }
+    let d = drone.clone();
+    engine.register_fn("flying", move || d.app_handle.state::<AppState>().drone.lock().unwrap().flying);
+}
+
+fn int(value: INT) -> ScriptResult<i32> {
+    i32::try_from(value).map_err(|_| format!("{} is out of range", value).into())
+}
+
+fn script_error(e: impl ToString) -> Box<EvalAltResult> {
+    e.to_string().into()
+}
+
+fn output(app_handle: &tauri::AppHandle, kind: OutputKind, message: String) {
+    println!("[Script] 📜 {:?}: {}", kind, message);
+    if let Err(e) = app_handle.emit_all("script-output", ScriptOutput { kind, message }) {
+        eprintln!("[Script] ❌ Failed to emit script-output: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn engine(stop: bool, deadline: Instant) -> Engine {
+        sandbox(Arc::new(AtomicBool::new(stop)), deadline)
+    }
+
+    #[test]
+    fn runs_plain_scripts() {
+        let engine = engine(false, Instant::now() + MAX_RUNTIME);
+        let sum: INT = engine.eval("let total = 0; for i in 1..=10 { total += i; } total").unwrap();
+        assert_eq!(sum, 55);
+    }
+
+    #[test]
+    fn stop_and_deadline_end_endless_loops() {
+        let stopped = engine(true, Instant::now() + MAX_RUNTIME).run("loop {}").unwrap_err();
+        assert!(matches!(*stopped, EvalAltResult::ErrorTerminated(..)), "{}", stopped);
+
+        let timed_out = engine(false, Instant::now()).run("loop {}").unwrap_err();
+        assert!(matches!(*timed_out, EvalAltResult::ErrorTerminated(..)), "{}", timed_out);
+    }
+
+    #[test]
+    fn has_no_way_out_of_the_sandbox() {
+        let engine = engine(false, Instant::now() + MAX_RUNTIME);
+        assert!(engine.run(r#"import "secrets" as s;"#).is_err());
+        assert!(engine.compile(r#"eval("1 + 1")"#).is_err());
+        assert!(engine.run(r#"let s = "x"; loop { s += s; }"#).is_err());
+    }
+}
@@ -0,0 +1,1027 @@
+// Tello SDK simulator
+// Stands in for the drone on localhost: answers the text protocol on the
+// command port, pushes state packets and streams synthetic H.264 video to the
+// client that sent "command", just like the real aircraft does.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::error::TelloError;
+
+const TICK: Duration = Duration::from_millis(100);
+const VIDEO_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+const VIDEO_CHUNK_SIZE: usize = 1460;
+
+const TAKEOFF_HEIGHT: f32 = 80.0;
+const MIN_FLYING_HEIGHT: f32 = 20.0;
+const RC_MAX_SPEED: f32 = 100.0; // cm/s at full stick
+const RC_MAX_YAW_RATE: f32 = 100.0; // deg/s at full stick
+const ROTATE_RATE: f32 = 90.0; // deg/s for cw/ccw
+
+// Battery drain in percent per second
+const DRAIN_FLYING: f32 = 0.13;
+const DRAIN_IDLE: f32 = 0.01;
+
+// Synthetic video size in macroblocks (320x240)
+const VIDEO_WIDTH_MBS: usize = 20;
+const VIDEO_HEIGHT_MBS: usize = 15;
+
+#[derive(Clone, Debug)]
+pub struct SimulatorConfig {
+    // Address the simulated drone listens on for commands
+    pub command_addr: SocketAddr,
+    // Ports on the client that state packets and video are pushed to
+    pub state_port: u16,
+    pub video_port: u16,
+    // Raw H.264 elementary stream to loop instead of the synthetic picture
+    pub video_file: Option<PathBuf>,
+    // Answer to `sn?`; give each simulator of a swarm its own
+    pub serial: String,
+}
+
+impl Default for SimulatorConfig {
+    fn default() -> Self {
+        Self {
+            command_addr: "127.0.0.1:8889".parse().unwrap(),
+            state_port: 8890,
+            video_port: 11111,
+            video_file: None,
+            serial: "0TQZSIMULATOR".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum FlightPhase {
+    Landed,
+    Flying,
+}
+
+// A command the drone only answers once it has finished flying it
+struct Maneuver {
+    target: [f32; 3],
+    target_yaw: f32,
+    speed: f32,
+    min_until: Instant,
+    lands: bool,
+    done: oneshot::Sender<String>,
+}
+
+struct SimDrone {
+    serial: String,
+    sdk_mode: bool,
+    client: Option<SocketAddr>,
+    phase: FlightPhase,
+    // x forward and y right of the takeoff heading, z up, all in cm
+    position: [f32; 3],
+    velocity: [f32; 3],
+    yaw: f32,
+    rc: [i32; 4],
+    speed: f32,
+    battery: f32,
+    flight_time: f32,
+    streaming: bool,
+    maneuver: Option<Maneuver>,
+    started: Instant,
+}
+
+impl SimDrone {
+    fn new(serial: String) -> Self {
+        Self {
+            serial,
+            sdk_mode: false,
+            client: None,
+            phase: FlightPhase::Landed,
+            position: [0.0; 3],
+            velocity: [0.0; 3],
+            yaw: 0.0,
+            rc: [0; 4],
+            speed: 100.0,
+            battery: 100.0,
+            flight_time: 0.0,
+            streaming: false,
+            maneuver: None,
+            started: Instant::now(),
+        }
+    }
+
+    fn flying(&self) -> bool {
+        self.phase == FlightPhase::Flying
+    }
+
+    fn temperature(&self) -> (i32, i32) {
+        let warm = (self.started.elapsed().as_secs_f32() / 10.0).min(25.0) as i32;
+        (55 + warm, 58 + warm)
+    }
+
+    fn tof(&self) -> i32 {
+        // The ToF sensor sits ~10 cm above the ground when landed
+        self.position[2] as i32 + 10
+    }
+
+    fn barometer(&self) -> f32 {
+        100.0 + self.position[2] / 100.0
+    }
+
+    fn cancel_maneuver(&mut self, reply: &str) {
+        if let Some(maneuver) = self.maneuver.take() {
+            let _ = maneuver.done.send(reply.to_string());
+        }
+    }
+
+    fn start_maneuver(&mut self, target: [f32; 3], target_yaw: f32, min_duration: Duration) -> oneshot::Receiver<String> {
+        self.start_maneuver_with(target, target_yaw, min_duration, false)
+    }
+
+    fn start_maneuver_with(
+        &mut self,
+        target: [f32; 3],
+        target_yaw: f32,
+        min_duration: Duration,
+        lands: bool,
+    ) -> oneshot::Receiver<String> {
+        let (done, rx) = oneshot::channel();
+        self.cancel_maneuver("error Interrupted");
+        self.rc = [0; 4];
+        self.maneuver = Some(Maneuver {
+            target,
+            target_yaw,
+            speed: self.speed,
+            min_until: Instant::now() + min_duration,
+            lands,
+            done,
+        });
+        rx
+    }
+
+    // Target position for a move of `distance` cm along a body-frame direction
+    fn body_target(&self, forward: f32, right: f32, up: f32) -> [f32; 3] {
+        let (sin, cos) = self.yaw.to_radians().sin_cos();
+        [
+            self.position[0] + forward * cos - right * sin,
+            self.position[1] + forward * sin + right * cos,
+            self.position[2] + up,
+        ]
+    }
+
+    fn step(&mut self, dt: f32) {
+        let drain = if self.flying() { DRAIN_FLYING } else { DRAIN_IDLE };
+        self.battery = (self.battery - drain * dt).max(0.0);
+
+        if self.flying() {
+            self.flight_time += dt;
+
+            if self.battery <= 0.0 && self.maneuver.as_ref().is_none_or(|m| !m.lands) {
+                let target = [self.position[0], self.position[1], 0.0];
+                let yaw = self.yaw;
+                // Nobody asked for this landing, so nobody waits for its reply
+                drop(self.start_maneuver_with(target, yaw, Duration::ZERO, true));
+            }
+        }
+
+        let previous = self.position;
+
+        if let Some(maneuver) = self.maneuver.as_mut() {
+            let delta = [
+                maneuver.target[0] - self.position[0],
+                maneuver.target[1] - self.position[1],
+                maneuver.target[2] - self.position[2],
+            ];
+            let distance = (delta[0] * delta[0] + delta[1] * delta[1] + delta[2] * delta[2]).sqrt();
+            let max_step = maneuver.speed * dt;
+
+            if distance <= max_step {
+                self.position = maneuver.target;
+            } else {
+                for (axis, d) in delta.iter().enumerate() {
+                    self.position[axis] += d / distance * max_step;
+                }
+            }
+
+            let yaw_error = wrap_degrees(maneuver.target_yaw - self.yaw);
+            let max_turn = ROTATE_RATE * dt;
+            self.yaw = if yaw_error.abs() <= max_turn {
+                maneuver.target_yaw
+            } else {
+                self.yaw + max_turn * yaw_error.signum()
+            };
+            self.yaw = wrap_degrees(self.yaw);
+
+            let arrived = self.position == maneuver.target
+                && wrap_degrees(self.yaw - maneuver.target_yaw).abs() < f32::EPSILON
+                && Instant::now() >= maneuver.min_until;
+
+            if arrived {
+                let maneuver = self.maneuver.take().unwrap();
+                if maneuver.lands {
+                    self.phase = FlightPhase::Landed;
+                }
+                let _ = maneuver.done.send("ok".to_string());
+            }
+        } else if self.flying() {
+            let [lr, fb, ud, yw] = self.rc.map(|v| v as f32 / 100.0);
+            let (sin, cos) = self.yaw.to_radians().sin_cos();
+            self.position[0] += (fb * cos - lr * sin) * RC_MAX_SPEED * dt;
+            self.position[1] += (fb * sin + lr * cos) * RC_MAX_SPEED * dt;
+            self.position[2] += ud * RC_MAX_SPEED * dt;
+            self.yaw = wrap_degrees(self.yaw + yw * RC_MAX_YAW_RATE * dt);
+        }
+
+        // Manoeuvres (takeoff, land) move through the low band on purpose
+        if self.flying() && self.maneuver.is_none() {
+            self.position[2] = self.position[2].max(MIN_FLYING_HEIGHT);
+        }
+
+        for (velocity, (now, before)) in self.velocity.iter_mut().zip(self.position.iter().zip(previous)) {
+            *velocity = (now - before) / dt;
+        }
+    }
+
+    fn state_packet(&self) -> String {
+        let (templ, temph) = self.temperature();
+        let (pitch, roll) = if self.flying() {
+            ((self.velocity[0] / 20.0) as i32, (self.velocity[1] / 20.0) as i32)
+        } else {
+            (0, 0)
+        };
+
+        // Velocities are reported in dm/s
+        format!(
+            "pitch:{};roll:{};yaw:{};vgx:{};vgy:{};vgz:{};templ:{};temph:{};tof:{};h:{};bat:{};baro:{:.2};time:{};agx:{:.2};agy:{:.2};agz:{:.2};\r\n",
+            pitch,
+            roll,
+            self.yaw.round() as i32,
+            (self.velocity[0] / 10.0).round() as i32,
+            (self.velocity[1] / 10.0).round() as i32,
+            (self.velocity[2] / 10.0).round() as i32,
+            templ,
+            temph,
+            self.tof(),
+            self.position[2].round() as i32,
+            self.battery.ceil() as i32,
+            self.barometer(),
+            self.flight_time as i32,
+            0.0,
+            0.0,
+            -1000.0,
+        )
+    }
+}
+
+fn wrap_degrees(angle: f32) -> f32 {
+    let mut wrapped = angle % 360.0;
+    if wrapped > 180.0 {
+        wrapped -= 360.0;
+    } else if wrapped <= -180.0 {
+        wrapped += 360.0;
+    }
+    wrapped
+}
+
+// What the command loop should do with a parsed command
+enum Reply {
+    Now(String),
+    After(oneshot::Receiver<String>),
+    None,
+}
+
+fn handle_command(drone: &mut SimDrone, command: &str) -> Reply {
+    let parts: Vec<&str> = command.split_whitespace().collect();
+    let Some(&keyword) = parts.first() else {
+        return Reply::None;
+    };
+
+    if keyword == "command" {
+        drone.sdk_mode = true;
+        return Reply::Now("ok".to_string());
+    }
+
+    // A real Tello ignores everything until it has been put into SDK mode
+    if !drone.sdk_mode {
+        return Reply::None;
+    }
+
+    let args: Vec<i32> = parts[1..].iter().filter_map(|p| p.parse().ok()).collect();
+    let ok = || Reply::Now("ok".to_string());
+    let error = |reason: &str| Reply::Now(format!("error {}", reason));
+    let out_of_range = || Reply::Now("out of range".to_string());
+    let in_range = |value: i32, min: i32, max: i32| (min..=max).contains(&value);
+
+    let needs_flight = [
+        "land", "up", "down", "left", "right", "forward", "back", "cw", "ccw", "flip", "go", "curve", "stop",
+    ];
+    if needs_flight.contains(&keyword) && !drone.flying() {
+        return error("Not flying");
+    }
+
+    match keyword {
+        "takeoff" => {
+            if drone.flying() {
+                return error("Already flying");
+            }
+            if drone.battery < 10.0 {
+                return error("Low battery");
+            }
+            drone.phase = FlightPhase::Flying;
+            let target = [drone.position[0], drone.position[1], TAKEOFF_HEIGHT];
+            let yaw = drone.yaw;
+            Reply::After(drone.start_maneuver(target, yaw, Duration::from_secs(2)))
+        }
+        "land" => {
+            let target = [drone.position[0], drone.position[1], 0.0];
+            let yaw = drone.yaw;
+            Reply::After(drone.start_maneuver_with(target, yaw, Duration::from_secs(1), true))
+        }
+        "emergency" => {
+            drone.cancel_maneuver("error Motor stop");
+            drone.phase = FlightPhase::Landed;
+            drone.position[2] = 0.0;
+            drone.rc = [0; 4];
+            ok()
+        }
+        "stop" => {
+            drone.cancel_maneuver("error Stopped");
+            drone.rc = [0; 4];
+            ok()
+        }
+        "up" | "down" | "left" | "right" | "forward" | "back" => {
+            let Some(&distance) = args.first() else {
+                return error("Invalid argument");
+            };
+            if !in_range(distance, 20, 500) {
+                return out_of_range();
+            }
+            let d = distance as f32;
+            let target = match keyword {
+                "up" => drone.body_target(0.0, 0.0, d),
+                "down" => drone.body_target(0.0, 0.0, -d),
+                "left" => drone.body_target(0.0, -d, 0.0),
+                "right" => drone.body_target(0.0, d, 0.0),
+                "forward" => drone.body_target(d, 0.0, 0.0),
+                _ => drone.body_target(-d, 0.0, 0.0),
+            };
+            let yaw = drone.yaw;
+            Reply::After(drone.start_maneuver(target, yaw, Duration::ZERO))
+        }
+        "cw" | "ccw" => {
+            let Some(&degrees) = args.first() else {
+                return error("Invalid argument");
+            };
+            if !in_range(degrees, 1, 360) {
+                return out_of_range();
+            }
+            let signed = if keyword == "cw" { degrees } else { -degrees } as f32;
+            let position = drone.position;
+            let target_yaw = wrap_degrees(drone.yaw + signed);
+            let min_duration = Duration::from_secs_f32(degrees as f32 / ROTATE_RATE);
+            Reply::After(drone.start_maneuver(position, target_yaw, min_duration))
+        }
+        "flip" => {
+            if !matches!(parts.get(1), Some(&("l" | "r" | "f" | "b"))) {
+                return error("Invalid argument");
+            }
+            if drone.battery < 50.0 {
+                return error("Low battery");
+            }
+            let position = drone.position;
+            let yaw = drone.yaw;
+            Reply::After(drone.start_maneuver(position, yaw, Duration::from_millis(1500)))
+        }
+        "go" | "curve" => {
+            // There are no mission pads to fly relative to
+            if parts.last().is_some_and(|part| part.starts_with('m')) {
+                return error("No mission pad detected");
+            }
+            // curve flies straight to its end point; good enough for a simulator
+            let (x, y, z, speed) = match (keyword, args.as_slice()) {
+                ("go", [x, y, z, speed, ..]) => (*x, *y, *z, *speed),
+                ("curve", [_, _, _, x, y, z, speed, ..]) => (*x, *y, *z, *speed),
+                _ => return error("Invalid argument"),
+            };
+            if !in_range(speed, 10, 100) || [x, y, z].iter().any(|v| !in_range(*v, -500, 500)) {
+                return out_of_range();
+            }
+            let target = drone.body_target(x as f32, -y as f32, z as f32);
+            let yaw = drone.yaw;
+            let previous_speed = drone.speed;
+            drone.speed = speed as f32;
+            let reply = drone.start_maneuver(target, yaw, Duration::ZERO);
+            drone.speed = previous_speed;
+            Reply::After(reply)
+        }
+        "jump" => error("No mission pad detected"),
+        "rc" => {
+            if let [lr, fb, ud, yw] = args.as_slice() {
+                drone.rc = [*lr, *fb, *ud, *yw].map(|v| v.clamp(-100, 100));
+            }
+            // The real drone never answers rc
+            Reply::None
+        }
+        "speed" => match args.first() {
+            Some(&speed) if in_range(speed, 10, 100) => {
+                drone.speed = speed as f32;
+                ok()
+            }
+            _ => out_of_range(),
+        },
+        "streamon" => {
+            drone.streaming = true;
+            ok()
+        }
+        "streamoff" => {
+            drone.streaming = false;
+            ok()
+        }
+        "mon" | "moff" | "mdirection" | "downvision" | "setfps" | "setresolution" | "setbitrate" | "wifi" | "ap" => ok(),
+        "speed?" => Reply::Now(format!("{:.1}", drone.speed)),
+        "battery?" => Reply::Now(format!("{}", drone.battery.ceil() as i32)),
+        "time?" => Reply::Now(format!("{}s", drone.flight_time as i32)),
+        "height?" => Reply::Now(format!("{}dm", (drone.position[2] / 10.0).round() as i32)),
+        "temp?" => {
+            let (low, high) = drone.temperature();
+            Reply::Now(format!("{}~{}C", low, high))
+        }
+        "attitude?" => Reply::Now(format!("pitch:0;roll:0;yaw:{};", drone.yaw.round() as i32)),
+        "baro?" => Reply::Now(format!("{:.6}", drone.barometer())),
+        "acceleration?" => Reply::Now("agx:0.00;agy:0.00;agz:-1000.00;".to_string()),
+        "tof?" => Reply::Now(format!("{}mm", drone.tof() * 10)),
+        "wifi?" => Reply::Now("90".to_string()),
+        "sdk?" => Reply::Now("30".to_string()),
+        "sn?" => Reply::Now(drone.serial.clone()),
+        _ => Reply::Now(format!("unknown command: {}", keyword)),
+    }
+}
+
+pub struct TelloSimulator {
+    config: SimulatorConfig,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl TelloSimulator {
+    pub async fn start(config: SimulatorConfig) -> Result<Self, TelloError> {
+        let bind_failed = |port: u16| move |e: std::io::Error| TelloError::BindFailed { port, reason: e.to_string() };
+        let command_socket = Arc::new(
+            UdpSocket::bind(config.command_addr)
+                .await
+                .map_err(bind_failed(config.command_addr.port()))?,
+        );
+        // Port 0 binds any free port; report the one we got
+        let config = SimulatorConfig {
+            command_addr: command_socket.local_addr().map_err(bind_failed(config.command_addr.port()))?,
+            ..config
+        };
+        let push_socket = Arc::new(
+            UdpSocket::bind((config.command_addr.ip(), 0))
+                .await
+                .map_err(bind_failed(0))?,
+        );
+
+        let recorded_video = match &config.video_file {
+            Some(path) => Some(
+                std::fs::read(path).map_err(|e| TelloError::Io(format!("Failed to read {}: {}", path.display(), e)))?,
+            ),
+            None => None,
+        };
+
+        println!("[Simulator] 🛩️ Simulated Tello listening on {}", config.command_addr);
+
+        let drone = Arc::new(Mutex::new(SimDrone::new(config.serial.clone())));
+
+        let tasks = vec![
+            tokio::spawn(command_loop(Arc::clone(&command_socket), Arc::clone(&drone))),
+            tokio::spawn(state_loop(Arc::clone(&push_socket), Arc::clone(&drone), config.state_port)),
+            tokio::spawn(video_loop(push_socket, drone, config.video_port, recorded_video)),
+        ];
+
+        Ok(Self { config, tasks })
+    }
+
+    pub fn command_addr(&self) -> SocketAddr {
+        self.config.command_addr
+    }
+}
+
+impl Drop for TelloSimulator {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+        println!("[Simulator] 🛑 Simulated Tello stopped");
+    }
+}
+
+async fn command_loop(socket: Arc<UdpSocket>, drone: Arc<Mutex<SimDrone>>) {
+    let mut buf = [0u8; 1024];
+
+    loop {
+        let (size, client) = match socket.recv_from(&mut buf).await {
+            Ok(received) => received,
+            Err(e) => {
+                eprintln!("[Simulator] ❌ Receive failed: {}", e);
+                continue;
+            }
+        };
+
+        let command = String::from_utf8_lossy(&buf[..size]).trim().to_string();
+
+        let reply = {
+            let mut drone = drone.lock().unwrap();
+            drone.client = Some(client);
+            handle_command(&mut drone, &command)
+        };
+
+        match reply {
+            Reply::Now(response) => {
+                let _ = socket.send_to(response.as_bytes(), client).await;
+            }
+            Reply::After(done) => {
+                let socket = Arc::clone(&socket);
+                tokio::spawn(async move {
+                    if let Ok(response) = done.await {
+                        let _ = socket.send_to(response.as_bytes(), client).await;
+                    }
+                });
+            }
+            Reply::None => {}
+        }
+    }
+}
+
+async fn state_loop(socket: Arc<UdpSocket>, drone: Arc<Mutex<SimDrone>>, state_port: u16) {
+    let mut interval = tokio::time::interval(TICK);
+    let mut last = Instant::now();
+
+    loop {
+        interval.tick().await;
+
+        let now = Instant::now();
+        let dt = now.duration_since(last).as_secs_f32();
+        last = now;
+
+        // The first tick fires immediately
+        if dt < 0.001 {
+            continue;
+        }
+
+        let packet = {
+            let mut drone = drone.lock().unwrap();
+            drone.step(dt);
+            match drone.client {
+                Some(client) if drone.sdk_mode => Some((drone.state_packet(), client)),
+                _ => None,
+            }
+        };
+
+        if let Some((packet, client)) = packet {
+            let _ = socket.send_to(packet.as_bytes(), (client.ip(), state_port)).await;
+        }
+    }
+}
+
+async fn video_loop(
+    socket: Arc<UdpSocket>,
+    drone: Arc<Mutex<SimDrone>>,
+    video_port: u16,
+    recorded_video: Option<Vec<u8>>,
+) {
+    let mut interval = tokio::time::interval(VIDEO_FRAME_INTERVAL);
+    let recorded_frames = recorded_video.as_deref().map(split_access_units);
+    let mut frame_index = 0usize;
+
+    loop {
+        interval.tick().await;
+
+        let (client, frame) = {
+            let drone = drone.lock().unwrap();
+            let client = match drone.client {
+                Some(client) if drone.streaming => client,
+                _ => continue,
+            };
+            let frame = match &recorded_frames {
+                Some(frames) if !frames.is_empty() => frames[frame_index % frames.len()].clone(),
+                _ => synthetic_frame(&drone, frame_index),
+            };
+            (client, frame)
+        };
+
+        frame_index += 1;
+
+        for chunk in frame.chunks(VIDEO_CHUNK_SIZE) {
+            let _ = socket.send_to(chunk, (client.ip(), video_port)).await;
+        }
+    }
+}
+
+// Split a recorded Annex B stream into one chunk per picture: every non-VCL
+// NAL unit is kept with the slice that follows it.
+fn split_access_units(stream: &[u8]) -> Vec<Vec<u8>> {
+    let mut starts = Vec::new();
+    let mut i = 0;
+    while i + 3 <= stream.len() {
+        if stream[i..i + 3] == [0, 0, 1] {
+            let start = if i > 0 && stream[i - 1] == 0 { i - 1 } else { i };
+            starts.push(start);
+            i += 3;
+        } else {
+            i += 1;
+        }
+    }
+
+    let mut frames = Vec::new();
+    let mut current = Vec::new();
+    for (n, &start) in starts.iter().enumerate() {
+        let end = starts.get(n + 1).copied().unwrap_or(stream.len());
+        let nal = &stream[start..end];
+        let header = nal.iter().position(|&b| b == 1).and_then(|p| nal.get(p + 1)).copied().unwrap_or(0);
+        current.extend_from_slice(nal);
+        if matches!(header & 0x1f, 1 | 5) {
+            frames.push(std::mem::take(&mut current));
+        }
+    }
+    frames
+}
+
+// Minimal H.264 writer: Baseline profile, every picture an IDR made of I_PCM
+// macroblocks, so no real encoder is needed and any decoder can show it.
+struct BitWriter {
+    bytes: Vec<u8>,
+    current: u8,
+    used: u8,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self { bytes: Vec::new(), current: 0, used: 0 }
+    }
+
+    fn bit(&mut self, bit: bool) {
+        self.current = (self.current << 1) | bit as u8;
+        self.used += 1;
+        if self.used == 8 {
+            self.bytes.push(self.current);
+            self.current = 0;
+            self.used = 0;
+        }
+    }
+
+    fn bits(&mut self, value: u32, count: u8) {
+        for i in (0..count).rev() {
+            self.bit((value >> i) & 1 == 1);
+        }
+    }
+
+    fn ue(&mut self, value: u32) {
+        let coded = value + 1;
+        let length = 32 - coded.leading_zeros() as u8;
+        self.bits(0, length - 1);
+        self.bits(coded, length);
+    }
+
+    fn se(&mut self, value: i32) {
+        let mapped = if value > 0 { 2 * value as u32 - 1 } else { 2 * value.unsigned_abs() };
+        self.ue(mapped);
+    }
+
+    fn align_zero(&mut self) {
+        while self.used != 0 {
+            self.bit(false);
+        }
+    }
+
+    fn byte(&mut self, byte: u8) {
+        self.bytes.push(byte);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        // rbsp_trailing_bits
+        self.bit(true);
+        self.align_zero();
+        self.bytes
+    }
+}
+
+fn nal_unit(header: u8, rbsp: &[u8]) -> Vec<u8> {
+    let mut nal = vec![0, 0, 0, 1, header];
+    let mut zeros = 0;
+    for &byte in rbsp {
+        if zeros >= 2 && byte <= 3 {
+            nal.push(3);
+            zeros = 0;
+        }
+        nal.push(byte);
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+    }
+    nal
+}
+
+fn sps() -> Vec<u8> {
+    let mut w = BitWriter::new();
+    w.bits(66, 8); // profile_idc: Baseline
+    w.bits(0b1100_0000, 8); // constraint_set0/1
+    w.bits(30, 8); // level_idc 3.0
+    w.ue(0); // seq_parameter_set_id
+    w.ue(0); // log2_max_frame_num_minus4
+    w.ue(2); // pic_order_cnt_type
+    w.ue(1); // max_num_ref_frames
+    w.bit(false); // gaps_in_frame_num_value_allowed_flag
+    w.ue(VIDEO_WIDTH_MBS as u32 - 1);
+    w.ue(VIDEO_HEIGHT_MBS as u32 - 1);
+    w.bit(true); // frame_mbs_only_flag
+    w.bit(true); // direct_8x8_inference_flag
+    w.bit(false); // frame_cropping_flag
+    w.bit(false); // vui_parameters_present_flag
+    nal_unit(0x67, &w.finish())
+}
+
+fn pps() -> Vec<u8> {
+    let mut w = BitWriter::new();
+    w.ue(0); // pic_parameter_set_id
+    w.ue(0); // seq_parameter_set_id
+    w.bit(false); // entropy_coding_mode_flag: CAVLC
+    w.bit(false); // bottom_field_pic_order_in_frame_present_flag
+    w.ue(0); // num_slice_groups_minus1
+    w.ue(0); // num_ref_idx_l0_default_active_minus1
+    w.ue(0); // num_ref_idx_l1_default_active_minus1
+    w.bit(false); // weighted_pred_flag
+    w.bits(0, 2); // weighted_bipred_idc
+    w.se(0); // pic_init_qp_minus26
+    w.se(0); // pic_init_qs_minus26
+    w.se(0); // chroma_qp_index_offset
+    w.bit(true); // deblocking_filter_control_present_flag
+    w.bit(false); // constrained_intra_pred_flag
+    w.bit(false); // redundant_pic_cnt_present_flag
+    nal_unit(0x68, &w.finish())
+}
+
+fn synthetic_frame(drone: &SimDrone, frame_index: usize) -> Vec<u8> {
+    let height = VIDEO_HEIGHT_MBS * 16;
+
+    // The horizon drops as the drone climbs and the ground stripes scroll with yaw
+    let horizon = (height as f32 * 0.35 + drone.position[2] * 0.4).clamp(8.0, height as f32 - 8.0) as usize;
+    let scroll = (drone.yaw * 4.0) as i32;
+    let luma = |x: usize, y: usize| -> u8 {
+        if y < horizon {
+            190 - (y * 40 / height) as u8
+        } else if (x as i32 + scroll).rem_euclid(64) < 32 {
+            90
+        } else {
+            60
+        }
+    };
+    let chroma = |y: usize, sky: (u8, u8), ground: (u8, u8)| if y * 2 < horizon { sky } else { ground };
+
+    let mut w = BitWriter::new();
+    w.ue(0); // first_mb_in_slice
+    w.ue(7); // slice_type: I (all slices)
+    w.ue(0); // pic_parameter_set_id
+    w.bits(0, 4); // frame_num
+    w.ue((frame_index % 65536) as u32); // idr_pic_id
+    w.bit(false); // no_output_of_prior_pics_flag
+    w.bit(false); // long_term_reference_flag
+    w.se(0); // slice_qp_delta
+    w.ue(1); // disable_deblocking_filter_idc
+
+    for mb_y in 0..VIDEO_HEIGHT_MBS {
+        for mb_x in 0..VIDEO_WIDTH_MBS {
+            w.ue(25); // mb_type: I_PCM
+            w.align_zero();
+            for y in 0..16 {
+                for x in 0..16 {
+                    w.byte(luma(mb_x * 16 + x, mb_y * 16 + y));
+                }
+            }
+            for plane in 0..2 {
+                for y in 0..8 {
+                    for _ in 0..8 {
+                        let (cb, cr) = chroma(mb_y * 8 + y, (150, 110), (110, 125));
+                        w.byte(if plane == 0 { cb } else { cr });
+                    }
+                }
+            }
+        }
+    }
+
+    let mut frame = sps();
+    frame.extend(pps());
+    frame.extend(nal_unit(0x65, &w.finish()));
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state_stream::parse_state;
+
+    fn drone() -> SimDrone {
+        let mut drone = SimDrone::new("0TQZTEST".to_string());
+        assert_eq!(now(handle_command(&mut drone, "command")), "ok");
+        drone
+    }
+
+    fn now(reply: Reply) -> String {
+        match reply {
+            Reply::Now(response) => response,
+            _ => panic!("expected an immediate reply"),
+        }
+    }
+
+    // Steps the drone until the manoeuvre answers, skipping its minimum duration
+    fn fly(drone: &mut SimDrone, reply: Reply) -> String {
+        let Reply::After(mut done) = reply else {
+            panic!("expected a manoeuvre");
+        };
+        for _ in 0..10_000 {
+            if let Some(maneuver) = drone.maneuver.as_mut() {
+                maneuver.min_until = Instant::now();
+            }
+            drone.step(0.1);
+            if let Ok(response) = done.try_recv() {
+                return response;
+            }
+        }
+        panic!("the manoeuvre never finished");
+    }
+
+    fn command(drone: &mut SimDrone, command: &str) -> String {
+        match handle_command(drone, command) {
+            Reply::Now(response) => response,
+            reply => fly(drone, reply),
+        }
+    }
+
+    fn airborne() -> SimDrone {
+        let mut drone = drone();
+        assert_eq!(command(&mut drone, "takeoff"), "ok");
+        drone
+    }
+
+    #[test]
+    fn ignores_everything_before_sdk_mode() {
+        let mut drone = SimDrone::new("0TQZTEST".to_string());
+        assert!(matches!(handle_command(&mut drone, "takeoff"), Reply::None));
+        assert!(matches!(handle_command(&mut drone, "battery?"), Reply::None));
+        assert!(!drone.flying());
+    }
+
+    #[test]
+    fn refuses_to_move_while_landed() {
+        let mut drone = drone();
+        for move_command in ["forward 50", "up 50", "cw 90", "flip l", "go 50 0 0 50", "stop", "land"] {
+            assert_eq!(command(&mut drone, move_command), "error Not flying", "{}", move_command);
+        }
+        assert_eq!(drone.position, [0.0; 3]);
+    }
+
+    #[test]
+    fn takes_off_and_lands() {
+        let mut drone = airborne();
+        assert_eq!(drone.position[2], TAKEOFF_HEIGHT);
+        assert_eq!(command(&mut drone, "height?"), "8dm");
+        assert_eq!(command(&mut drone, "takeoff"), "error Already flying");
+
+        assert_eq!(command(&mut drone, "land"), "ok");
+        assert!(!drone.flying());
+        assert_eq!(drone.position[2], 0.0);
+        assert_eq!(command(&mut drone, "land"), "error Not flying");
+    }
+
+    #[test]
+    fn refuses_takeoff_on_a_low_battery() {
+        let mut drone = drone();
+        drone.battery = 9.0;
+        assert_eq!(command(&mut drone, "takeoff"), "error Low battery");
+        assert!(!drone.flying());
+    }
+
+    #[test]
+    fn checks_move_ranges() {
+        let mut drone = airborne();
+        assert_eq!(command(&mut drone, "forward 19"), "out of range");
+        assert_eq!(command(&mut drone, "forward 501"), "out of range");
+        assert_eq!(command(&mut drone, "forward"), "error Invalid argument");
+        assert_eq!(command(&mut drone, "cw 361"), "out of range");
+        assert_eq!(command(&mut drone, "go 50 0 0 9"), "out of range");
+
+        assert_eq!(command(&mut drone, "forward 100"), "ok");
+        assert_eq!(drone.position, [100.0, 0.0, TAKEOFF_HEIGHT]);
+        assert_eq!(command(&mut drone, "cw 90"), "ok");
+        assert_eq!(drone.yaw, 90.0);
+        assert_eq!(command(&mut drone, "forward 50"), "ok");
+        assert!((drone.position[1] - 50.0).abs() < 0.01, "{:?}", drone.position);
+    }
+
+    #[test]
+    fn never_flies_below_the_minimum_height() {
+        let mut drone = airborne();
+        assert_eq!(command(&mut drone, "down 100"), "ok");
+        assert_eq!(drone.position[2], MIN_FLYING_HEIGHT);
+        assert!(drone.flying());
+
+        // Holding the stick down does not put it on the ground either
+        assert!(matches!(handle_command(&mut drone, "rc 0 0 -100 0"), Reply::None));
+        for _ in 0..50 {
+            drone.step(0.1);
+        }
+        assert_eq!(drone.position[2], MIN_FLYING_HEIGHT);
+    }
+
+    #[test]
+    fn stop_and_emergency_end_the_manoeuvre() {
+        let mut drone = airborne();
+        let Reply::After(mut done) = handle_command(&mut drone, "forward 500") else {
+            panic!("expected a manoeuvre");
+        };
+        assert_eq!(command(&mut drone, "stop"), "ok");
+        assert_eq!(done.try_recv().unwrap(), "error Stopped");
+        assert!(drone.flying());
+
+        let Reply::After(mut done) = handle_command(&mut drone, "forward 500") else {
+            panic!("expected a manoeuvre");
+        };
+        assert_eq!(command(&mut drone, "emergency"), "ok");
+        assert_eq!(done.try_recv().unwrap(), "error Motor stop");
+        assert!(!drone.flying());
+        assert_eq!(drone.position[2], 0.0);
+    }
+
+    #[test]
+    fn drains_the_battery_faster_in_flight() {
+        let mut drone = drone();
+        drone.step(100.0);
+        assert!((drone.battery - (100.0 - DRAIN_IDLE * 100.0)).abs() < 0.001);
+
+        let mut drone = airborne();
+        let before = drone.battery;
+        drone.step(100.0);
+        assert!((before - drone.battery - DRAIN_FLYING * 100.0).abs() < 0.001);
+        assert_eq!(command(&mut drone, "battery?"), format!("{}", drone.battery.ceil() as i32));
+    }
+
+    #[test]
+    fn lands_by_itself_on_an_empty_battery() {
+        let mut drone = airborne();
+        drone.battery = 0.05;
+        for _ in 0..100 {
+            drone.step(0.1);
+        }
+        assert_eq!(drone.battery, 0.0);
+        assert!(!drone.flying());
+        assert_eq!(drone.position[2], 0.0);
+        assert_eq!(command(&mut drone, "takeoff"), "error Low battery");
+    }
+
+    #[test]
+    fn synthetic_video_survives_the_frame_assembler() {
+        use crate::h264::{self, FrameAssembler, NAL_SPS};
+
+        let drone = airborne();
+        let frames: Vec<Vec<u8>> = (0..3).map(|index| synthetic_frame(&drone, index)).collect();
+
+        // Cut into datagrams the way the video task sends them
+        let mut assembler = FrameAssembler::new();
+        let mut assembled = Vec::new();
+        for frame in &frames {
+            for chunk in frame.chunks(VIDEO_CHUNK_SIZE) {
+                assembled.extend(assembler.push(chunk));
+            }
+        }
+        assembled.extend(assembler.flush());
+
+        assert_eq!(assembled.len(), frames.len());
+        for (unit, frame) in assembled.iter().zip(&frames) {
+            assert!(unit.keyframe);
+            assert_eq!(&unit.data, frame);
+        }
+
+        let sps = h264::split_nal_units(&assembled[0].data)[0];
+        assert_eq!(sps[0] & 0x1f, NAL_SPS);
+        assert_eq!(h264::sps_dimensions(sps), Some((VIDEO_WIDTH_MBS as u32 * 16, VIDEO_HEIGHT_MBS as u32 * 16)));
+        assert_eq!(split_access_units(&frames.concat()), frames);
+    }
+
+    #[test]
+    fn formats_state_packets_like_the_drone() {
+        let mut drone = airborne();
+        // Hovering, so no velocity is left over from the climb
+        drone.step(0.1);
+        drone.battery = 87.4;
+        drone.yaw = -45.0;
+
+        let packet = drone.state_packet();
+        assert!(packet.starts_with("pitch:0;roll:0;yaw:-45;vgx:0;vgy:0;vgz:0;"), "{}", packet);
+        assert!(packet.ends_with(";agx:0.00;agy:0.00;agz:-1000.00;\r\n"), "{}", packet);
+
+        let state = parse_state(&packet).unwrap();
+        assert_eq!(state.h, 80);
+        assert_eq!(state.tof, 90);
+        assert_eq!(state.bat, 88);
+        assert_eq!(state.baro, 100.8);
+        assert_eq!(state.agz, -1000.0);
+        assert_eq!(state.pad, None);
+    }
+}
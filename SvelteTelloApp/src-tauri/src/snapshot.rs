@@ -0,0 +1,128 @@
+// Still pictures from the video stream
+// A P-frame only makes sense on top of the frames before it, so we keep every
+// frame since the last keyframe, decode that run in software and save the
+// newest picture as a JPEG.
+
+use base64::{engine::general_purpose, Engine as _};
+use image::codecs::jpeg::JpegEncoder;
+use image::RgbImage;
+use openh264::decoder::Decoder;
+use openh264::formats::YUVSource;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::error::TelloError;
+use crate::h264::AccessUnit;
+
+// Enough for the Tello's keyframe interval; frames past this are not kept
+const MAX_HISTORY_FRAMES: usize = 300;
+
+const JPEG_QUALITY: u8 = 90;
+const THUMBNAIL_WIDTH: u32 = 160;
+const THUMBNAIL_QUALITY: u8 = 75;
+
+// Mirrors `CapturedImage` in the frontend gallery store
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CapturedImage {
+    pub id: String,
+    pub filename: String,
+    pub path: String,
+    pub timestamp: u64, // milliseconds since the Unix epoch
+    pub size: u64,      // bytes
+    pub thumbnail: Option<String>, // data URL
+}
+
+// The frames since (and including) the most recent keyframe
+#[derive(Default)]
+pub struct FrameHistory {
+    frames: Vec<AccessUnit>,
+}
+
+impl FrameHistory {
+    pub fn push(&mut self, frame: &AccessUnit) {
+        if frame.keyframe {
+            self.frames.clear();
+        } else if self.frames.is_empty() || self.frames.len() >= MAX_HISTORY_FRAMES {
+            // Nothing to decode this frame against, or already full
+            return;
+        }
+        self.frames.push(frame.clone());
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    pub fn frames(&self) -> &[AccessUnit] {
+        &self.frames
+    }
+}
+
+// Decode `frames` (keyframe first) and save the last picture into `directory`
+pub fn take_picture(frames: &[AccessUnit], directory: &Path) -> Result<CapturedImage, TelloError> {
+    if !frames.first().is_some_and(|frame| frame.keyframe) {
+        return Err(TelloError::InvalidState("No keyframe received yet, try again in a moment".to_string()));
+    }
+
+    // The decoder takes a run of complete frames and returns the newest picture
+    let stream: Vec<u8> = frames.iter().flat_map(|frame| frame.data.iter().copied()).collect();
+    let image = decode_picture(&stream)?;
+
+    std::fs::create_dir_all(directory).map_err(|e| TelloError::io("Failed to create directory", e))?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let filename = format!("tello_{}.jpg", timestamp);
+    let path = directory.join(&filename);
+
+    let jpeg = encode_jpeg(&image, JPEG_QUALITY)?;
+    std::fs::write(&path, &jpeg).map_err(|e| TelloError::io(format!("Failed to save {}", path.display()), e))?;
+
+    println!("[Snapshot] 📸 Saved {} ({}x{}, {} bytes)", path.display(), image.width(), image.height(), jpeg.len());
+
+    Ok(CapturedImage {
+        id: format!("img_{}", timestamp),
+        filename,
+        path: path.to_string_lossy().to_string(),
+        timestamp,
+        size: jpeg.len() as u64,
+        thumbnail: Some(data_url(&thumbnail_jpeg(&image)?)),
+    })
+}
+
+// Decode an Annex B stream (parameter sets first) to its last picture
+pub fn decode_picture(stream: &[u8]) -> Result<RgbImage, TelloError> {
+    let mut decoder = Decoder::new().map_err(|e| TelloError::Media(format!("Failed to create H.264 decoder: {}", e)))?;
+
+    let yuv = decoder
+        .decode(stream)
+        .map_err(|e| TelloError::Media(format!("Failed to decode frame: {}", e)))?
+        .ok_or_else(|| TelloError::Media("Decoder did not produce a picture".to_string()))?;
+
+    let (width, height) = yuv.dimensions();
+    let mut rgb = vec![0; width * height * 3];
+    yuv.write_rgb8(&mut rgb);
+
+    RgbImage::from_raw(width as u32, height as u32, rgb).ok_or_else(|| TelloError::Media("Decoded picture has the wrong size".to_string()))
+}
+
+fn encode_jpeg(image: &RgbImage, quality: u8) -> Result<Vec<u8>, TelloError> {
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, quality)
+        .encode_image(image)
+        .map_err(|e| TelloError::Media(format!("Failed to encode JPEG: {}", e)))?;
+    Ok(jpeg)
+}
+
+pub fn thumbnail_jpeg(image: &RgbImage) -> Result<Vec<u8>, TelloError> {
+    let height = (image.height() * THUMBNAIL_WIDTH / image.width().max(1)).max(1);
+    let thumbnail = image::imageops::thumbnail(image, THUMBNAIL_WIDTH, height);
+    encode_jpeg(&thumbnail, THUMBNAIL_QUALITY)
+}
+
+// Thumbnails travel to the UI as data URLs so an <img> can show them directly
+pub fn data_url(jpeg: &[u8]) -> String {
+    format!("data:image/jpeg;base64,{}", general_purpose::STANDARD.encode(jpeg))
+}
@@ -0,0 +1,303 @@
+// State stream receiver for Tello
+// Listens on UDP 8890 for the drone's `key:value;` state packets (~10 Hz),
+// keeps DroneState current and forwards every packet to the UI.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tauri::Manager;
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::error::TelloError;
+use crate::flight_log::{self, FlightEvent, FlightRecorder};
+use crate::DroneState;
+
+// One decoded state packet, using the SDK's own field names
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelloStatePacket {
+    pub pitch: i32,
+    pub roll: i32,
+    pub yaw: i32,
+    pub vgx: i32,
+    pub vgy: i32,
+    pub vgz: i32,
+    pub templ: i32,
+    pub temph: i32,
+    pub tof: i32,
+    pub h: i32,
+    pub bat: i32,
+    pub baro: f32,
+    pub time: i32,
+    pub agx: f32,
+    pub agy: f32,
+    pub agz: f32,
+    // Tello EDU only: the mission pad in sight, if detection is on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pad: Option<PadPose>,
+}
+
+// A detected mission pad and the drone's pose relative to it: cm and degrees
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PadPose {
+    pub id: i32,
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+    pub pitch: i32,
+    pub roll: i32,
+    pub yaw: i32,
+}
+
+pub fn parse_state(packet: &str) -> Result<TelloStatePacket, String> {
+    let fields: HashMap<&str, &str> = packet
+        .trim()
+        .split(';')
+        .filter(|pair| !pair.is_empty())
+        .filter_map(|pair| pair.split_once(':'))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect();
+
+    Ok(TelloStatePacket {
+        pitch: field(&fields, "pitch")?,
+        roll: field(&fields, "roll")?,
+        yaw: field(&fields, "yaw")?,
+        vgx: field(&fields, "vgx")?,
+        vgy: field(&fields, "vgy")?,
+        vgz: field(&fields, "vgz")?,
+        templ: field(&fields, "templ")?,
+        temph: field(&fields, "temph")?,
+        tof: field(&fields, "tof")?,
+        h: field(&fields, "h")?,
+        bat: field(&fields, "bat")?,
+        baro: field(&fields, "baro")?,
+        time: field(&fields, "time")?,
+        agx: field(&fields, "agx")?,
+        agy: field(&fields, "agy")?,
+        agz: field(&fields, "agz")?,
+        pad: pad_pose(&fields)?,
+    })
+}
+
+// mid is -1 while no pad is in sight and -2 with detection off; x, y, z and
+// mpry only mean something next to a real pad id
+fn pad_pose(fields: &HashMap<&str, &str>) -> Result<Option<PadPose>, String> {
+    if !fields.contains_key("mid") {
+        return Ok(None);
+    }
+    let id: i32 = field(fields, "mid")?;
+    if id < 1 {
+        return Ok(None);
+    }
+
+    let mpry = fields.get("mpry").copied().unwrap_or_default();
+    let angles: Vec<i32> = mpry.split(',').map(|angle| angle.trim().parse()).collect::<Result<_, _>>()
+        .map_err(|_| format!("Invalid value '{}' for 'mpry' in state packet", mpry))?;
+    let &[pitch, roll, yaw] = angles.as_slice() else {
+        return Err(format!("Invalid value '{}' for 'mpry' in state packet", mpry));
+    };
+
+    Ok(Some(PadPose {
+        id,
+        x: field(fields, "x")?,
+        y: field(fields, "y")?,
+        z: field(fields, "z")?,
+        pitch,
+        roll,
+        yaw,
+    }))
+}
+
+fn field<T: FromStr>(fields: &HashMap<&str, &str>, key: &str) -> Result<T, String> {
+    let value = fields
+        .get(key)
+        .ok_or_else(|| format!("State packet is missing '{}'", key))?;
+
+    value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for '{}' in state packet", value, key))
+}
+
+impl DroneState {
+    pub fn apply_state_packet(&mut self, packet: &TelloStatePacket) {
+        self.battery = packet.bat;
+        self.temperature = (packet.templ + packet.temph) / 2;
+        self.height = packet.h;
+        self.tof = packet.tof;
+        self.pitch = packet.pitch;
+        self.roll = packet.roll;
+        self.yaw = packet.yaw;
+        self.mission_pad = packet.pad;
+    }
+}
+
+pub struct StateReceiver {
+    task: JoinHandle<()>,
+    last_packet: Arc<Mutex<Option<Instant>>>,
+}
+
+impl StateReceiver {
+    // Only packets from `drone_ip` are taken; the state port is open to anyone
+    pub async fn start(
+        local_addr: &str,
+        drone_ip: IpAddr,
+        drone: Arc<Mutex<DroneState>>,
+        flight_log: Arc<Mutex<Option<FlightRecorder>>>,
+        app_handle: tauri::AppHandle,
+    ) -> Result<Self, TelloError> {
+        let socket = UdpSocket::bind(local_addr).await.map_err(|e| TelloError::BindFailed {
+            port: local_addr.parse::<SocketAddr>().map_or(0, |addr| addr.port()),
+            reason: e.to_string(),
+        })?;
+
+        println!("[TelloState] 🎯 Listening for state packets on {}", local_addr);
+
+        let last_packet = Arc::new(Mutex::new(None));
+        let last_packet_writer = Arc::clone(&last_packet);
+
+        let task = tokio::spawn(async move {
+            let mut buf = [0u8; 2048];
+            let mut packet_count: u64 = 0;
+            let mut previous: Option<Instant> = None;
+
+            loop {
+                let size = match socket.recv_from(&mut buf).await {
+                    Ok((size, from)) if from.ip() == drone_ip => size,
+                    Ok((_, from)) => {
+                        eprintln!("[TelloState] ⚠️ Ignoring state packet from {}", from);
+                        continue;
+                    }
+                    Err(e) => {
+                        eprintln!("[TelloState] ❌ Receive failed: {}", e);
+                        continue;
+                    }
+                };
+
+                let text = String::from_utf8_lossy(&buf[..size]);
+
+                let packet = match parse_state(&text) {
+                    Ok(packet) => packet,
+                    Err(e) => {
+                        eprintln!("[TelloState] ⚠️ {}: '{}'", e, text.trim());
+                        continue;
+                    }
+                };
+
+                if packet_count == 0 {
+                    println!("[TelloState] 🎉 First state packet received");
+                }
+                packet_count += 1;
+                let now = Instant::now();
+                *last_packet_writer.lock().unwrap() = Some(now);
+
+                // Cap the step so a gap in the stream doesn't become a jump in position
+                let dt = previous.map_or(0.0, |at| now.duration_since(at).as_secs_f64().min(0.5));
+                previous = Some(now);
+
+                let mut state = drone.lock().unwrap();
+                state.apply_state_packet(&packet);
+                state.integrate_velocity(&packet, dt);
+                drop(state);
+
+                flight_log::record(&flight_log, FlightEvent::State(packet.clone()));
+
+                if let Err(e) = app_handle.emit_all("drone-state", &packet) {
+                    eprintln!("[TelloState] ❌ Failed to emit state: {}", e);
+                }
+            }
+        });
+
+        Ok(Self { task, last_packet })
+    }
+
+    // When the last valid state packet arrived
+    pub fn last_packet(&self) -> Option<Instant> {
+        *self.last_packet.lock().unwrap()
+    }
+}
+
+impl Drop for StateReceiver {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Captured from a Tello (SDK 1.3) sitting on the bench
+    const TELLO_SAMPLE: &str = "pitch:0;roll:0;yaw:0;vgx:0;vgy:0;vgz:0;templ:83;temph:85;tof:10;h:0;bat:87;baro:195.39;time:0;agx:-1.00;agy:-4.00;agz:-998.00;\r\n";
+
+    // Captured from a Tello EDU (SDK 2.0) in flight with mission pads enabled
+    const TELLO_EDU_SAMPLE: &str = "mid:-1;x:-100;y:-100;z:-100;mpry:0,0,0;pitch:-2;roll:1;yaw:-47;vgx:3;vgy:-1;vgz:0;templ:61;temph:64;tof:96;h:80;bat:72;baro:-47.92;time:14;agx:-31.00;agy:-15.00;agz:-1001.00;\r\n";
+
+    #[test]
+    fn parses_tello_sample() {
+        let packet = parse_state(TELLO_SAMPLE).unwrap();
+
+        assert_eq!(packet.templ, 83);
+        assert_eq!(packet.temph, 85);
+        assert_eq!(packet.tof, 10);
+        assert_eq!(packet.bat, 87);
+        assert_eq!(packet.baro, 195.39);
+        assert_eq!(packet.agz, -998.0);
+    }
+
+    #[test]
+    fn parses_edu_sample_without_a_pad_in_sight() {
+        let packet = parse_state(TELLO_EDU_SAMPLE).unwrap();
+
+        assert_eq!(packet.pad, None);
+
+        assert_eq!(packet.pitch, -2);
+        assert_eq!(packet.roll, 1);
+        assert_eq!(packet.yaw, -47);
+        assert_eq!(packet.vgx, 3);
+        assert_eq!(packet.vgy, -1);
+        assert_eq!(packet.h, 80);
+        assert_eq!(packet.time, 14);
+        assert_eq!(packet.baro, -47.92);
+        assert_eq!(packet.agx, -31.0);
+    }
+
+    #[test]
+    fn parses_detected_mission_pad() {
+        let sample = TELLO_EDU_SAMPLE.replace("mid:-1;x:-100;y:-100;z:-100;mpry:0,0,0", "mid:4;x:12;y:-7;z:95;mpry:1,-2,33");
+        let packet = parse_state(&sample).unwrap();
+
+        assert_eq!(packet.pad, Some(PadPose { id: 4, x: 12, y: -7, z: 95, pitch: 1, roll: -2, yaw: 33 }));
+        assert!(parse_state(&sample.replace("mpry:1,-2,33", "mpry:1,-2")).is_err());
+        assert_eq!(parse_state(TELLO_SAMPLE).unwrap().pad, None);
+    }
+
+    #[test]
+    fn rejects_truncated_packet() {
+        let err = parse_state("pitch:0;roll:0;yaw:0;vgx:0;").unwrap_err();
+        assert!(err.contains("vgy"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_non_numeric_value() {
+        let corrupted = TELLO_SAMPLE.replace("bat:87", "bat:8?");
+        let err = parse_state(&corrupted).unwrap_err();
+        assert!(err.contains("bat"), "{}", err);
+    }
+
+    #[test]
+    fn applies_packet_to_drone_state() {
+        let packet = parse_state(TELLO_EDU_SAMPLE).unwrap();
+        let mut drone = DroneState::default();
+
+        drone.apply_state_packet(&packet);
+
+        assert_eq!(drone.battery, 72);
+        assert_eq!(drone.temperature, 62);
+        assert_eq!(drone.height, 80);
+        assert_eq!(drone.tof, 96);
+        assert_eq!(drone.yaw, -47);
+    }
+}
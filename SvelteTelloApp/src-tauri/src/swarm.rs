@@ -0,0 +1,729 @@
+// Swarm control for Tello EDUs in station mode
+// Every drone has joined the same router (`ap` command) and is addressed by its
+// IP, or by its serial number once connected. Each drone gets its own command
+// channel. They all push state packets to the same local port, so packets are
+// told apart by their source address; drones sharing an IP (simulators on
+// localhost) need a state port each. A swarm step sends every drone its
+// command at once and only ends when all of them have answered, so the next
+// step starts with the whole swarm in place. Every drone gets the single
+// drone's safety net: the geofence and battery policy against its own state,
+// and a link watchdog with keepalives.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::battery::BatteryPolicy;
+use crate::command_channel::{self, CommandChannel};
+use crate::error::TelloError;
+use crate::geofence::{self, GeofenceConfig, Verdict};
+use crate::state_stream::{self, PadPose};
+use crate::tello_command::{Query, TelloCommand};
+use crate::tello_response::Reading;
+use crate::watchdog::{self, Watch, WatchdogConfig};
+use crate::DroneState;
+
+const SWARM_FILE: &str = "swarm.json";
+const SDK_COMMAND_PORT: u16 = 8889;
+const MAX_DRONES: usize = 16;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+// A drone whose state packets stop for this long is shown offline
+const STATE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SwarmMember {
+    pub ip: IpAddr,
+    // Port the drone accepts SDK commands on
+    pub command_port: u16,
+    // Local port the drone pushes state packets to
+    pub state_port: u16,
+}
+
+impl Default for SwarmMember {
+    fn default() -> Self {
+        Self {
+            ip: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            command_port: SDK_COMMAND_PORT,
+            state_port: 8890,
+        }
+    }
+}
+
+impl SwarmMember {
+    // Registry key: the IP, plus the port when it is not the SDK's own
+    pub fn id(&self) -> String {
+        if self.command_port == SDK_COMMAND_PORT {
+            self.ip.to_string()
+        } else {
+            self.command_addr().to_string()
+        }
+    }
+
+    pub fn command_addr(&self) -> SocketAddr {
+        SocketAddr::new(self.ip, self.command_port)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SwarmConfig {
+    pub members: Vec<SwarmMember>,
+}
+
+impl SwarmConfig {
+    pub fn validate(&self) -> Result<(), TelloError> {
+        let invalid = |message: String| Err(TelloError::InvalidArgument(message));
+
+        if self.members.len() > MAX_DRONES {
+            return invalid(format!("A swarm can have at most {} drones", MAX_DRONES));
+        }
+        for (index, member) in self.members.iter().enumerate() {
+            if member.ip.is_unspecified() {
+                return invalid(format!("Drone {} needs an IP address", index + 1));
+            }
+            if member.command_port == 0 || member.state_port == 0 {
+                return invalid(format!("{}: command and state ports must be non-zero", member.id()));
+            }
+            for other in &self.members[..index] {
+                if other.id() == member.id() {
+                    return invalid(format!("{} is listed twice", member.id()));
+                }
+                if other.ip == member.ip && other.state_port == member.state_port {
+                    return invalid(format!("Drones at {} need different state ports", member.ip));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn path(app_data_dir: &Path) -> PathBuf {
+        app_data_dir.join(SWARM_FILE)
+    }
+
+    // Missing or unreadable files fall back to an empty swarm
+    pub fn load(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                eprintln!("[Swarm] ⚠️ Ignoring invalid {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), TelloError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| TelloError::Io(format!("Failed to create directory: {}", e)))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| TelloError::Io(format!("Failed to serialize swarm: {}", e)))?;
+        std::fs::write(path, json).map_err(|e| TelloError::Io(format!("Failed to save swarm: {}", e)))
+    }
+}
+
+// One command of a step; `drone` is an id or serial, None means every drone
+// without a command of its own
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SwarmCommand {
+    #[serde(default)]
+    pub drone: Option<String>,
+    pub command: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SwarmStep {
+    pub commands: Vec<SwarmCommand>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SwarmReply {
+    pub drone: String,
+    pub command: String,
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SwarmReport {
+    // Steps every drone acknowledged
+    pub completed: usize,
+    pub total: usize,
+    // Replies to the last step run; the failed one if the run stopped early
+    pub replies: Vec<SwarmReply>,
+    // `Swarm::stop` ended the run before its last step
+    pub stopped: bool,
+}
+
+// The single drone's settings, shared so changes apply to the swarm as well
+#[derive(Clone, Default)]
+pub struct SwarmSafety {
+    pub geofence: Arc<Mutex<GeofenceConfig>>,
+    pub battery_policy: Arc<Mutex<BatteryPolicy>>,
+    pub watchdog: Arc<Mutex<WatchdogConfig>>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SwarmDroneStatus {
+    pub id: String,
+    pub serial: Option<String>,
+    // State packets are arriving
+    pub online: bool,
+    pub flying: bool,
+    pub battery: i32,
+    pub height: i32,
+    pub yaw: i32,
+    pub mission_pad: Option<PadPose>,
+}
+
+// What the state stream last said about one drone, shared with its link watchdog
+#[derive(Clone, Default)]
+struct Telemetry {
+    state: Arc<Mutex<DroneState>>,
+    last_packet: Arc<Mutex<Option<Instant>>>,
+}
+
+#[derive(Clone)]
+struct SwarmDrone {
+    id: String,
+    serial: Option<String>,
+    channel: CommandChannel,
+    telemetry: Telemetry,
+}
+
+impl SwarmDrone {
+    // `note` says how the geofence changed the command
+    async fn send(&self, command: String, note: Option<String>) -> SwarmReply {
+        let result = match command_channel::response_timeout(&command) {
+            Some(timeout) => self.channel.request(&command, timeout).await,
+            // emergency skips the queue, so nothing waits behind a manoeuvre
+            None => self.channel.send(&command).await.map(|_| "sent".to_string()),
+        };
+
+        let (success, message) = match result {
+            Ok(reply) if command.ends_with('?') => (!reply.starts_with("error"), reply),
+            Ok(reply) => (reply == "ok" || reply == "sent", reply),
+            Err(e) => (false, e.to_string()),
+        };
+
+        if success {
+            let mut state = self.telemetry.state.lock().unwrap();
+            match command.split_whitespace().next() {
+                // Distances are fenced from where each drone took off
+                Some("takeoff") => {
+                    state.flying = true;
+                    state.reset_position();
+                }
+                Some("land" | "emergency") => state.flying = false,
+                _ => {}
+            }
+        }
+
+        let message = match note {
+            Some(note) => format!("{} (geofence: {})", message, note),
+            None => message,
+        };
+        SwarmReply { drone: self.id.clone(), command, success, message }
+    }
+
+    fn refuse(&self, command: String, reason: String) -> SwarmReply {
+        SwarmReply { drone: self.id.clone(), command, success: false, message: reason }
+    }
+
+    fn status(&self, now: Instant) -> SwarmDroneStatus {
+        let last_packet = *self.telemetry.last_packet.lock().unwrap();
+        let state = self.telemetry.state.lock().unwrap();
+        SwarmDroneStatus {
+            id: self.id.clone(),
+            serial: self.serial.clone(),
+            online: last_packet.is_some_and(|at| now.duration_since(at) < STATE_TIMEOUT),
+            flying: state.flying,
+            battery: state.battery,
+            height: state.height,
+            yaw: state.yaw,
+            mission_pad: state.mission_pad,
+        }
+    }
+}
+
+// A planned command that passed the safety checks
+struct Cleared {
+    drone: SwarmDrone,
+    command: String,
+    // How the geofence changed the command
+    note: Option<String>,
+}
+
+// State sockets and link watchdogs; they stop once the last Swarm clone is gone
+struct Tasks(Vec<JoinHandle<()>>);
+
+impl Drop for Tasks {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
+
+// Cheap to clone, so commands can run without holding the AppState lock
+#[derive(Clone)]
+pub struct Swarm {
+    drones: Vec<SwarmDrone>,
+    safety: SwarmSafety,
+    // Bumped by `stop`; a run that sees it change ends before its next step
+    stops: Arc<AtomicU64>,
+    _tasks: Arc<Tasks>,
+}
+
+impl Swarm {
+    // Puts every drone in SDK mode; fails unless all of them answer
+    pub async fn connect(config: &SwarmConfig, safety: SwarmSafety) -> Result<Self, TelloError> {
+        config.validate()?;
+        if config.members.is_empty() {
+            return Err(TelloError::InvalidArgument("Add drones to the swarm first".to_string()));
+        }
+
+        // Listen before the drones start pushing state
+        let mut routes: HashMap<u16, HashMap<IpAddr, Telemetry>> = HashMap::new();
+        let telemetry: Vec<Telemetry> = config
+            .members
+            .iter()
+            .map(|member| {
+                let telemetry = Telemetry::default();
+                routes.entry(member.state_port).or_default().insert(member.ip, telemetry.clone());
+                telemetry
+            })
+            .collect();
+
+        let mut tasks = Tasks(Vec::new());
+        for (port, route) in routes {
+            tasks.0.push(listen(port, route).await?);
+        }
+
+        let joins: Vec<_> = config
+            .members
+            .iter()
+            .cloned()
+            .zip(telemetry)
+            .map(|(member, telemetry)| tokio::spawn(join(member, telemetry)))
+            .collect();
+
+        let mut drones = Vec::with_capacity(joins.len());
+        for join in joins {
+            drones.push(join.await.map_err(|e| TelloError::Internal(format!("Swarm connect task failed: {}", e)))??);
+        }
+
+        for drone in &drones {
+            tasks.0.push(watch_link(drone.clone(), Arc::clone(&safety.watchdog)));
+        }
+
+        println!("[Swarm] 🐝 {} drones in SDK mode", drones.len());
+        Ok(Self {
+            drones,
+            safety,
+            stops: Arc::new(AtomicU64::new(0)),
+            _tasks: Arc::new(tasks),
+        })
+    }
+
+    pub fn status(&self) -> Vec<SwarmDroneStatus> {
+        let now = Instant::now();
+        self.drones.iter().map(|drone| drone.status(now)).collect()
+    }
+
+    pub fn flying(&self) -> Vec<String> {
+        self.drones
+            .iter()
+            .filter(|drone| drone.telemetry.state.lock().unwrap().flying)
+            .map(|drone| drone.id.clone())
+            .collect()
+    }
+
+    fn find(&self, key: &str) -> Result<&SwarmDrone, TelloError> {
+        self.drones
+            .iter()
+            .find(|drone| drone.id == key || drone.serial.as_deref() == Some(key))
+            .ok_or_else(|| TelloError::InvalidArgument(format!("No drone '{}' in the swarm", key)))
+    }
+
+    // Who gets what in `step`: a drone's own command, else the broadcast one
+    fn plan(&self, step: &SwarmStep) -> Result<Vec<(SwarmDrone, String)>, TelloError> {
+        let mut broadcast = None;
+        let mut direct = HashMap::new();
+
+        for entry in &step.commands {
+            // Same checks as the single drone's raw commands
+            let command = entry.command.parse::<TelloCommand>()?.to_string();
+            match &entry.drone {
+                None => {
+                    if broadcast.replace(command).is_some() {
+                        return Err(TelloError::InvalidArgument("A step can only have one command for all drones".to_string()));
+                    }
+                }
+                Some(key) => {
+                    let drone = self.find(key)?;
+                    if direct.insert(drone.id.clone(), command).is_some() {
+                        return Err(TelloError::InvalidArgument(format!("{} has two commands in one step", drone.id)));
+                    }
+                }
+            }
+        }
+
+        let plan: Vec<_> = self
+            .drones
+            .iter()
+            .filter_map(|drone| direct.remove(&drone.id).or_else(|| broadcast.clone()).map(|command| (drone.clone(), command)))
+            .collect();
+        if plan.is_empty() {
+            return Err(TelloError::InvalidArgument("The step has no commands".to_string()));
+        }
+        Ok(plan)
+    }
+
+    // The single drone's checks, each drone against its own state: the battery
+    // policy before takeoff and the geofence on everything. Any refusal holds
+    // back the whole step, so the swarm stays in lockstep.
+    fn guard(&self, plan: Vec<(SwarmDrone, String)>) -> Result<Vec<Cleared>, Vec<SwarmReply>> {
+        let geofence = self.safety.geofence.lock().unwrap().clone();
+        let battery_policy = self.safety.battery_policy.lock().unwrap().clone();
+        let takeoff = TelloCommand::Takeoff.to_string();
+
+        let mut allowed = Vec::with_capacity(plan.len());
+        let mut refused = Vec::new();
+        for (drone, command) in plan {
+            let state = drone.telemetry.state.lock().unwrap().clone();
+
+            if command == takeoff {
+                if let Err(e) = battery_policy.allows_takeoff(state.battery) {
+                    println!("[Swarm] 🪫 {}: {}", drone.id, e);
+                    refused.push(drone.refuse(command, e.to_string()));
+                    continue;
+                }
+            }
+
+            match geofence::check(&geofence, &state, &command) {
+                Verdict::Allow => allowed.push(Cleared { drone, command, note: None }),
+                Verdict::Clamp(clamped, reason) => {
+                    println!("[Geofence] ✂️ {}: '{}' -> '{}': {}", drone.id, command, clamped, reason);
+                    allowed.push(Cleared { drone, command: clamped, note: Some(reason) });
+                }
+                Verdict::Reject(reason) => {
+                    println!("[Geofence] 🚫 {}: '{}' rejected: {}", drone.id, command, reason);
+                    refused.push(drone.refuse(command, reason));
+                }
+            }
+        }
+
+        if refused.is_empty() {
+            Ok(allowed)
+        } else {
+            Err(refused)
+        }
+    }
+
+    // Sends every drone its command at once and waits for all the replies.
+    // A refused step sends nothing and returns the refusals.
+    pub async fn step(&self, step: &SwarmStep) -> Result<Vec<SwarmReply>, TelloError> {
+        let plan = match self.guard(self.plan(step)?) {
+            Ok(plan) => plan,
+            Err(refused) => return Ok(refused),
+        };
+        let sends: Vec<_> = plan
+            .into_iter()
+            .map(|cleared| tokio::spawn(async move { cleared.drone.send(cleared.command, cleared.note).await }))
+            .collect();
+
+        let mut replies = Vec::with_capacity(sends.len());
+        for send in sends {
+            replies.push(send.await.map_err(|e| TelloError::Internal(format!("Swarm command task failed: {}", e)))?);
+        }
+        Ok(replies)
+    }
+
+    // Runs the steps in order; a step any drone fails ends the run there, and
+    // so does `stop`
+    pub async fn run(&self, steps: &[SwarmStep]) -> Result<SwarmReport, TelloError> {
+        for (index, step) in steps.iter().enumerate() {
+            self.plan(step).map_err(|e| TelloError::InvalidArgument(format!("Step {}: {}", index + 1, e)))?;
+        }
+
+        let stops = self.stops.load(Ordering::SeqCst);
+        let mut report = SwarmReport { completed: 0, total: steps.len(), replies: Vec::new(), stopped: false };
+        for (index, step) in steps.iter().enumerate() {
+            // A step already sent always runs to the end; stopping only keeps
+            // the next one from going out
+            if self.stops.load(Ordering::SeqCst) != stops {
+                println!("[Swarm] ⏹️ Run stopped before step {}/{}", index + 1, steps.len());
+                report.stopped = true;
+                return Ok(report);
+            }
+
+            println!("[Swarm] ▶️ Step {}/{}", index + 1, steps.len());
+            report.replies = self.step(step).await?;
+
+            if let Some(failed) = report.replies.iter().find(|reply| !reply.success) {
+                eprintln!("[Swarm] ❌ Step {} stopped the run: {} answered '{}' to '{}'", index + 1, failed.drone, failed.message, failed.command);
+                return Ok(report);
+            }
+            report.completed += 1;
+        }
+
+        println!("[Swarm] ✅ All {} steps acknowledged", steps.len());
+        Ok(report)
+    }
+
+    // Ends every run in progress after its current step
+    pub fn stop(&self) {
+        self.stops.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+async fn join(member: SwarmMember, telemetry: Telemetry) -> Result<SwarmDrone, TelloError> {
+    let id = member.id();
+    // Each drone replies to whichever port asked, so any free port will do
+    let channel = CommandChannel::bind("0.0.0.0:0", member.command_addr(), Arc::new(Mutex::new(None))).await?;
+
+    let command = TelloCommand::Command.to_string();
+    let reply = channel
+        .request(&command, CONNECT_TIMEOUT)
+        .await
+        .map_err(|e| TelloError::Network(format!("{}: {}", id, e)))?;
+    if !reply.eq_ignore_ascii_case("ok") {
+        return Err(TelloError::DroneError { command, reason: format!("{} answered '{}'", id, reply) });
+    }
+
+    // Older firmware has no sn?, the IP still identifies the drone
+    let serial = match crate::read(&channel, Query::SerialNumber).await {
+        Ok(Reading::Text { value }) => Some(value),
+        _ => None,
+    };
+    println!("[Swarm] ✅ {} joined (serial {})", id, serial.as_deref().unwrap_or("unknown"));
+
+    Ok(SwarmDrone { id, serial, channel, telemetry })
+}
+
+// The single drone's link watchdog for one drone of the swarm: keepalives
+// while it idles, and the configured recovery once its link is lost
+fn watch_link(drone: SwarmDrone, config: Arc<Mutex<WatchdogConfig>>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut watch = Watch::new();
+        let mut interval = tokio::time::interval(watchdog::TICK);
+
+        loop {
+            interval.tick().await;
+
+            let config = config.lock().unwrap().clone();
+            let last_state = *drone.telemetry.last_packet.lock().unwrap();
+            if let Some((event, payload)) = watch.update(&config, &drone.channel, last_state, &drone.telemetry.state) {
+                println!("[Swarm] 📶 {} {}: {}", drone.id, event, payload.message);
+            }
+            watch.recover(&config, &drone.channel, &drone.telemetry.state).await;
+        }
+    })
+}
+
+async fn listen(port: u16, routes: HashMap<IpAddr, Telemetry>) -> Result<JoinHandle<()>, TelloError> {
+    let socket = UdpSocket::bind(("0.0.0.0", port))
+        .await
+        .map_err(|e| TelloError::BindFailed { port, reason: e.to_string() })?;
+
+    println!("[Swarm] 🎯 Listening for state packets on port {}", port);
+
+    Ok(tokio::spawn(async move {
+        let mut buf = [0u8; 2048];
+
+        loop {
+            let (size, from) = match socket.recv_from(&mut buf).await {
+                Ok(received) => received,
+                Err(e) => {
+                    eprintln!("[Swarm] ❌ Receive failed: {}", e);
+                    continue;
+                }
+            };
+
+            // Drones outside the swarm are ignored
+            let Some(telemetry) = routes.get(&from.ip()) else { continue };
+
+            match state_stream::parse_state(&String::from_utf8_lossy(&buf[..size])) {
+                Ok(packet) => {
+                    let now = Instant::now();
+                    let previous = telemetry.last_packet.lock().unwrap().replace(now);
+                    // Cap the step so a gap in the stream doesn't become a jump in position
+                    let dt = previous.map_or(0.0, |at| now.duration_since(at).as_secs_f64().min(0.5));
+
+                    let mut state = telemetry.state.lock().unwrap();
+                    state.apply_state_packet(&packet);
+                    state.integrate_velocity(&packet, dt);
+                }
+                Err(e) => eprintln!("[Swarm] ⚠️ {} from {}", e, from),
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulator::{SimulatorConfig, TelloSimulator};
+
+    fn member(ip: &str, command_port: u16, state_port: u16) -> SwarmMember {
+        SwarmMember { ip: ip.parse().unwrap(), command_port, state_port }
+    }
+
+    fn free_port() -> u16 {
+        std::net::UdpSocket::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    fn command(drone: Option<&str>, command: &str) -> SwarmCommand {
+        SwarmCommand { drone: drone.map(str::to_string), command: command.to_string() }
+    }
+
+    fn broadcast(text: &str) -> SwarmStep {
+        SwarmStep { commands: vec![command(None, text)] }
+    }
+
+    #[test]
+    fn validates_members() {
+        let swarm = SwarmConfig {
+            members: vec![member("192.168.1.21", 8889, 8890), member("192.168.1.22", 8889, 8890)],
+        };
+        assert!(swarm.validate().is_ok());
+        assert_eq!(swarm.members[0].id(), "192.168.1.21");
+        assert_eq!(member("127.0.0.1", 9001, 9101).id(), "127.0.0.1:9001");
+
+        let twice = SwarmConfig { members: vec![member("192.168.1.21", 8889, 8890), member("192.168.1.21", 8889, 8891)] };
+        assert!(twice.validate().is_err());
+        // Same IP: only the state port tells their packets apart
+        let shared = SwarmConfig { members: vec![member("127.0.0.1", 9001, 8890), member("127.0.0.1", 9002, 8890)] };
+        assert!(shared.validate().is_err());
+        assert!(SwarmConfig { members: vec![SwarmMember::default()] }.validate().is_err());
+    }
+
+    // Simulators on localhost, each with its own state port
+    async fn simulated_swarm(count: usize) -> (Vec<TelloSimulator>, SwarmConfig) {
+        let mut simulators = Vec::new();
+        let mut members = Vec::new();
+        for index in 0..count {
+            let state_port = free_port();
+            let simulator = TelloSimulator::start(SimulatorConfig {
+                command_addr: "127.0.0.1:0".parse().unwrap(),
+                state_port,
+                video_port: free_port(),
+                serial: format!("SIM{}", index),
+                ..SimulatorConfig::default()
+            })
+            .await
+            .unwrap();
+            members.push(member("127.0.0.1", simulator.command_addr().port(), state_port));
+            simulators.push(simulator);
+        }
+        (simulators, SwarmConfig { members })
+    }
+
+    #[tokio::test]
+    async fn flies_simulated_swarm_in_lockstep() {
+        let (_simulators, config) = simulated_swarm(3).await;
+        let swarm = Swarm::connect(&config, SwarmSafety::default()).await.unwrap();
+        let serials: Vec<_> = swarm.status().into_iter().map(|drone| drone.serial).collect();
+        assert_eq!(serials, [Some("SIM0".to_string()), Some("SIM1".to_string()), Some("SIM2".to_string())]);
+
+        // Nothing is flying, so the first step fails on every drone
+        let report = swarm.run(&[broadcast("forward 20")]).await.unwrap();
+        assert_eq!(report.completed, 0);
+        assert!(report.replies.iter().all(|reply| !reply.success));
+
+        // Bad steps are refused before anything is sent
+        assert!(swarm.run(&[SwarmStep { commands: vec![command(Some("SIM9"), "takeoff")] }]).await.is_err());
+        assert!(swarm.run(&[SwarmStep { commands: vec![command(None, "takeoff"), command(None, "land")] }]).await.is_err());
+
+        let steps = [
+            broadcast("takeoff"),
+            // SIM0 climbs while the others hold
+            SwarmStep { commands: vec![command(Some("SIM0"), "up 40"), command(None, "battery?")] },
+        ];
+        let report = swarm.run(&steps).await.unwrap();
+        assert_eq!(report.completed, 2, "{:?}", report.replies);
+        assert_eq!(report.replies[0].command, "up 40");
+        assert_eq!(swarm.flying().len(), 3);
+
+        // Each drone's own state stream
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let status = swarm.status();
+        assert!(status.iter().all(|drone| drone.online));
+        assert!(status[0].height > status[1].height, "{:?}", status);
+
+        let report = swarm.run(&[broadcast("land")]).await.unwrap();
+        assert_eq!(report.completed, 1);
+        assert!(swarm.flying().is_empty());
+    }
+
+    #[tokio::test]
+    async fn holds_each_drone_to_the_geofence_and_battery_policy() {
+        let (_simulators, config) = simulated_swarm(2).await;
+        let safety = SwarmSafety::default();
+        *safety.geofence.lock().unwrap() = GeofenceConfig { max_height: 100, ..GeofenceConfig::default() };
+        safety.battery_policy.lock().unwrap().critical_level = 100;
+        let swarm = Swarm::connect(&config, safety.clone()).await.unwrap();
+
+        // The policy needs a battery reading from each drone's own state stream
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let report = swarm.run(&[broadcast("takeoff")]).await.unwrap();
+        assert_eq!(report.completed, 0);
+        assert_eq!(report.replies.len(), 2);
+        assert!(report.replies.iter().all(|reply| !reply.success && reply.message.starts_with("Battery at")), "{:?}", report.replies);
+        assert!(swarm.flying().is_empty());
+
+        // Settings changes reach a connected swarm
+        safety.battery_policy.lock().unwrap().critical_level = 10;
+        assert_eq!(swarm.run(&[broadcast("takeoff")]).await.unwrap().completed, 1);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        // 80 cm up, 20 cm of room left: SIM0's climb is shortened
+        let report = swarm.run(&[SwarmStep { commands: vec![command(Some("SIM0"), "up 50"), command(None, "battery?")] }]).await.unwrap();
+        assert_eq!(report.completed, 1, "{:?}", report.replies);
+        assert_eq!(report.replies[0].command, "up 20");
+        assert!(report.replies[0].message.contains("geofence: shortened"), "{}", report.replies[0].message);
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        // SIM0 is at the ceiling, so nobody moves
+        let report = swarm.run(&[SwarmStep { commands: vec![command(Some("SIM0"), "up 20"), command(None, "down 20")] }]).await.unwrap();
+        assert_eq!(report.completed, 0);
+        assert_eq!(report.replies.len(), 1);
+        assert_eq!(report.replies[0].drone, swarm.drones[0].id);
+        let heights: Vec<i32> = swarm.status().iter().map(|drone| drone.height).collect();
+        assert_eq!(heights, [100, 80]);
+
+        swarm.run(&[broadcast("land")]).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn stop_ends_a_run_after_the_current_step() {
+        let (_simulators, config) = simulated_swarm(1).await;
+        let swarm = Swarm::connect(&config, SwarmSafety::default()).await.unwrap();
+
+        // Takeoff takes the simulator two seconds
+        let run = tokio::spawn({
+            let swarm = swarm.clone();
+            async move { swarm.run(&[broadcast("takeoff"), broadcast("up 20"), broadcast("land")]).await }
+        });
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        swarm.stop();
+
+        let report = run.await.unwrap().unwrap();
+        assert!(report.stopped);
+        assert_eq!(report.completed, 1);
+        assert_eq!(report.replies[0].command, "takeoff");
+        assert_eq!(swarm.flying().len(), 1);
+
+        // The next run is not affected by the earlier stop
+        let report = swarm.run(&[broadcast("land")]).await.unwrap();
+        assert!(!report.stopped);
+        assert_eq!(report.completed, 1);
+    }
+}
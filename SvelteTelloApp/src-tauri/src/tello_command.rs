@@ -0,0 +1,804 @@
+// Tello SDK commands
+// Every command the app sends is built here, so a bad argument is caught before
+// it reaches the drone (which would only answer a vague "error"). Ranges follow
+// the Tello SDK 2.0 and Tello EDU / RoboMaster TT SDK 3.0 documents. Build
+// commands with the constructors; Display gives the wire format.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::TelloError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Forward,
+    Back,
+}
+
+impl Direction {
+    pub fn keyword(self) -> &'static str {
+        match self {
+            Self::Up => "up",
+            Self::Down => "down",
+            Self::Left => "left",
+            Self::Right => "right",
+            Self::Forward => "forward",
+            Self::Back => "back",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rotation {
+    Clockwise,
+    CounterClockwise,
+}
+
+impl Rotation {
+    pub fn keyword(self) -> &'static str {
+        match self {
+            Self::Clockwise => "cw",
+            Self::CounterClockwise => "ccw",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlipDirection {
+    Left,
+    Right,
+    Forward,
+    Back,
+}
+
+impl FlipDirection {
+    // Accepts the SDK letters as well as the words the UI uses
+    pub fn parse(direction: &str) -> Result<Self, TelloError> {
+        match direction.to_ascii_lowercase().as_str() {
+            "l" | "left" => Ok(Self::Left),
+            "r" | "right" => Ok(Self::Right),
+            "f" | "forward" => Ok(Self::Forward),
+            "b" | "back" => Ok(Self::Back),
+            _ => Err(invalid(format!("Unknown flip direction '{}' (expected l, r, f or b)", direction))),
+        }
+    }
+
+    pub fn letter(self) -> &'static str {
+        match self {
+            Self::Left => "l",
+            Self::Right => "r",
+            Self::Forward => "f",
+            Self::Back => "b",
+        }
+    }
+}
+
+// setfps: low = 5, medium = 15, high = 30
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameRate {
+    Low,
+    Medium,
+    High,
+}
+
+impl FrameRate {
+    pub fn parse(fps: &str) -> Result<Self, TelloError> {
+        match fps {
+            "low" => Ok(Self::Low),
+            "medium" => Ok(Self::Medium),
+            "high" => Ok(Self::High),
+            _ => Err(invalid(format!("Unknown frame rate '{}' (expected low, medium or high)", fps))),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+}
+
+// setresolution: low = 480p, high = 720p
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    Low,
+    High,
+}
+
+impl Resolution {
+    pub fn parse(resolution: &str) -> Result<Self, TelloError> {
+        match resolution {
+            "low" => Ok(Self::Low),
+            "high" => Ok(Self::High),
+            _ => Err(invalid(format!("Unknown resolution '{}' (expected low or high)", resolution))),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::High => "high",
+        }
+    }
+}
+
+// A mission pad id: m1..m8, or m-1 (any pad) and m-2 (the nearest pad)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MissionPad(i32);
+
+impl MissionPad {
+    pub fn new(id: i32) -> Result<Self, TelloError> {
+        if (1..=8).contains(&id) || id == -1 || id == -2 {
+            Ok(Self(id))
+        } else {
+            Err(invalid(format!("Mission pad id {} must be 1-8, -1 (any) or -2 (nearest)", id)))
+        }
+    }
+
+    pub fn parse(pad: &str) -> Result<Self, TelloError> {
+        let id = pad.strip_prefix('m')
+            .and_then(|id| id.parse().ok())
+            .ok_or_else(|| invalid(format!("Expected a mission pad like m1, got '{}'", pad)))?;
+        Self::new(id)
+    }
+}
+
+impl fmt::Display for MissionPad {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m{}", self.0)
+    }
+}
+
+// Read commands; each answers with a value instead of "ok"
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Query {
+    Speed,
+    Battery,
+    Time,
+    Height,
+    Temperature,
+    Attitude,
+    Barometer,
+    Acceleration,
+    Tof,
+    Wifi,
+    Sdk,
+    SerialNumber,
+    // SDK 3.0
+    Hardware,
+    WifiVersion,
+    Ap,
+    Ssid,
+}
+
+impl Query {
+    const ALL: [Self; 16] = [
+        Self::Speed, Self::Battery, Self::Time, Self::Height, Self::Temperature,
+        Self::Attitude, Self::Barometer, Self::Acceleration, Self::Tof, Self::Wifi,
+        Self::Sdk, Self::SerialNumber, Self::Hardware, Self::WifiVersion, Self::Ap, Self::Ssid,
+    ];
+
+    pub fn keyword(self) -> &'static str {
+        match self {
+            Self::Speed => "speed?",
+            Self::Battery => "battery?",
+            Self::Time => "time?",
+            Self::Height => "height?",
+            Self::Temperature => "temp?",
+            Self::Attitude => "attitude?",
+            Self::Barometer => "baro?",
+            Self::Acceleration => "acceleration?",
+            Self::Tof => "tof?",
+            Self::Wifi => "wifi?",
+            Self::Sdk => "sdk?",
+            Self::SerialNumber => "sn?",
+            Self::Hardware => "hardware?",
+            Self::WifiVersion => "wifiversion?",
+            Self::Ap => "ap?",
+            Self::Ssid => "ssid?",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TelloCommand {
+    // Enter SDK mode
+    Command,
+    Takeoff,
+    Land,
+    StreamOn,
+    StreamOff,
+    // Stops the motors immediately, the drone drops
+    Emergency,
+    // Hover in place, also interrupts a running motion command
+    Stop,
+    // SDK 3.0: spin the motors at low speed without taking off
+    MotorOn,
+    MotorOff,
+    // SDK 3.0: take off from a throw within 5 s
+    ThrowFly,
+    Move { direction: Direction, distance: i32 },
+    Rotate { rotation: Rotation, degrees: i32 },
+    Flip(FlipDirection),
+    // Fly to (x, y, z) relative to the drone, or to a mission pad when given
+    Go { x: i32, y: i32, z: i32, speed: i32, pad: Option<MissionPad> },
+    // Fly an arc through the first point to the second
+    Curve { x1: i32, y1: i32, z1: i32, x2: i32, y2: i32, z2: i32, speed: i32, pad: Option<MissionPad> },
+    // Fly to (x, y, z) relative to pad1, then find pad2 and turn to yaw
+    Jump { x: i32, y: i32, z: i32, speed: i32, yaw: i32, pad1: MissionPad, pad2: MissionPad },
+    Speed(i32),
+    Rc { left_right: i32, forward_back: i32, up_down: i32, yaw: i32 },
+    // Change the drone's own access point
+    Wifi { ssid: String, password: String },
+    // SDK 2.0: join an existing network as a station instead
+    Ap { ssid: String, password: String },
+    // SDK 3.0: move the state and video streams to other ports
+    Port { state_port: u16, video_port: u16 },
+    // Mission pad detection
+    MissionPadOn,
+    MissionPadOff,
+    // 0 = downward camera, 1 = forward, 2 = both
+    MissionPadDirection(i32),
+    // SDK 3.0: stream the downward camera instead of the front one
+    DownVision(bool),
+    SetFps(FrameRate),
+    SetResolution(Resolution),
+    // Mbps, 0 = auto
+    SetBitrate(i32),
+    Query(Query),
+}
+
+impl TelloCommand {
+    pub fn movement(direction: Direction, distance: i32) -> Result<Self, TelloError> {
+        in_range("Distance", distance, 20, 500)?;
+        Ok(Self::Move { direction, distance })
+    }
+
+    pub fn rotate(rotation: Rotation, degrees: i32) -> Result<Self, TelloError> {
+        in_range("Rotation", degrees, 1, 360)?;
+        Ok(Self::Rotate { rotation, degrees })
+    }
+
+    pub fn flip(direction: &str) -> Result<Self, TelloError> {
+        Ok(Self::Flip(FlipDirection::parse(direction)?))
+    }
+
+    pub fn go(x: i32, y: i32, z: i32, speed: i32, pad: Option<MissionPad>) -> Result<Self, TelloError> {
+        check_point(x, y, z)?;
+        in_range("Speed", speed, 10, 100)?;
+        Ok(Self::Go { x, y, z, speed, pad })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn curve(x1: i32, y1: i32, z1: i32, x2: i32, y2: i32, z2: i32, speed: i32, pad: Option<MissionPad>) -> Result<Self, TelloError> {
+        check_point(x1, y1, z1)?;
+        check_point(x2, y2, z2)?;
+        in_range("Curve speed", speed, 10, 60)?;
+
+        // The arc is the circle through the current position and both points
+        let radius = arc_radius([x1, y1, z1], [x2, y2, z2])
+            .ok_or_else(|| invalid("Curve points must not be in a straight line with the drone"))?;
+        if !(50.0..=1000.0).contains(&radius) {
+            return Err(invalid(format!("Curve radius {:.0}cm must be between 50 and 1000cm", radius)));
+        }
+        Ok(Self::Curve { x1, y1, z1, x2, y2, z2, speed, pad })
+    }
+
+    pub fn jump(x: i32, y: i32, z: i32, speed: i32, yaw: i32, pad1: MissionPad, pad2: MissionPad) -> Result<Self, TelloError> {
+        check_point(x, y, z)?;
+        in_range("Speed", speed, 10, 100)?;
+        in_range("Yaw", yaw, -360, 360)?;
+        Ok(Self::Jump { x, y, z, speed, yaw, pad1, pad2 })
+    }
+
+    pub fn speed(speed: i32) -> Result<Self, TelloError> {
+        in_range("Speed", speed, 10, 100)?;
+        Ok(Self::Speed(speed))
+    }
+
+    pub fn rc(left_right: i32, forward_back: i32, up_down: i32, yaw: i32) -> Result<Self, TelloError> {
+        for (name, value) in [("left/right", left_right), ("forward/back", forward_back), ("up/down", up_down), ("yaw", yaw)] {
+            in_range(&format!("rc {}", name), value, -100, 100)?;
+        }
+        Ok(Self::Rc { left_right, forward_back, up_down, yaw })
+    }
+
+    // Centred sticks, always in range
+    pub fn hover() -> Self {
+        Self::Rc { left_right: 0, forward_back: 0, up_down: 0, yaw: 0 }
+    }
+
+    pub fn wifi(ssid: &str, password: &str) -> Result<Self, TelloError> {
+        check_credentials(ssid, password)?;
+        Ok(Self::Wifi { ssid: ssid.to_string(), password: password.to_string() })
+    }
+
+    pub fn ap(ssid: &str, password: &str) -> Result<Self, TelloError> {
+        check_credentials(ssid, password)?;
+        Ok(Self::Ap { ssid: ssid.to_string(), password: password.to_string() })
+    }
+
+    pub fn port(state_port: u16, video_port: u16) -> Result<Self, TelloError> {
+        for port in [state_port, video_port] {
+            in_range("Port", port as i32, 1025, 65535)?;
+        }
+        if state_port == video_port {
+            return Err(invalid("State and video ports must differ"));
+        }
+        Ok(Self::Port { state_port, video_port })
+    }
+
+    pub fn mission_pad_direction(direction: i32) -> Result<Self, TelloError> {
+        in_range("Mission pad direction", direction, 0, 2)?;
+        Ok(Self::MissionPadDirection(direction))
+    }
+
+    pub fn set_bitrate(bitrate: i32) -> Result<Self, TelloError> {
+        in_range("Bitrate", bitrate, 0, 5)?;
+        Ok(Self::SetBitrate(bitrate))
+    }
+}
+
+fn invalid(message: impl Into<String>) -> TelloError {
+    TelloError::InvalidArgument(message.into())
+}
+
+fn in_range(name: &str, value: i32, min: i32, max: i32) -> Result<(), TelloError> {
+    if (min..=max).contains(&value) {
+        Ok(())
+    } else {
+        Err(invalid(format!("{} {} is out of range ({} to {})", name, value, min, max)))
+    }
+}
+
+// go/curve/jump coordinates: -500..=500 each, and not all within 20cm of zero
+fn check_point(x: i32, y: i32, z: i32) -> Result<(), TelloError> {
+    for (axis, value) in [("x", x), ("y", y), ("z", z)] {
+        in_range(axis, value, -500, 500)?;
+    }
+    if [x, y, z].iter().all(|value| value.abs() <= 20) {
+        return Err(invalid(format!("Point ({}, {}, {}) is too close, one coordinate must be beyond ±20cm", x, y, z)));
+    }
+    Ok(())
+}
+
+// Radius of the circle through the origin, a and b; None if they are collinear
+fn arc_radius(a: [i32; 3], b: [i32; 3]) -> Option<f64> {
+    let a = a.map(f64::from);
+    let b = b.map(f64::from);
+    let length = |v: [f64; 3]| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    let cross = [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ];
+    let area2 = length(cross);
+    if area2 < 1e-6 {
+        return None;
+    }
+    // R = |a|·|b|·|a - b| / (2·|a × b|)
+    Some(length(a) * length(b) * length([a[0] - b[0], a[1] - b[1], a[2] - b[2]]) / (2.0 * area2))
+}
+
+fn check_credentials(ssid: &str, password: &str) -> Result<(), TelloError> {
+    // The SDK splits on spaces, so neither can contain any
+    if ssid.is_empty() || ssid.chars().any(char::is_whitespace) {
+        return Err(invalid("SSID must be non-empty and contain no spaces"));
+    }
+    if password.is_empty() || password.chars().any(char::is_whitespace) {
+        return Err(invalid("Password must be non-empty and contain no spaces"));
+    }
+    Ok(())
+}
+
+impl fmt::Display for TelloCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Command => write!(f, "command"),
+            Self::Takeoff => write!(f, "takeoff"),
+            Self::Land => write!(f, "land"),
+            Self::StreamOn => write!(f, "streamon"),
+            Self::StreamOff => write!(f, "streamoff"),
+            Self::Emergency => write!(f, "emergency"),
+            Self::Stop => write!(f, "stop"),
+            Self::MotorOn => write!(f, "motoron"),
+            Self::MotorOff => write!(f, "motoroff"),
+            Self::ThrowFly => write!(f, "throwfly"),
+            Self::Move { direction, distance } => write!(f, "{} {}", direction.keyword(), distance),
+            Self::Rotate { rotation, degrees } => write!(f, "{} {}", rotation.keyword(), degrees),
+            Self::Flip(direction) => write!(f, "flip {}", direction.letter()),
+            Self::Go { x, y, z, speed, pad } => {
+                write!(f, "go {} {} {} {}", x, y, z, speed)?;
+                match pad {
+                    Some(pad) => write!(f, " {}", pad),
+                    None => Ok(()),
+                }
+            }
+            Self::Curve { x1, y1, z1, x2, y2, z2, speed, pad } => {
+                write!(f, "curve {} {} {} {} {} {} {}", x1, y1, z1, x2, y2, z2, speed)?;
+                match pad {
+                    Some(pad) => write!(f, " {}", pad),
+                    None => Ok(()),
+                }
+            }
+            Self::Jump { x, y, z, speed, yaw, pad1, pad2 } => {
+                write!(f, "jump {} {} {} {} {} {} {}", x, y, z, speed, yaw, pad1, pad2)
+            }
+            Self::Speed(speed) => write!(f, "speed {}", speed),
+            Self::Rc { left_right, forward_back, up_down, yaw } => {
+                write!(f, "rc {} {} {} {}", left_right, forward_back, up_down, yaw)
+            }
+            Self::Wifi { ssid, password } => write!(f, "wifi {} {}", ssid, password),
+            Self::Ap { ssid, password } => write!(f, "ap {} {}", ssid, password),
+            Self::Port { state_port, video_port } => write!(f, "port {} {}", state_port, video_port),
+            Self::MissionPadOn => write!(f, "mon"),
+            Self::MissionPadOff => write!(f, "moff"),
+            Self::MissionPadDirection(direction) => write!(f, "mdirection {}", direction),
+            Self::DownVision(on) => write!(f, "downvision {}", u8::from(*on)),
+            Self::SetFps(fps) => write!(f, "setfps {}", fps.name()),
+            Self::SetResolution(resolution) => write!(f, "setresolution {}", resolution.name()),
+            Self::SetBitrate(bitrate) => write!(f, "setbitrate {}", bitrate),
+            Self::Query(query) => write!(f, "{}", query.keyword()),
+        }
+    }
+}
+
+// Parses wire-format commands (e.g. from the raw command box) with the same
+// validation as the constructors
+impl FromStr for TelloCommand {
+    type Err = TelloError;
+
+    fn from_str(command: &str) -> Result<Self, TelloError> {
+        let parts: Vec<&str> = command.split_whitespace().collect();
+        let Some((&keyword, args)) = parts.split_first() else {
+            return Err(invalid("Empty command"));
+        };
+
+        if let Some(query) = Query::ALL.into_iter().find(|query| query.keyword() == keyword) {
+            expect_args(keyword, args, &[0])?;
+            return Ok(Self::Query(query));
+        }
+
+        let simple = match keyword {
+            "command" => Some(Self::Command),
+            "takeoff" => Some(Self::Takeoff),
+            "land" => Some(Self::Land),
+            "streamon" => Some(Self::StreamOn),
+            "streamoff" => Some(Self::StreamOff),
+            "emergency" => Some(Self::Emergency),
+            "stop" => Some(Self::Stop),
+            "motoron" => Some(Self::MotorOn),
+            "motoroff" => Some(Self::MotorOff),
+            "throwfly" => Some(Self::ThrowFly),
+            "mon" => Some(Self::MissionPadOn),
+            "moff" => Some(Self::MissionPadOff),
+            _ => None,
+        };
+        if let Some(command) = simple {
+            expect_args(keyword, args, &[0])?;
+            return Ok(command);
+        }
+
+        let direction = match keyword {
+            "up" => Some(Direction::Up),
+            "down" => Some(Direction::Down),
+            "left" => Some(Direction::Left),
+            "right" => Some(Direction::Right),
+            "forward" => Some(Direction::Forward),
+            "back" => Some(Direction::Back),
+            _ => None,
+        };
+        if let Some(direction) = direction {
+            expect_args(keyword, args, &[1])?;
+            return Self::movement(direction, number(args[0])?);
+        }
+
+        match keyword {
+            "cw" | "ccw" => {
+                expect_args(keyword, args, &[1])?;
+                let rotation = if keyword == "cw" { Rotation::Clockwise } else { Rotation::CounterClockwise };
+                Self::rotate(rotation, number(args[0])?)
+            }
+            "flip" => {
+                expect_args(keyword, args, &[1])?;
+                Self::flip(args[0])
+            }
+            "go" => {
+                expect_args(keyword, args, &[4, 5])?;
+                let pad = args.get(4).map(|pad| MissionPad::parse(pad)).transpose()?;
+                Self::go(number(args[0])?, number(args[1])?, number(args[2])?, number(args[3])?, pad)
+            }
+            "curve" => {
+                expect_args(keyword, args, &[7, 8])?;
+                let pad = args.get(7).map(|pad| MissionPad::parse(pad)).transpose()?;
+                Self::curve(
+                    number(args[0])?, number(args[1])?, number(args[2])?,
+                    number(args[3])?, number(args[4])?, number(args[5])?,
+                    number(args[6])?, pad,
+                )
+            }
+            "jump" => {
+                expect_args(keyword, args, &[7])?;
+                Self::jump(
+                    number(args[0])?, number(args[1])?, number(args[2])?,
+                    number(args[3])?, number(args[4])?,
+                    MissionPad::parse(args[5])?, MissionPad::parse(args[6])?,
+                )
+            }
+            "speed" => {
+                expect_args(keyword, args, &[1])?;
+                Self::speed(number(args[0])?)
+            }
+            "rc" => {
+                expect_args(keyword, args, &[4])?;
+                Self::rc(number(args[0])?, number(args[1])?, number(args[2])?, number(args[3])?)
+            }
+            "wifi" => {
+                expect_args(keyword, args, &[2])?;
+                Self::wifi(args[0], args[1])
+            }
+            "ap" => {
+                expect_args(keyword, args, &[2])?;
+                Self::ap(args[0], args[1])
+            }
+            "port" => {
+                expect_args(keyword, args, &[2])?;
+                Self::port(number(args[0])?, number(args[1])?)
+            }
+            "mdirection" => {
+                expect_args(keyword, args, &[1])?;
+                Self::mission_pad_direction(number(args[0])?)
+            }
+            "downvision" => {
+                expect_args(keyword, args, &[1])?;
+                match args[0] {
+                    "0" => Ok(Self::DownVision(false)),
+                    "1" => Ok(Self::DownVision(true)),
+                    other => Err(invalid(format!("downvision expects 0 or 1, got '{}'", other))),
+                }
+            }
+            "setfps" => {
+                expect_args(keyword, args, &[1])?;
+                Ok(Self::SetFps(FrameRate::parse(args[0])?))
+            }
+            "setresolution" => {
+                expect_args(keyword, args, &[1])?;
+                Ok(Self::SetResolution(Resolution::parse(args[0])?))
+            }
+            "setbitrate" => {
+                expect_args(keyword, args, &[1])?;
+                Self::set_bitrate(number(args[0])?)
+            }
+            _ => Err(invalid(format!("Unknown command '{}'", keyword))),
+        }
+    }
+}
+
+fn expect_args(keyword: &str, args: &[&str], counts: &[usize]) -> Result<(), TelloError> {
+    if counts.contains(&args.len()) {
+        return Ok(());
+    }
+    let expected: Vec<String> = counts.iter().map(usize::to_string).collect();
+    Err(invalid(format!("'{}' takes {} argument(s), got {}", keyword, expected.join(" or "), args.len())))
+}
+
+fn number<T: FromStr>(value: &str) -> Result<T, TelloError> {
+    value.parse().map_err(|_| invalid(format!("Expected a number, got '{}'", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(command: &TelloCommand) {
+        assert_eq!(&command.to_string().parse::<TelloCommand>().unwrap(), command);
+    }
+
+    #[test]
+    fn control_commands() {
+        let commands = [
+            (TelloCommand::Command, "command"),
+            (TelloCommand::Takeoff, "takeoff"),
+            (TelloCommand::Land, "land"),
+            (TelloCommand::StreamOn, "streamon"),
+            (TelloCommand::StreamOff, "streamoff"),
+            (TelloCommand::Emergency, "emergency"),
+            (TelloCommand::Stop, "stop"),
+            (TelloCommand::MotorOn, "motoron"),
+            (TelloCommand::MotorOff, "motoroff"),
+            (TelloCommand::ThrowFly, "throwfly"),
+        ];
+        for (command, wire) in commands {
+            assert_eq!(command.to_string(), wire);
+            round_trip(&command);
+        }
+        assert!("takeoff now".parse::<TelloCommand>().is_err());
+    }
+
+    #[test]
+    fn movement() {
+        let command = TelloCommand::movement(Direction::Forward, 50).unwrap();
+        assert_eq!(command.to_string(), "forward 50");
+        round_trip(&command);
+        assert_eq!(TelloCommand::movement(Direction::Up, 500).unwrap().to_string(), "up 500");
+
+        assert!(TelloCommand::movement(Direction::Left, 19).is_err());
+        assert!(TelloCommand::movement(Direction::Down, 501).is_err());
+        assert!("back fast".parse::<TelloCommand>().is_err());
+    }
+
+    #[test]
+    fn rotate() {
+        let command = TelloCommand::rotate(Rotation::CounterClockwise, 90).unwrap();
+        assert_eq!(command.to_string(), "ccw 90");
+        round_trip(&command);
+        assert_eq!(TelloCommand::rotate(Rotation::Clockwise, 360).unwrap().to_string(), "cw 360");
+
+        assert!(TelloCommand::rotate(Rotation::Clockwise, 0).is_err());
+        assert!(TelloCommand::rotate(Rotation::Clockwise, 361).is_err());
+    }
+
+    #[test]
+    fn flip() {
+        assert_eq!(TelloCommand::flip("l").unwrap().to_string(), "flip l");
+        assert_eq!(TelloCommand::flip("Back").unwrap().to_string(), "flip b");
+        round_trip(&TelloCommand::flip("f").unwrap());
+
+        assert!(TelloCommand::flip("x").is_err());
+        assert!("flip x".parse::<TelloCommand>().is_err());
+    }
+
+    #[test]
+    fn go() {
+        let command = TelloCommand::go(100, -50, 0, 60, None).unwrap();
+        assert_eq!(command.to_string(), "go 100 -50 0 60");
+        round_trip(&command);
+
+        let command = TelloCommand::go(0, 0, 100, 30, Some(MissionPad::new(3).unwrap())).unwrap();
+        assert_eq!(command.to_string(), "go 0 0 100 30 m3");
+        round_trip(&command);
+
+        assert!(TelloCommand::go(10, -20, 5, 50, None).is_err());
+        assert!(TelloCommand::go(501, 0, 0, 50, None).is_err());
+        assert!(TelloCommand::go(100, 0, 0, 101, None).is_err());
+        assert!("go 100 0 0 50 m9".parse::<TelloCommand>().is_err());
+    }
+
+    #[test]
+    fn curve() {
+        // Quarter circle with a radius of 100cm
+        let command = TelloCommand::curve(71, 29, 0, 100, 100, 0, 40, None).unwrap();
+        assert_eq!(command.to_string(), "curve 71 29 0 100 100 0 40");
+        round_trip(&command);
+
+        let command = TelloCommand::curve(71, 29, 0, 100, 100, 0, 40, Some(MissionPad::new(-2).unwrap())).unwrap();
+        assert_eq!(command.to_string(), "curve 71 29 0 100 100 0 40 m-2");
+        round_trip(&command);
+
+        // Too fast, collinear, and a radius over 10m
+        assert!(TelloCommand::curve(71, 29, 0, 100, 100, 0, 61, None).is_err());
+        assert!(TelloCommand::curve(50, 0, 0, 100, 0, 0, 40, None).is_err());
+        assert!(TelloCommand::curve(250, 1, 0, 500, 0, 0, 40, None).is_err());
+    }
+
+    #[test]
+    fn jump() {
+        let command = TelloCommand::jump(100, 0, 80, 50, 90, MissionPad::new(1).unwrap(), MissionPad::new(2).unwrap()).unwrap();
+        assert_eq!(command.to_string(), "jump 100 0 80 50 90 m1 m2");
+        round_trip(&command);
+
+        assert!(TelloCommand::jump(100, 0, 80, 50, 400, MissionPad::new(-1).unwrap(), MissionPad::new(-1).unwrap()).is_err());
+        assert!("jump 100 0 80 50 90 m1".parse::<TelloCommand>().is_err());
+    }
+
+    #[test]
+    fn speed() {
+        let command = TelloCommand::speed(50).unwrap();
+        assert_eq!(command.to_string(), "speed 50");
+        round_trip(&command);
+
+        assert!(TelloCommand::speed(9).is_err());
+        assert!(TelloCommand::speed(500).is_err());
+        assert!("speed 500".parse::<TelloCommand>().is_err());
+    }
+
+    #[test]
+    fn rc() {
+        let command = TelloCommand::rc(-100, 0, 50, 100).unwrap();
+        assert_eq!(command.to_string(), "rc -100 0 50 100");
+        round_trip(&command);
+
+        assert!(TelloCommand::rc(0, 101, 0, 0).is_err());
+        assert!(TelloCommand::rc(0, 0, 0, -101).is_err());
+        assert!("rc 0 0 0".parse::<TelloCommand>().is_err());
+    }
+
+    #[test]
+    fn wifi_and_ap() {
+        let command = TelloCommand::wifi("TELLO-Lab", "secret123").unwrap();
+        assert_eq!(command.to_string(), "wifi TELLO-Lab secret123");
+        round_trip(&command);
+
+        let command = TelloCommand::ap("HomeNet", "hunter22").unwrap();
+        assert_eq!(command.to_string(), "ap HomeNet hunter22");
+        round_trip(&command);
+
+        assert!(TelloCommand::wifi("My Net", "secret123").is_err());
+        assert!(TelloCommand::ap("HomeNet", "").is_err());
+    }
+
+    #[test]
+    fn port() {
+        let command = TelloCommand::port(8890, 11111).unwrap();
+        assert_eq!(command.to_string(), "port 8890 11111");
+        round_trip(&command);
+
+        assert!(TelloCommand::port(80, 11111).is_err());
+        assert!(TelloCommand::port(9000, 9000).is_err());
+    }
+
+    #[test]
+    fn mission_pads() {
+        assert_eq!(TelloCommand::MissionPadOn.to_string(), "mon");
+        assert_eq!(TelloCommand::MissionPadOff.to_string(), "moff");
+        round_trip(&TelloCommand::MissionPadOn);
+
+        let command = TelloCommand::mission_pad_direction(2).unwrap();
+        assert_eq!(command.to_string(), "mdirection 2");
+        round_trip(&command);
+        assert!(TelloCommand::mission_pad_direction(3).is_err());
+
+        assert_eq!(MissionPad::new(-1).unwrap().to_string(), "m-1");
+        assert!(MissionPad::new(0).is_err());
+        assert!(MissionPad::parse("pad1").is_err());
+    }
+
+    #[test]
+    fn downvision() {
+        assert_eq!(TelloCommand::DownVision(true).to_string(), "downvision 1");
+        assert_eq!(TelloCommand::DownVision(false).to_string(), "downvision 0");
+        round_trip(&TelloCommand::DownVision(true));
+        assert!("downvision 2".parse::<TelloCommand>().is_err());
+    }
+
+    #[test]
+    fn video_settings() {
+        assert_eq!(TelloCommand::SetFps(FrameRate::Medium).to_string(), "setfps medium");
+        assert_eq!(TelloCommand::SetResolution(Resolution::Low).to_string(), "setresolution low");
+        round_trip(&TelloCommand::SetFps(FrameRate::High));
+        round_trip(&TelloCommand::SetResolution(Resolution::High));
+        assert!("setfps ultra".parse::<TelloCommand>().is_err());
+
+        let command = TelloCommand::set_bitrate(5).unwrap();
+        assert_eq!(command.to_string(), "setbitrate 5");
+        round_trip(&command);
+        assert!(TelloCommand::set_bitrate(6).is_err());
+        assert!(TelloCommand::set_bitrate(-1).is_err());
+    }
+
+    #[test]
+    fn queries() {
+        for query in Query::ALL {
+            let command = TelloCommand::Query(query);
+            assert_eq!(command.to_string(), query.keyword());
+            round_trip(&command);
+        }
+        assert_eq!(TelloCommand::Query(Query::Temperature).to_string(), "temp?");
+        assert!("pitch?".parse::<TelloCommand>().is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_and_empty() {
+        assert!("hover".parse::<TelloCommand>().is_err());
+        assert!("   ".parse::<TelloCommand>().is_err());
+    }
+}
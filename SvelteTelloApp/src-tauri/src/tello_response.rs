@@ -0,0 +1,166 @@
+// Replies to Tello read commands
+// Each query answers in its own format ("87", "60~63C", "10dm", "100mm",
+// "pitch:0;roll:0;yaw:0;"), and any of them can come back as "error" or
+// "unknown command" on older firmware. Parsing them here means callers get a
+// real reading or a reason, never a silent zero.
+
+use crate::error::TelloError;
+use crate::tello_command::Query;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Reading {
+    Speed { cm_per_s: f64 },
+    Battery { percent: i32 },
+    // Motor time since takeoff
+    Time { seconds: i32 },
+    // The drone answers in dm; converted to cm like the state stream's `h`
+    Height { cm: i32 },
+    Temperature { low: i32, high: i32 },
+    Attitude { pitch: i32, roll: i32, yaw: i32 },
+    Barometer { meters: f64 },
+    Acceleration { x: f64, y: f64, z: f64 },
+    // The drone answers in mm; converted to cm like the state stream's `tof`
+    Tof { cm: i32 },
+    WifiSnr { snr: i32 },
+    // sdk?, sn?, hardware?, wifiversion?, ap? and ssid? answer free text
+    Text { value: String },
+}
+
+// "error" becomes DroneError, "unknown command" Unsupported, anything else that
+// does not fit the query UnexpectedReply
+pub fn parse(query: Query, reply: &str) -> Result<Reading, TelloError> {
+    let reply = reply.trim();
+    let keyword = query.keyword();
+
+    if reply.to_ascii_lowercase().starts_with("unknown command") {
+        return Err(TelloError::Unsupported(keyword.to_string()));
+    }
+    if let Some(reason) = reply.strip_prefix("error") {
+        let reason = reason.trim();
+        return Err(TelloError::DroneError {
+            command: keyword.to_string(),
+            reason: if reason.is_empty() { "error".to_string() } else { reason.to_string() },
+        });
+    }
+
+    let malformed = || TelloError::UnexpectedReply { command: keyword.to_string(), reply: reply.to_string() };
+
+    let reading = match query {
+        Query::Speed => number(reply).map(|cm_per_s| Reading::Speed { cm_per_s }),
+        Query::Battery => number(reply)
+            .filter(|percent| (0..=100).contains(percent))
+            .map(|percent| Reading::Battery { percent }),
+        Query::Time => number(reply.trim_end_matches('s')).map(|seconds| Reading::Time { seconds }),
+        Query::Height => number(reply.trim_end_matches("dm")).map(|dm: i32| Reading::Height { cm: dm * 10 }),
+        // "60~63C", or a single value on some firmware
+        Query::Temperature => {
+            let range = reply.trim_end_matches('C');
+            match range.split_once('~') {
+                Some((low, high)) => number(low).zip(number(high)),
+                None => number(range).map(|value| (value, value)),
+            }
+            .map(|(low, high)| Reading::Temperature { low, high })
+        }
+        Query::Attitude => {
+            let values = fields(reply);
+            field(&values, "pitch")
+                .zip(field(&values, "roll"))
+                .zip(field(&values, "yaw"))
+                .map(|((pitch, roll), yaw)| Reading::Attitude { pitch, roll, yaw })
+        }
+        Query::Barometer => number(reply).map(|meters| Reading::Barometer { meters }),
+        Query::Acceleration => {
+            let values = fields(reply);
+            field(&values, "agx")
+                .zip(field(&values, "agy"))
+                .zip(field(&values, "agz"))
+                .map(|((x, y), z)| Reading::Acceleration { x, y, z })
+        }
+        Query::Tof => number(reply.trim_end_matches("mm")).map(|mm: i32| Reading::Tof { cm: mm / 10 }),
+        Query::Wifi => number(reply).map(|snr| Reading::WifiSnr { snr }),
+        Query::Sdk | Query::SerialNumber | Query::Hardware | Query::WifiVersion | Query::Ap | Query::Ssid => {
+            // A bare "ok" is a late reply to some other command
+            (!reply.is_empty() && !reply.eq_ignore_ascii_case("ok")).then(|| Reading::Text { value: reply.to_string() })
+        }
+    };
+
+    reading.ok_or_else(malformed)
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> Option<T> {
+    value.trim().parse().ok()
+}
+
+// "key:value;key:value;" pairs, as sent by attitude? and acceleration?
+fn fields(reply: &str) -> Vec<(&str, &str)> {
+    reply.split(';')
+        .filter_map(|pair| pair.split_once(':'))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .collect()
+}
+
+fn field<T: std::str::FromStr>(fields: &[(&str, &str)], key: &str) -> Option<T> {
+    fields.iter().find(|(name, _)| *name == key).and_then(|(_, value)| number(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scalar_readings() {
+        assert_eq!(parse(Query::Battery, "87\r\n"), Ok(Reading::Battery { percent: 87 }));
+        assert_eq!(parse(Query::Speed, "100.0"), Ok(Reading::Speed { cm_per_s: 100.0 }));
+        assert_eq!(parse(Query::Time, "42s"), Ok(Reading::Time { seconds: 42 }));
+        assert_eq!(parse(Query::Height, "12dm"), Ok(Reading::Height { cm: 120 }));
+        assert_eq!(parse(Query::Tof, "100mm"), Ok(Reading::Tof { cm: 10 }));
+        assert_eq!(parse(Query::Barometer, "195.39"), Ok(Reading::Barometer { meters: 195.39 }));
+        assert_eq!(parse(Query::Wifi, "90"), Ok(Reading::WifiSnr { snr: 90 }));
+    }
+
+    #[test]
+    fn parses_temperature_range() {
+        assert_eq!(parse(Query::Temperature, "60~63C"), Ok(Reading::Temperature { low: 60, high: 63 }));
+        assert_eq!(parse(Query::Temperature, "83C"), Ok(Reading::Temperature { low: 83, high: 83 }));
+    }
+
+    #[test]
+    fn parses_key_value_readings() {
+        assert_eq!(
+            parse(Query::Attitude, "pitch:-2;roll:1;yaw:-47;\r\n"),
+            Ok(Reading::Attitude { pitch: -2, roll: 1, yaw: -47 })
+        );
+        assert_eq!(
+            parse(Query::Acceleration, "agx:-1.00;agy:-4.00;agz:-998.00;"),
+            Ok(Reading::Acceleration { x: -1.0, y: -4.0, z: -998.0 })
+        );
+        assert!(matches!(parse(Query::Attitude, "pitch:0;roll:0;"), Err(TelloError::UnexpectedReply { .. })));
+    }
+
+    #[test]
+    fn parses_text_readings() {
+        assert_eq!(parse(Query::Sdk, "30"), Ok(Reading::Text { value: "30".to_string() }));
+        assert_eq!(parse(Query::SerialNumber, "0TQZH77ED00H0N"), Ok(Reading::Text { value: "0TQZH77ED00H0N".to_string() }));
+        assert!(matches!(parse(Query::SerialNumber, "ok"), Err(TelloError::UnexpectedReply { .. })));
+    }
+
+    #[test]
+    fn reports_drone_errors() {
+        assert_eq!(
+            parse(Query::Battery, "error"),
+            Err(TelloError::DroneError { command: "battery?".to_string(), reason: "error".to_string() })
+        );
+        assert_eq!(
+            parse(Query::Height, "error Not joystick"),
+            Err(TelloError::DroneError { command: "height?".to_string(), reason: "Not joystick".to_string() })
+        );
+        assert_eq!(parse(Query::Sdk, "unknown command: sdk?"), Err(TelloError::Unsupported("sdk?".to_string())));
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(matches!(parse(Query::Battery, "ok"), Err(TelloError::UnexpectedReply { .. })));
+        assert!(matches!(parse(Query::Battery, "187"), Err(TelloError::UnexpectedReply { .. })));
+        assert!(matches!(parse(Query::Tof, "far"), Err(TelloError::UnexpectedReply { .. })));
+    }
+}
@@ -1,14 +1,22 @@
-// Video packet receiver for Tello
-// Receives UDP H.264 stream from port 11111 and provides latest packet
+// Video receiver for Tello
+// Receives the UDP H.264 stream on the endpoint's video port (11111), reassembles
+// it into complete frames and hands them out through a bounded channel
 
+use crate::endpoint::DroneEndpoint;
+use crate::error::TelloError;
+use crate::h264::{AccessUnit, FrameAssembler};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
 use std::sync::{Arc, Mutex};
 use std::net::UdpSocket;
 use std::time::Duration;
 use std::thread;
 
+// ~4 seconds of video at 30fps
+const FRAME_QUEUE_SIZE: usize = 120;
+
 pub struct TelloVideoCapture {
     running: Arc<Mutex<bool>>,
-    latest_packet: Arc<Mutex<Option<Vec<u8>>>>,
+    frames: Option<Receiver<AccessUnit>>,
     receiver_thread: Option<thread::JoinHandle<()>>,
 }
 
@@ -16,36 +24,37 @@ impl TelloVideoCapture {
     pub fn new() -> Self {
         Self {
             running: Arc::new(Mutex::new(false)),
-            latest_packet: Arc::new(Mutex::new(None)),
+            frames: None,
             receiver_thread: None,
         }
     }
 
-    pub fn start(&mut self) {
+    // Binds the video port right away, so a busy port is reported to the caller
+    // instead of silently ending the receiver thread
+    pub fn start(&mut self, endpoint: &DroneEndpoint) -> Result<(), TelloError> {
+        let local_addr = endpoint.local_video_bind();
+        let drone_video_addr = endpoint.video_addr();
+        
+        println!("[TelloVideo] 🎬 Starting UDP receiver on {}...", local_addr);
+        
+        let socket = UdpSocket::bind(&local_addr).map_err(|e| {
+            eprintln!("[TelloVideo] ❌ Failed to bind: {}", e);
+            eprintln!("[TelloVideo] 💡 Tip: Make sure no other app is using {}", local_addr);
+            TelloError::VideoPortBusy { port: endpoint.video_port, reason: e.to_string() }
+        })?;
+        println!("[TelloVideo] ✅ Bound to {}", local_addr);
+        
         let running = Arc::clone(&self.running);
-        let latest_packet = Arc::clone(&self.latest_packet);
+        let (frame_tx, frame_rx) = mpsc::sync_channel(FRAME_QUEUE_SIZE);
+        self.frames = Some(frame_rx);
         
         *running.lock().unwrap() = true;
         
         let handle = thread::spawn(move || {
-            println!("[TelloVideo] 🎬 Starting UDP receiver on port 11111...");
-            
-            let socket = match UdpSocket::bind("0.0.0.0:11111") {
-                Ok(s) => {
-                    println!("[TelloVideo] ✅ Bound to port 11111");
-                    s
-                }
-                Err(e) => {
-                    eprintln!("[TelloVideo] ❌ Failed to bind: {}", e);
-                    eprintln!("[TelloVideo] 💡 Tip: Make sure no other app is using port 11111");
-                    return;
-                }
-            };
-            
             // Send a "kick-start" packet to the drone's video port
             // Some Tello versions need this to start streaming
             println!("[TelloVideo] 📤 Sending video stream kick-start packet...");
-            if let Err(e) = socket.send_to(&[0x01], "192.168.10.1:11111") {
+            if let Err(e) = socket.send_to(&[0x01], drone_video_addr) {
                 eprintln!("[TelloVideo] ⚠️ Could not send kick-start: {}", e);
             } else {
                 println!("[TelloVideo] ✅ Kick-start sent");
@@ -57,6 +66,8 @@ impl TelloVideoCapture {
             let mut buf = vec![0u8; 65536]; // 64KB buffer
             let mut packet_count = 0;
             let mut wait_count = 0;
+            let mut assembler = FrameAssembler::new();
+            let mut sink = FrameSink::new(frame_tx);
             
             println!("[TelloVideo] 🎯 Waiting for H.264 packets from drone...");
             println!("[TelloVideo] 💡 Drone may take a few seconds to start streaming");
@@ -71,9 +82,10 @@ impl TelloVideoCapture {
                         packet_count += 1;
                         wait_count = 0; // Reset wait counter on successful receive
                         
-                        // Store raw H.264 packet
-                        let packet = buf[..size].to_vec();
-                        *latest_packet.lock().unwrap() = Some(packet);
+                        // Reassemble complete frames from the H.264 fragments
+                        for frame in assembler.push(&buf[..size]) {
+                            sink.send(frame);
+                        }
                         
                         if packet_count % 30 == 0 {
                             println!("[TelloVideo] 📊 {} packets received", packet_count);
@@ -114,14 +126,22 @@ impl TelloVideoCapture {
                 }
             }
             
-            println!("[TelloVideo] 🛑 Video receiver stopped ({} packets total)", packet_count);
+            for frame in assembler.flush() {
+                sink.send(frame);
+            }
+            
+            println!("[TelloVideo] 🛑 Video receiver stopped ({} packets, {} frames, {} dropped)", 
+                     packet_count, sink.sent, sink.dropped);
         });
         
         self.receiver_thread = Some(handle);
+        Ok(())
     }
 
-    pub fn get_packet(&self) -> Option<Vec<u8>> {
-        self.latest_packet.lock().unwrap().clone()
+    // Complete frames in arrival order. Can only be taken once; the channel
+    // closes when the capture stops.
+    pub fn take_frames(&mut self) -> Option<Receiver<AccessUnit>> {
+        self.frames.take()
     }
 
     pub fn stop(&mut self) {
@@ -139,3 +159,105 @@ impl Drop for TelloVideoCapture {
     }
 }
 
+
+// Sending side of the frame queue. If the consumer falls behind, whole frames
+// are dropped (never fragments) and delivery resumes at the next keyframe, so
+// the decoder never sees a picture whose references are missing.
+struct FrameSink {
+    sender: SyncSender<AccessUnit>,
+    waiting_for_keyframe: bool,
+    sent: u64,
+    dropped: u64,
+}
+
+impl FrameSink {
+    fn new(sender: SyncSender<AccessUnit>) -> Self {
+        Self {
+            sender,
+            waiting_for_keyframe: true,
+            sent: 0,
+            dropped: 0,
+        }
+    }
+
+    fn send(&mut self, frame: AccessUnit) {
+        if self.waiting_for_keyframe && !frame.keyframe {
+            self.dropped += 1;
+            return;
+        }
+        
+        match self.sender.try_send(frame) {
+            Ok(()) => {
+                self.waiting_for_keyframe = false;
+                self.sent += 1;
+            }
+            Err(TrySendError::Full(_)) => {
+                if !self.waiting_for_keyframe {
+                    eprintln!("[TelloVideo] ⚠️ Frame queue full, skipping to next keyframe");
+                }
+                self.waiting_for_keyframe = true;
+                self.dropped += 1;
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                self.dropped += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn frame(keyframe: bool, sequence: u64) -> AccessUnit {
+        AccessUnit { data: vec![0, 0, 0, 1, if keyframe { 0x65 } else { 0x41 }], keyframe, sequence, received_at: Instant::now() }
+    }
+
+    fn received(frames: &Receiver<AccessUnit>) -> Vec<u64> {
+        frames.try_iter().map(|frame| frame.sequence).collect()
+    }
+
+    #[test]
+    fn waits_for_a_keyframe_before_sending_anything() {
+        let (frame_tx, frames) = mpsc::sync_channel(4);
+        let mut sink = FrameSink::new(frame_tx);
+
+        sink.send(frame(false, 1));
+        sink.send(frame(true, 2));
+        sink.send(frame(false, 3));
+
+        assert_eq!(received(&frames), vec![2, 3]);
+        assert_eq!((sink.sent, sink.dropped), (2, 1));
+    }
+
+    #[test]
+    fn skips_to_the_next_keyframe_once_the_queue_is_full() {
+        let (frame_tx, frames) = mpsc::sync_channel(2);
+        let mut sink = FrameSink::new(frame_tx);
+
+        sink.send(frame(true, 1));
+        sink.send(frame(false, 2));
+        sink.send(frame(false, 3)); // queue full
+        assert_eq!(received(&frames), vec![1, 2]);
+
+        // Room again, but these reference the frame that was lost
+        sink.send(frame(false, 4));
+        sink.send(frame(false, 5));
+        sink.send(frame(true, 6));
+        sink.send(frame(false, 7));
+
+        assert_eq!(received(&frames), vec![6, 7]);
+        assert_eq!((sink.sent, sink.dropped), (4, 3));
+    }
+
+    #[test]
+    fn counts_frames_sent_to_a_closed_queue_as_dropped() {
+        let (frame_tx, frames) = mpsc::sync_channel(2);
+        let mut sink = FrameSink::new(frame_tx);
+        drop(frames);
+
+        sink.send(frame(true, 1));
+        assert_eq!((sink.sent, sink.dropped), (0, 1));
+    }
+}
@@ -0,0 +1,56 @@
+// Video quality presets
+// The SDK exposes bitrate, resolution and frame rate as separate commands
+// (SDK 2.0+); the settings page only offers a handful of presets on top of them.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::TelloError;
+use crate::tello_command::{FrameRate, Resolution};
+
+// What the drone last acknowledged; None until the setting has been applied
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct VideoSettings {
+    pub quality: Option<String>,
+    pub bitrate: Option<u8>, // Mbps, 0 = auto
+    pub resolution: Option<String>,
+    pub fps: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VideoQuality {
+    Auto,
+    Low,
+    Medium,
+    High,
+}
+
+impl VideoQuality {
+    pub fn parse(quality: &str) -> Result<Self, TelloError> {
+        match quality.to_ascii_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "low" => Ok(Self::Low),
+            "medium" => Ok(Self::Medium),
+            "high" => Ok(Self::High),
+            _ => Err(TelloError::InvalidArgument(format!("Unknown video quality '{}' (expected auto, low, medium or high)", quality))),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+        }
+    }
+
+    // (setbitrate, setresolution, setfps) arguments
+    pub fn parameters(self) -> (u8, Resolution, FrameRate) {
+        match self {
+            Self::Auto => (0, Resolution::High, FrameRate::High),
+            Self::Low => (1, Resolution::Low, FrameRate::Medium),
+            Self::Medium => (3, Resolution::Low, FrameRate::High),
+            Self::High => (5, Resolution::High, FrameRate::High),
+        }
+    }
+}
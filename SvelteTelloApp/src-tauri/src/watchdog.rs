@@ -0,0 +1,437 @@
+// Link watchdog
+// The Tello lands by itself after 15 s without commands, and a dead WiFi link
+// otherwise goes unnoticed. This watches how long the drone has been silent
+// (no replies and no state packets), keeps the link alive while the user is
+// idle, and runs the configured recovery once the link is considered lost.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::Manager;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::command_channel::CommandChannel;
+use crate::flight_log::{self, FlightRecorder};
+use crate::state_stream::StateReceiver;
+use crate::tello_command::TelloCommand;
+use crate::{AppState, DroneState};
+
+pub(crate) const TICK: Duration = Duration::from_millis(250);
+const KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(3);
+// How often a recovery action is repeated while the link stays lost
+const RECOVERY_RETRY: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkRecovery {
+    // Re-enter SDK mode with `command` until the drone answers
+    Reconnect,
+    // Stop all motion with `rc 0 0 0 0`
+    Hover,
+    Land,
+}
+
+impl LinkRecovery {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Reconnect => "reconnect",
+            Self::Hover => "hover",
+            Self::Land => "land",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatchdogConfig {
+    pub degraded_after_ms: u64,
+    pub lost_after_ms: u64,
+    // Send a keepalive when no command went out for this long
+    pub keepalive_interval_ms: u64,
+    pub recovery: LinkRecovery,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            degraded_after_ms: 2000,
+            lost_after_ms: 6000,
+            keepalive_interval_ms: 5000,
+            recovery: LinkRecovery::Hover,
+        }
+    }
+}
+
+// Payload of the `link-degraded`, `link-lost` and `link-restored` events
+#[derive(Serialize, Clone, Debug)]
+pub struct LinkEvent {
+    pub silence_ms: u64,
+    pub recovery: Option<LinkRecovery>,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum LinkStatus {
+    Healthy,
+    Degraded,
+    Lost,
+}
+
+pub struct LinkWatchdog {
+    task: JoinHandle<()>,
+}
+
+impl LinkWatchdog {
+    // `config` is read on every tick, so changes apply without a restart
+    pub fn start(
+        config: Arc<Mutex<WatchdogConfig>>,
+        channel: CommandChannel,
+        state_receiver: Arc<Mutex<Option<StateReceiver>>>,
+        drone: Arc<Mutex<DroneState>>,
+        flight_log: Arc<Mutex<Option<FlightRecorder>>>,
+        app_handle: tauri::AppHandle,
+    ) -> Self {
+        let task = tokio::spawn(async move {
+            println!("[Watchdog] 🐕 Watching the drone link");
+
+            let mut watch = Watch::new();
+            let mut interval = tokio::time::interval(TICK);
+
+            loop {
+                interval.tick().await;
+
+                let config = config.lock().unwrap().clone();
+                let last_state = state_receiver.lock().unwrap().as_ref().and_then(StateReceiver::last_packet);
+
+                if let Some((event, payload)) = watch.update(&config, &channel, last_state, &drone) {
+                    // Whatever was flying the drone would undo the hover or
+                    // fight the landing
+                    let take_over = event == "link-lost" && config.recovery != LinkRecovery::Reconnect;
+                    emit(&app_handle, &flight_log, event, payload);
+                    if take_over {
+                        crate::take_over(&app_handle.state::<AppState>(), "Link lost");
+                    }
+                }
+                watch.recover(&config, &channel, &drone).await;
+            }
+        });
+
+        Self { task }
+    }
+}
+
+impl Drop for LinkWatchdog {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// The watchdog's state between ticks, kept apart from the task and the
+// AppHandle so the transitions can be driven directly; the swarm runs one
+// per drone
+pub(crate) struct Watch {
+    started: Instant,
+    status: LinkStatus,
+    probe: Option<JoinHandle<()>>,
+    last_recovery: Option<Instant>,
+}
+
+impl Watch {
+    pub(crate) fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            status: LinkStatus::Healthy,
+            probe: None,
+            last_recovery: None,
+        }
+    }
+
+    // Moves between healthy, degraded and lost, and returns the event to
+    // raise when the status changed
+    pub(crate) fn update(
+        &mut self,
+        config: &WatchdogConfig,
+        channel: &CommandChannel,
+        last_state: Option<Instant>,
+        drone: &Mutex<DroneState>,
+    ) -> Option<(&'static str, LinkEvent)> {
+        let now = Instant::now();
+        let activity = channel.activity();
+        let last_heard = [activity.last_reply, last_state].into_iter().flatten().max().unwrap_or(self.started);
+        let silence = now.duration_since(last_heard);
+        let silence_ms = silence.as_millis() as u64;
+
+        // Keep the drone from auto-landing while nothing else is being sent
+        if self.probe_idle() && now.duration_since(activity.last_sent) >= Duration::from_millis(config.keepalive_interval_ms) {
+            self.probe = Some(spawn_probe(channel.clone()));
+        }
+
+        if silence_ms >= config.lost_after_ms {
+            if self.status == LinkStatus::Lost {
+                return None;
+            }
+            self.status = LinkStatus::Lost;
+            self.last_recovery = None;
+            drone.lock().unwrap().connected = false;
+            Some(("link-lost", LinkEvent {
+                silence_ms,
+                recovery: Some(config.recovery),
+                message: format!("No contact with the drone for {:.1}s, recovery: {}", silence.as_secs_f64(), config.recovery.name()),
+            }))
+        } else if silence_ms >= config.degraded_after_ms {
+            if self.status != LinkStatus::Healthy {
+                return None;
+            }
+            self.status = LinkStatus::Degraded;
+            Some(("link-degraded", LinkEvent {
+                silence_ms,
+                recovery: None,
+                message: format!("No contact with the drone for {:.1}s", silence.as_secs_f64()),
+            }))
+        } else if self.status != LinkStatus::Healthy {
+            if self.status == LinkStatus::Lost {
+                drone.lock().unwrap().connected = true;
+            }
+            self.status = LinkStatus::Healthy;
+            Some(("link-restored", LinkEvent {
+                silence_ms,
+                recovery: None,
+                message: "Drone link restored".to_string(),
+            }))
+        } else {
+            None
+        }
+    }
+
+    // Runs the configured recovery while the link is lost
+    pub(crate) async fn recover(&mut self, config: &WatchdogConfig, channel: &CommandChannel, drone: &Mutex<DroneState>) {
+        if self.status != LinkStatus::Lost {
+            return;
+        }
+        let now = Instant::now();
+
+        // Probe until the drone answers, whatever the recovery; a reply or
+        // state packet is what ends the lost state
+        if self.last_recovery.is_none_or(|at| now.duration_since(at) >= RECOVERY_RETRY) {
+            self.last_recovery = Some(now);
+            if self.probe_idle() {
+                self.probe = Some(spawn_probe(channel.clone()));
+            }
+            if config.recovery == LinkRecovery::Land && drone.lock().unwrap().flying {
+                send(channel, TelloCommand::Land).await;
+            }
+        }
+
+        // rc values time out on the drone, so hovering has to be held
+        if config.recovery == LinkRecovery::Hover && drone.lock().unwrap().flying {
+            send(channel, TelloCommand::hover()).await;
+        }
+    }
+
+    fn probe_idle(&self) -> bool {
+        self.probe.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+}
+
+// `command` both keeps the drone in SDK mode and re-enters it after a reboot;
+// any answer counts as proof of life through the channel's activity
+fn spawn_probe(channel: CommandChannel) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        if let Err(e) = channel.request(&TelloCommand::Command.to_string(), KEEPALIVE_TIMEOUT).await {
+            println!("[Watchdog] ⚠️ Keepalive failed: {}", e);
+        }
+    })
+}
+
+async fn send(channel: &CommandChannel, command: TelloCommand) {
+    if let Err(e) = channel.send(&command.to_string()).await {
+        eprintln!("[Watchdog] ❌ Failed to send '{}': {}", command, e);
+    }
+}
+
+fn emit(app_handle: &tauri::AppHandle, flight_log: &Mutex<Option<FlightRecorder>>, event: &str, payload: LinkEvent) {
+    println!("[Watchdog] 📶 {}: {}", event, payload.message);
+    flight_log::record(flight_log, flight_log::mode_event(&event.replace('-', "_"), Some(payload.message.clone())));
+    if let Err(e) = app_handle.emit_all(event, payload) {
+        eprintln!("[Watchdog] ❌ Failed to emit {}: {}", event, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::UdpSocket;
+
+    // A drone that never answers unless told to; the watchdog's traffic is
+    // read back with non-blocking receives
+    async fn silent_drone() -> (UdpSocket, CommandChannel) {
+        let drone = UdpSocket::bind("127.0.0.1:0").unwrap();
+        drone.set_nonblocking(true).unwrap();
+        let channel = CommandChannel::bind("127.0.0.1:0", drone.local_addr().unwrap(), Arc::new(Mutex::new(None))).await.unwrap();
+        (drone, channel)
+    }
+
+    fn flying() -> Mutex<DroneState> {
+        Mutex::new(DroneState { connected: true, flying: true, ..Default::default() })
+    }
+
+    fn config(recovery: LinkRecovery) -> WatchdogConfig {
+        WatchdogConfig { recovery, ..WatchdogConfig::default() }
+    }
+
+    // Lets the channel task flush, then collects what reached the drone
+    async fn sent(drone: &UdpSocket) -> Vec<String> {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        let mut buf = [0u8; 1024];
+        let mut commands = Vec::new();
+        while let Ok(size) = drone.recv(&mut buf) {
+            commands.push(String::from_utf8_lossy(&buf[..size]).to_string());
+        }
+        commands
+    }
+
+    fn event(update: Option<(&'static str, LinkEvent)>) -> Option<&'static str> {
+        update.map(|(event, _)| event)
+    }
+
+    // Ticks until `at` after the start, recovering along the way like the task
+    async fn run_until(watch: &mut Watch, at: Duration, config: &WatchdogConfig, channel: &CommandChannel, drone: &Mutex<DroneState>) -> Vec<&'static str> {
+        let mut events = Vec::new();
+        while watch.started.elapsed() < at {
+            tokio::time::advance(TICK).await;
+            events.extend(event(watch.update(config, channel, None, drone)));
+            watch.recover(config, channel, drone).await;
+        }
+        events
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn degrades_then_loses_the_link_and_recovers() {
+        let (_drone, channel) = silent_drone().await;
+        let config = config(LinkRecovery::Reconnect);
+        let drone = flying();
+        let mut watch = Watch::new();
+
+        assert_eq!(event(watch.update(&config, &channel, None, &drone)), None);
+
+        tokio::time::advance(Duration::from_millis(config.degraded_after_ms)).await;
+        let (name, payload) = watch.update(&config, &channel, None, &drone).unwrap();
+        assert_eq!(name, "link-degraded");
+        assert_eq!(payload.silence_ms, config.degraded_after_ms);
+        assert_eq!(payload.message, "No contact with the drone for 2.0s");
+        assert_eq!(event(watch.update(&config, &channel, None, &drone)), None);
+
+        tokio::time::advance(Duration::from_millis(config.lost_after_ms - config.degraded_after_ms)).await;
+        let (name, payload) = watch.update(&config, &channel, None, &drone).unwrap();
+        assert_eq!(name, "link-lost");
+        assert_eq!(payload.recovery, Some(LinkRecovery::Reconnect));
+        assert_eq!(payload.message, "No contact with the drone for 6.0s, recovery: reconnect");
+        assert!(!drone.lock().unwrap().connected);
+        assert_eq!(event(watch.update(&config, &channel, None, &drone)), None);
+
+        // A state packet is as good as a reply
+        let (name, _) = watch.update(&config, &channel, Some(Instant::now()), &drone).unwrap();
+        assert_eq!(name, "link-restored");
+        assert!(drone.lock().unwrap().connected);
+        assert_eq!(event(watch.update(&config, &channel, Some(Instant::now()), &drone)), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn restores_a_degraded_link_without_losing_it() {
+        let (_drone, channel) = silent_drone().await;
+        let config = config(LinkRecovery::Land);
+        let drone = flying();
+        let mut watch = Watch::new();
+
+        tokio::time::advance(Duration::from_millis(config.degraded_after_ms)).await;
+        assert_eq!(event(watch.update(&config, &channel, None, &drone)), Some("link-degraded"));
+        assert_eq!(event(watch.update(&config, &channel, Some(Instant::now()), &drone)), Some("link-restored"));
+        assert!(drone.lock().unwrap().connected);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_reply_to_the_probe_ends_the_lost_state() {
+        let (drone_socket, channel) = silent_drone().await;
+        let config = config(LinkRecovery::Reconnect);
+        let drone = flying();
+        let mut watch = Watch::new();
+
+        let events = run_until(&mut watch, Duration::from_millis(config.lost_after_ms), &config, &channel, &drone).await;
+        assert_eq!(events, ["link-degraded", "link-lost"]);
+
+        // Reconnect only probes with `command`, it never moves the drone
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        let mut buf = [0u8; 64];
+        let mut client = None;
+        while let Ok((size, from)) = drone_socket.recv_from(&mut buf) {
+            assert_eq!(&buf[..size], b"command");
+            client = Some(from);
+        }
+        drone_socket.send_to(b"ok", client.expect("no keepalive probe was sent")).unwrap();
+        tokio::time::sleep(Duration::from_millis(1)).await;
+
+        assert_eq!(event(watch.update(&config, &channel, None, &drone)), Some("link-restored"));
+        assert!(drone.lock().unwrap().connected);
+    }
+
+    // What the recovery sent, without the keepalive probes
+    async fn recovery_sent(drone: &UdpSocket) -> Vec<String> {
+        sent(drone).await.into_iter().filter(|command| command != "command").collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn hover_holds_the_sticks_while_lost() {
+        let (drone_socket, channel) = silent_drone().await;
+        let config = config(LinkRecovery::Hover);
+        let drone = flying();
+        let mut watch = Watch::new();
+        let lost_at = Duration::from_millis(config.lost_after_ms);
+
+        run_until(&mut watch, lost_at - TICK, &config, &channel, &drone).await;
+        assert_eq!(recovery_sent(&drone_socket).await, Vec::<String>::new());
+
+        // Every tick, since the drone drops rc values after a moment
+        run_until(&mut watch, lost_at + TICK * 2, &config, &channel, &drone).await;
+        assert_eq!(recovery_sent(&drone_socket).await, ["rc 0 0 0 0"; 3]);
+
+        // Nothing to hold once it is on the ground
+        drone.lock().unwrap().flying = false;
+        run_until(&mut watch, lost_at + RECOVERY_RETRY * 2, &config, &channel, &drone).await;
+        assert_eq!(recovery_sent(&drone_socket).await, Vec::<String>::new());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn land_repeats_until_the_drone_is_down() {
+        let (drone_socket, channel) = silent_drone().await;
+        let config = config(LinkRecovery::Land);
+        let drone = flying();
+        let mut watch = Watch::new();
+        let lost_at = Duration::from_millis(config.lost_after_ms);
+
+        run_until(&mut watch, lost_at, &config, &channel, &drone).await;
+        assert_eq!(recovery_sent(&drone_socket).await, ["land"]);
+
+        run_until(&mut watch, lost_at + RECOVERY_RETRY - TICK, &config, &channel, &drone).await;
+        assert_eq!(recovery_sent(&drone_socket).await, Vec::<String>::new());
+        run_until(&mut watch, lost_at + RECOVERY_RETRY, &config, &channel, &drone).await;
+        assert_eq!(recovery_sent(&drone_socket).await, ["land"]);
+
+        drone.lock().unwrap().flying = false;
+        run_until(&mut watch, lost_at + RECOVERY_RETRY * 3, &config, &channel, &drone).await;
+        assert_eq!(recovery_sent(&drone_socket).await, Vec::<String>::new());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reconnect_never_moves_the_drone() {
+        let (drone_socket, channel) = silent_drone().await;
+        let config = config(LinkRecovery::Reconnect);
+        let drone = flying();
+        let mut watch = Watch::new();
+
+        run_until(&mut watch, Duration::from_millis(config.lost_after_ms) + RECOVERY_RETRY * 3, &config, &channel, &drone).await;
+        let commands = sent(&drone_socket).await;
+        assert!(!commands.is_empty());
+        assert!(commands.iter().all(|command| command == "command"), "{:?}", commands);
+    }
+}
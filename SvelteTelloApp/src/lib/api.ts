@@ -1,7 +1,8 @@
 // Tauri API wrapper for drone commands
 import { invoke } from '@tauri-apps/api/tauri';
-import type { TelloCommand, TelloResponse, RCControl, TelemetryData } from '$lib/types/drone';
+import type { TelloCommand, TelloResponse, RCControl, TelemetryData, DroneEndpoint, NetworkDiagnostic, RecordingInfo, WatchdogConfig, RcStreamConfig, BatteryPolicy, GeofenceConfig, FlightLogInfo, ReplayStatus, FlightExport, GeoOrigin, TrackFormat, GamepadConfig, GamepadStatus, PadEvent, Mission, MissionStatus, SwarmConfig, SwarmDroneStatus, SwarmReport, SwarmStep } from '$lib/types/drone';
 import type { FaceModel, RecognitionResult } from '$lib/types/recognition';
+import type { CapturedImage } from '$lib/stores/gallery';
 
 export class TelloAPI {
   // Connection
@@ -26,7 +27,8 @@ export class TelloAPI {
     return await invoke('emergency');
   }
 
-  static async sendRC(leftRight: number, forwardBack: number, upDown: number, yaw: number): Promise<void> {
+  // The message explains any geofence clamping
+  static async sendRC(leftRight: number, forwardBack: number, upDown: number, yaw: number): Promise<{ success: boolean; message: string }> {
     return await invoke('send_rc_control', {
       leftRight,
       forwardBack,
@@ -44,6 +46,15 @@ export class TelloAPI {
     return await invoke('flip', { direction });
   }
 
+  // Mission pads (Tello EDU); direction 0 = down, 1 = forward, 2 = both
+  static async enableMissionPads(direction: 0 | 1 | 2): Promise<{ success: boolean; message: string }> {
+    return await invoke('enable_mission_pads', { direction });
+  }
+
+  static async disableMissionPads(): Promise<{ success: boolean; message: string }> {
+    return await invoke('disable_mission_pads');
+  }
+
   // Telemetry
   static async getDroneState(): Promise<any> {
     return await invoke('get_drone_state');
@@ -70,6 +81,219 @@ export class TelloAPI {
     return await invoke('set_video_bitrate', { bitrate });
   }
 
+  // Recording
+  static async startRecording(directory?: string): Promise<{ success: boolean; message: string }> {
+    return await invoke('start_recording', { directory });
+  }
+
+  static async stopRecording(): Promise<RecordingInfo> {
+    return await invoke('stop_recording');
+  }
+
+  // Pictures
+  static async takePicture(directory?: string): Promise<CapturedImage> {
+    return await invoke('take_picture', { directory });
+  }
+
+  // Gallery
+  static async listGalleryItems(pictureDirectory?: string, videoDirectory?: string): Promise<CapturedImage[]> {
+    return await invoke('list_gallery_items', { pictureDirectory, videoDirectory });
+  }
+
+  static async deleteGalleryItem(path: string, pictureDirectory?: string, videoDirectory?: string): Promise<{ success: boolean; message: string }> {
+    return await invoke('delete_gallery_item', { path, pictureDirectory, videoDirectory });
+  }
+
+  static async getThumbnail(path: string, pictureDirectory?: string, videoDirectory?: string): Promise<string> {
+    return await invoke('get_thumbnail', { path, pictureDirectory, videoDirectory });
+  }
+
+  // Flight logs, newest first
+  static async listFlightLogs(): Promise<FlightLogInfo[]> {
+    return await invoke('list_flight_logs');
+  }
+
+  // Writes a CSV and a KML/GPX track of the flight
+  static async exportFlightLog(path: string, origin: GeoOrigin, format: TrackFormat, directory?: string): Promise<FlightExport> {
+    return await invoke('export_flight_log', { path, origin, format, directory });
+  }
+
+  // Replay (only while disconnected)
+  static async startReplay(path: string, speed?: number): Promise<ReplayStatus> {
+    return await invoke('start_replay', { path, speed });
+  }
+
+  static async pauseReplay(paused: boolean): Promise<ReplayStatus> {
+    return await invoke('pause_replay', { paused });
+  }
+
+  static async seekReplay(position: number): Promise<{ success: boolean; message: string }> {
+    return await invoke('seek_replay', { position });
+  }
+
+  static async setReplaySpeed(speed: number): Promise<ReplayStatus> {
+    return await invoke('set_replay_speed', { speed });
+  }
+
+  static async stopReplay(): Promise<{ success: boolean; message: string }> {
+    return await invoke('stop_replay');
+  }
+
+  // Geofence
+  static async getGeofence(): Promise<GeofenceConfig> {
+    return await invoke('get_geofence');
+  }
+
+  static async setGeofence(config: GeofenceConfig): Promise<{ success: boolean; message: string }> {
+    return await invoke('set_geofence', { config });
+  }
+
+  // Battery policy
+  static async getBatteryPolicy(): Promise<BatteryPolicy> {
+    return await invoke('get_battery_policy');
+  }
+
+  static async setBatteryPolicy(policy: BatteryPolicy): Promise<{ success: boolean; message: string }> {
+    return await invoke('set_battery_policy', { policy });
+  }
+
+  // Link watchdog
+  static async getWatchdogConfig(): Promise<WatchdogConfig> {
+    return await invoke('get_watchdog_config');
+  }
+
+  static async setWatchdogConfig(config: WatchdogConfig): Promise<{ success: boolean; message: string }> {
+    return await invoke('set_watchdog_config', { config });
+  }
+
+  // Missions
+  static async listMissions(): Promise<Mission[]> {
+    return await invoke('list_missions');
+  }
+
+  static async saveMission(mission: Mission): Promise<{ success: boolean; message: string }> {
+    return await invoke('save_mission', { mission });
+  }
+
+  static async deleteMission(name: string): Promise<{ success: boolean; message: string }> {
+    return await invoke('delete_mission', { name });
+  }
+
+  static async startMission(name: string, pictureDirectory?: string, videoDirectory?: string): Promise<MissionStatus> {
+    return await invoke('start_mission', { name, pictureDirectory, videoDirectory });
+  }
+
+  static async pauseMission(paused: boolean): Promise<MissionStatus> {
+    return await invoke('pause_mission', { paused });
+  }
+
+  static async abortMission(): Promise<MissionStatus> {
+    return await invoke('abort_mission');
+  }
+
+  static async getMissionStatus(): Promise<MissionStatus | null> {
+    return await invoke('get_mission_status');
+  }
+
+  // Scripts; output arrives as `script-output` events
+  static async runScript(source: string): Promise<{ success: boolean; message: string }> {
+    return await invoke('run_script', { source });
+  }
+
+  static async stopScript(): Promise<{ success: boolean; message: string }> {
+    return await invoke('stop_script');
+  }
+
+  // Swarm of Tello EDUs in station mode
+  static async getSwarmConfig(): Promise<SwarmConfig> {
+    return await invoke('get_swarm_config');
+  }
+
+  static async setSwarmConfig(config: SwarmConfig): Promise<{ success: boolean; message: string }> {
+    return await invoke('set_swarm_config', { config });
+  }
+
+  static async connectSwarm(): Promise<SwarmDroneStatus[]> {
+    return await invoke('connect_swarm');
+  }
+
+  static async disconnectSwarm(): Promise<{ success: boolean; message: string }> {
+    return await invoke('disconnect_swarm');
+  }
+
+  static async getSwarmStatus(): Promise<SwarmDroneStatus[]> {
+    return await invoke('get_swarm_status');
+  }
+
+  // Without a drone the command goes to the whole swarm
+  static async swarmCommand(command: string, drone?: string): Promise<SwarmReport> {
+    return await invoke('swarm_command', { drone: drone ?? null, command });
+  }
+
+  static async runSwarmSteps(steps: SwarmStep[]): Promise<SwarmReport> {
+    return await invoke('run_swarm_steps', { steps });
+  }
+
+  // The step in flight finishes; the run sends nothing after it
+  static async stopSwarmRun(): Promise<{ success: boolean; message: string }> {
+    return await invoke('stop_swarm_run');
+  }
+
+  static async startSwarmSimulators(count: number): Promise<SwarmConfig> {
+    return await invoke('start_swarm_simulators', { count });
+  }
+
+  static async stopSwarmSimulators(): Promise<{ success: boolean; message: string }> {
+    return await invoke('stop_swarm_simulators');
+  }
+
+  // Gamepad
+  static async getGamepadConfig(): Promise<GamepadConfig> {
+    return await invoke('get_gamepad_config');
+  }
+
+  static async setGamepadConfig(config: GamepadConfig): Promise<{ success: boolean; message: string }> {
+    return await invoke('set_gamepad_config', { config });
+  }
+
+  static async getGamepadStatus(): Promise<GamepadStatus> {
+    return await invoke('get_gamepad_status');
+  }
+
+  static async sendVirtualGamepadEvent(event: PadEvent): Promise<{ success: boolean; message: string }> {
+    return await invoke('send_virtual_gamepad_event', { event });
+  }
+
+  // RC streaming
+  static async getRcStreamConfig(): Promise<RcStreamConfig> {
+    return await invoke('get_rc_stream_config');
+  }
+
+  static async setRcStreamConfig(config: RcStreamConfig): Promise<{ success: boolean; message: string }> {
+    return await invoke('set_rc_stream_config', { config });
+  }
+
+  static async getDroneEndpoint(): Promise<DroneEndpoint> {
+    return await invoke('get_drone_endpoint');
+  }
+
+  static async setDroneEndpoint(endpoint: DroneEndpoint): Promise<{ success: boolean; message: string }> {
+    return await invoke('set_drone_endpoint', { endpoint });
+  }
+
+  static async diagnoseNetwork(): Promise<NetworkDiagnostic> {
+    return await invoke('diagnose_network');
+  }
+
+  // Simulator
+  static async startSimulator(): Promise<{ success: boolean; message: string }> {
+    return await invoke('start_simulator');
+  }
+
+  static async stopSimulator(): Promise<{ success: boolean; message: string }> {
+    return await invoke('stop_simulator');
+  }
+
   // Face Recognition
   static async listFaceModels(): Promise<FaceModel[]> {
     return await invoke('list_face_models');
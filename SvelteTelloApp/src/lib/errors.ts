@@ -0,0 +1,39 @@
+import type { TelloError, TelloErrorKind } from '$lib/types/drone';
+
+export function isTelloError(error: unknown): error is TelloError {
+  return typeof error === 'object' && error !== null && 'kind' in error && 'message' in error;
+}
+
+export function errorKind(error: unknown): TelloErrorKind | null {
+  return isTelloError(error) ? error.kind : null;
+}
+
+// Text to show for anything a command rejected with
+export function errorMessage(error: unknown): string {
+  if (isTelloError(error)) return error.message;
+  if (error instanceof Error) return error.message;
+  return String(error);
+}
+
+// What the user can do about it, for the errors where that is clear
+export function recoveryHint(error: unknown): string | null {
+  switch (errorKind(error)) {
+    case 'not_on_tello_network':
+      return 'Join the TELLO-XXXXXX WiFi network, then connect again.';
+    case 'bind_failed':
+      return 'Another Tello app is probably running. Close it and connect again.';
+    case 'video_port_busy':
+      return 'Close other apps showing the Tello video, then restart the stream.';
+    case 'no_response':
+    case 'timeout':
+      return 'Check that the drone is powered on and within WiFi range.';
+    case 'not_connected':
+      return 'Connect to the drone first.';
+    case 'battery_too_low':
+      return 'Replace or charge the battery before flying.';
+    case 'unsupported':
+      return 'Update the drone firmware to use this feature.';
+    default:
+      return null;
+  }
+}
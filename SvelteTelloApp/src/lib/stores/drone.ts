@@ -1,4 +1,5 @@
 import { writable, derived } from 'svelte/store';
+import type { PadPose, TelloState } from '$lib/types/drone';
 
 export interface DroneState {
   connected: boolean;
@@ -11,10 +12,12 @@ export interface DroneState {
   yaw: number;    // degrees
   speed: number;  // current speed setting (50 or 100)
   videoActive: boolean;
+  replaying: boolean; // driven by a recorded flight, not a drone
+  missionPad: PadPose | null; // Tello EDU: the pad in sight
 }
 
 export interface ConnectionStatus {
-  status: 'disconnected' | 'connecting' | 'connected' | 'error';
+  status: 'disconnected' | 'connecting' | 'connected' | 'degraded' | 'error';
   message: string;
   lastUpdate: number;
 }
@@ -31,6 +34,8 @@ function createDroneStore() {
     yaw: 0,
     speed: 50,
     videoActive: false,
+    replaying: false,
+    missionPad: null,
   });
 
   return {
@@ -45,6 +50,18 @@ function createDroneStore() {
       update(state => ({ ...state, pitch, roll, yaw })),
     setSpeed: (speed: number) => update(state => ({ ...state, speed })),
     setVideoActive: (videoActive: boolean) => update(state => ({ ...state, videoActive })),
+    setReplaying: (replaying: boolean) => update(state => ({ ...state, replaying })),
+    // A `drone-state` packet, live or replayed
+    applyState: (packet: TelloState) => update(state => ({
+      ...state,
+      battery: packet.bat,
+      temperature: Math.round((packet.templ + packet.temph) / 2),
+      height: packet.h,
+      pitch: packet.pitch,
+      roll: packet.roll,
+      yaw: packet.yaw,
+      missionPad: packet.pad ?? null,
+    })),
     reset: () => set({
       connected: false,
       flying: false,
@@ -56,6 +73,8 @@ function createDroneStore() {
       yaw: 0,
       speed: 50,
       videoActive: false,
+      replaying: false,
+      missionPad: null,
     }),
   };
 }
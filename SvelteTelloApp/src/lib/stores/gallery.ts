@@ -7,6 +7,10 @@ export interface CapturedImage {
   timestamp: number;
   size: number; // bytes
   thumbnail?: string; // base64 or path
+  kind?: 'image' | 'video';
+  width?: number;
+  height?: number;
+  duration?: number; // seconds, videos only
 }
 
 function createGalleryStore() {
@@ -23,6 +27,9 @@ function createGalleryStore() {
       console.log('[GalleryStore] Removing image:', id);
       update(images => images.filter(img => img.id !== id));
     },
+    setThumbnail: (id: string, thumbnail: string) => {
+      update(images => images.map(img => (img.id === id ? { ...img, thumbnail } : img)));
+    },
     clear: () => set([]),
   };
 }
@@ -1,4 +1,5 @@
 import { writable } from 'svelte/store';
+import type { GeoOrigin, TrackFormat } from '$lib/types/drone';
 
 export interface AppSettings {
   videoQuality: 'auto' | 'low' | 'medium' | 'high';
@@ -10,6 +11,11 @@ export interface AppSettings {
   freeFlyEnabled: boolean;
   autoLandOnLowBattery: boolean;
   batteryWarningLevel: number;
+  batteryCriticalLevel: number;
+  exportDirectory: string;
+  exportTrackFormat: TrackFormat;
+  exportOrigin: GeoOrigin; // takeoff point of exported tracks
+  script: string; // last flight script in the editor
 }
 
 function createSettingsStore() {
@@ -23,6 +29,11 @@ function createSettingsStore() {
     freeFlyEnabled: true,
     autoLandOnLowBattery: true,
     batteryWarningLevel: 15,
+    batteryCriticalLevel: 10,
+    exportDirectory: '~/Documents/Tello',
+    exportTrackFormat: 'kml',
+    exportOrigin: { latitude: 0, longitude: 0, altitude: 0, heading: 0 },
+    script: '// Climb to 150cm above the ground, turn and look around\ntakeoff();\nwhile tof() < 150 {\n  up(20);\n}\ncw(90);\ntake_picture();\nland();\n',
   };
 
   // Load from localStorage if available
@@ -19,40 +19,348 @@ export interface RCControl {
   yaw: number;          // -100 to 100
 }
 
+// Payload of the `drone-state` event, one per state packet (SDK field names)
 export interface TelloState {
   pitch: number;
   roll: number;
   yaw: number;
-  speedX: number;
-  speedY: number;
-  speedZ: number;
-  temperature: { low: number; high: number };
+  vgx: number;
+  vgy: number;
+  vgz: number;
+  templ: number;
+  temph: number;
   tof: number;  // Time of Flight distance in cm
-  height: number; // cm
-  battery: number; // percentage
-  barometer: number;
+  h: number;    // height in cm
+  bat: number;  // battery percentage
+  baro: number;
   time: number;
-  acceleration: { x: number; y: number; z: number };
+  agx: number;
+  agy: number;
+  agz: number;
+  pad?: PadPose; // Tello EDU with pad detection on and a pad in sight
 }
 
-export interface TelemetryData {
-  battery: number;
-  temperature: number;
-  height: number;
+// A detected mission pad and the drone's pose relative to it (cm, degrees)
+export interface PadPose {
+  id: number;
+  x: number;
+  y: number;
+  z: number;
   pitch: number;
   roll: number;
   yaw: number;
+}
+
+// Network endpoint of the drone, persisted by the backend
+export interface DroneEndpoint {
+  ip: string;
+  command_port: number;        // drone's SDK command port (8889)
+  local_command_port: number;  // our command socket, 0 = any free port
+  state_port: number;          // local port for state packets (8890)
+  video_port: number;          // local port for H.264 video (11111)
+}
+
+// Which local interface routes to the drone, from the backend's network check
+export interface NetworkDiagnostic {
+  drone_ip: string;
+  interface: string | null;
+  local_ip: string | null;
+  same_subnet: boolean;
+  reachable: boolean;
+  message: string;
+}
+
+// Each reading is null when its query failed; `errors` says why
+export interface TelemetryData {
+  battery: number | null;
+  temperature: number | null;
+  height: number | null;
+  pitch: number | null;
+  roll: number | null;
+  yaw: number | null;
   tof: number | null;
+  errors: string[];
+}
+
+// Payload of the `video-frame` event: one complete H.264 access unit
+export interface VideoFrame {
+  data: string;       // base64 Annex B bytes
+  keyframe: boolean;  // IDR frame, carries SPS/PPS
+  sequence: number;
+  timestamp: number;  // microseconds since the first frame
+}
+
+// Returned by stop_recording
+export interface RecordingInfo {
+  path: string;
+  duration: number; // seconds
+  size: number;     // bytes
+  frames: number;
+}
+
+// Payload of the link-degraded / link-lost / link-restored events
+export interface LinkEvent {
+  silence_ms: number;
+  recovery: 'reconnect' | 'hover' | 'land' | null;
+  message: string;
+}
+
+export interface GeofenceConfig {
+  enabled: boolean;
+  max_height: number;   // cm
+  min_height: number;   // cm
+  max_distance: number; // cm from the takeoff point
+  max_yaw_rate: number; // 0-100, rc units
+}
+
+// Payload of the battery-warning / battery-critical events
+export interface BatteryEvent {
+  battery: number;
+  level: number;
+  action: 'land' | null;
+  message: string;
+}
+
+export interface BatteryPolicy {
+  warning_levels: number[];
+  critical_level: number;
+  auto_land: boolean;
+}
+
+export interface WatchdogConfig {
+  degraded_after_ms: number;
+  lost_after_ms: number;
+  keepalive_interval_ms: number;
+  recovery: 'reconnect' | 'hover' | 'land';
+}
+
+// The backend repeats the current rc setpoint at rate_hz and zeroes it when
+// it is not refreshed within deadman_ms
+export interface RcStreamConfig {
+  rate_hz: number;     // 20 - 50
+  deadman_ms: number;  // 200 - 5000
 }
 
 export type VideoQuality = 'auto' | 'low' | 'medium' | 'high';
 
+// Every backend command rejects with one of these
+export type TelloErrorKind =
+  | 'bind_failed'
+  | 'not_connected'
+  | 'timeout'
+  | 'no_response'
+  | 'drone_error'
+  | 'unsupported'
+  | 'unexpected_reply'
+  | 'not_on_tello_network'
+  | 'video_port_busy'
+  | 'battery_too_low'
+  | 'invalid_argument'
+  | 'not_found'
+  | 'invalid_state'
+  | 'network'
+  | 'media'
+  | 'io'
+  | 'internal';
+
+export interface TelloError {
+  kind: TelloErrorKind;
+  message: string;
+}
+
+// One line of a flight log; also the payload of the replayed `flight-event`
 export interface FlightLog {
   timestamp: number;
-  event: string;
+  event: 'command' | 'reply' | 'error' | 'state' | 'mode';
   data?: any;
 }
 
+export type TrackFormat = 'kml' | 'gpx';
+
+// Takeoff point and heading that anchor an exported track
+export interface GeoOrigin {
+  latitude: number;
+  longitude: number;
+  altitude: number; // m above sea level
+  heading: number;  // degrees clockwise from north
+}
+
+export interface FlightStats {
+  samples: number;
+  duration: number;     // seconds
+  max_height: number;   // cm
+  battery_used: number; // percent
+  distance: number;     // cm, 3D
+}
+
+// Returned by export_flight_log: paths of the written files
+export interface FlightExport {
+  csv: string;
+  track: string;
+  stats: FlightStats;
+}
+
+// Payload of the `replay-status` event
+export interface ReplayStatus {
+  name: string;
+  position: number; // seconds
+  duration: number; // seconds
+  paused: boolean;
+  speed: number;
+  finished: boolean;
+}
+
+// One recorded flight (list_flight_logs)
+export interface FlightLogInfo {
+  name: string;
+  path: string;
+  started: number; // ms since the Unix epoch
+  duration: number; // seconds
+  size: number;
+  entries: number;
+  ended_with: string | null;
+}
+
+// One step of a mission; distances in cm, speeds in cm/s
+export type MissionStep =
+  | { type: 'takeoff' }
+  | { type: 'move'; direction: 'up' | 'down' | 'left' | 'right' | 'forward' | 'back'; distance: number }
+  // x forward, y left, z up; with a pad (1-8, -1 any, -2 nearest) relative to that pad
+  | { type: 'go'; x: number; y: number; z: number; speed: number; pad?: number }
+  | { type: 'curve'; x1: number; y1: number; z1: number; x2: number; y2: number; z2: number; speed: number; pad?: number }
+  | { type: 'jump'; x: number; y: number; z: number; speed: number; yaw: number; pad1: number; pad2: number }
+  | { type: 'pads_on'; direction: 0 | 1 | 2 } // 0 = down, 1 = forward, 2 = both
+  | { type: 'pads_off' }
+  | { type: 'rotate'; rotation: 'clockwise' | 'counter_clockwise'; degrees: number }
+  | { type: 'hover'; seconds: number }
+  | { type: 'take_picture' }
+  | { type: 'start_recording' }
+  | { type: 'stop_recording' }
+  | { type: 'land' };
+
+export interface Mission {
+  name: string;
+  steps: MissionStep[];
+}
+
+// Payload of the `mission-progress` event
+export interface MissionStatus {
+  name: string;
+  step: number; // index of the current step, equals total once completed
+  total: number;
+  state: 'running' | 'paused' | 'completed' | 'aborted' | 'failed';
+  message: string;
+}
+
+// Payload of the `script-output` event
+export interface ScriptOutput {
+  kind: 'print' | 'debug' | 'error' | 'finished';
+  message: string;
+}
+
+// One Tello EDU of the swarm, reached on the router in station mode
+export interface SwarmMember {
+  ip: string;
+  command_port: number; // 8889 on a real drone
+  state_port: number;   // local port it pushes state to; drones sharing an IP need their own
+}
+
+export interface SwarmConfig {
+  members: SwarmMember[];
+}
+
+export interface SwarmDroneStatus {
+  id: string;          // IP, or IP:port off the SDK's command port
+  serial: string | null;
+  online: boolean;     // state packets are arriving
+  flying: boolean;
+  battery: number;
+  height: number;
+  yaw: number;
+  mission_pad: PadPose | null;
+}
+
+// `drone` is an id or serial; without one the command goes to every drone
+// that has no command of its own in the step
+export interface SwarmCommand {
+  drone?: string;
+  command: string;
+}
+
+// Every drone gets its command at once; the step ends when all have answered
+export interface SwarmStep {
+  commands: SwarmCommand[];
+}
+
+export interface SwarmReply {
+  drone: string;
+  command: string;
+  success: boolean;
+  message: string;
+}
+
+export interface SwarmReport {
+  completed: number; // steps every drone acknowledged
+  total: number;
+  replies: SwarmReply[]; // of the last step run
+  stopped: boolean;      // stop_swarm_run ended it before its last step
+}
+
+export type PadAxis = 'left_stick_x' | 'left_stick_y' | 'right_stick_x' | 'right_stick_y' | 'left_z' | 'right_z';
+
+export type PadButton =
+  | 'south' | 'east' | 'north' | 'west'
+  | 'left_trigger' | 'left_trigger2' | 'right_trigger' | 'right_trigger2'
+  | 'select' | 'start' | 'mode' | 'left_thumb' | 'right_thumb'
+  | 'd_pad_up' | 'd_pad_down' | 'd_pad_left' | 'd_pad_right';
+
+export type GamepadAction = 'takeoff' | 'land' | 'emergency' | 'flip_forward' | 'flip_back' | 'flip_left' | 'flip_right';
+
+export interface AxisBinding {
+  axis: PadAxis;
+  invert: boolean;
+}
+
+export interface ButtonBinding {
+  button: PadButton;
+  action: GamepadAction;
+}
+
+// Stick and button mapping, persisted by the backend (gamepad.json)
+export interface GamepadConfig {
+  enabled: boolean;
+  left_right: AxisBinding;
+  forward_back: AxisBinding;
+  up_down: AxisBinding;
+  yaw: AxisBinding;
+  deadzone: number;  // 0 - 0.5 of full deflection
+  expo: number;      // 0 (linear) - 1 (cubic)
+  max_speed: number; // rc value at full deflection, 10 - 100
+  rate_hz: number;   // 10 - 50
+  buttons: ButtonBinding[];
+}
+
+// Input for send_virtual_gamepad_event; axis values are -1.0 - 1.0
+export type PadEvent =
+  | { type: 'connected'; name: string }
+  | { type: 'disconnected'; name: string }
+  | { type: 'axis'; axis: PadAxis; value: number }
+  | { type: 'pressed'; button: PadButton }
+  | { type: 'released'; button: PadButton };
+
+// Payload of the `gamepad-status` event
+export interface GamepadStatus {
+  connected: boolean;
+  name: string | null;
+  error: string | null;
+}
+
+// Payload of the `gamepad-action` event
+export interface GamepadActionEvent {
+  action: GamepadAction;
+  result: { success: boolean; message: string };
+}
+
 export type DroneMode = 'manual' | 'free_fly' | 'face_follow' | 'tricks';
 
 export interface FreeFlyDebug {